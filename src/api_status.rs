@@ -0,0 +1,272 @@
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Wait used for a 429 that doesn't say how long to back off
+const DEFAULT_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+// Quota reported by the `X-RateLimit-*` headers for one resource (core, search, ...)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimit {
+    pub limit: u32,
+    pub remaining: u32,
+    // unix timestamp (seconds) at which the quota refills
+    pub reset: u64,
+}
+
+impl RateLimit {
+    pub fn resets_in(&self) -> Duration {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Duration::from_secs(self.reset.saturating_sub(now))
+    }
+}
+
+// A request sleeping before it is retried
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    pub until: SystemTime,
+    pub reason: String,
+}
+
+impl Backoff {
+    pub fn remaining(&self) -> Duration {
+        self.until
+            .duration_since(SystemTime::now())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StatusSnapshot {
+    pub rate_limits: HashMap<String, RateLimit>,
+    pub backoff: Option<Backoff>,
+    pub offline: bool,
+}
+
+// API health shared between the request code, which updates it on every
+// response, and the UI, which reads a snapshot every frame
+#[derive(Debug, Clone, Default)]
+pub struct ApiStatus {
+    inner: Arc<Mutex<StatusSnapshot>>,
+}
+
+impl ApiStatus {
+    pub fn snapshot(&self) -> StatusSnapshot {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, StatusSnapshot> {
+        // the data stays consistent even if a holder panicked
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set_offline(&self, offline: bool) {
+        self.lock().offline = offline;
+    }
+
+    pub fn record_headers(&self, headers: &HeaderMap) {
+        if let Some((resource, rate_limit)) = parse_rate_limit(headers) {
+            self.lock().rate_limits.insert(resource, rate_limit);
+        }
+    }
+
+    // publish a backoff until the returned guard is dropped, which also covers
+    // the request being cancelled mid-wait
+    pub fn begin_backoff(&self, wait: Duration, reason: String) -> BackoffGuard {
+        let until = SystemTime::now() + wait;
+        self.lock().backoff = Some(Backoff { until, reason });
+        BackoffGuard {
+            status: self.clone(),
+            until,
+        }
+    }
+}
+
+pub struct BackoffGuard {
+    status: ApiStatus,
+    until: SystemTime,
+}
+
+impl Drop for BackoffGuard {
+    fn drop(&mut self) {
+        let mut snapshot = self.status.lock();
+        // another request may have started a longer wait in the meantime
+        if snapshot
+            .backoff
+            .as_ref()
+            .is_some_and(|b| b.until == self.until)
+        {
+            snapshot.backoff = None;
+        }
+    }
+}
+
+fn header_value<T: std::str::FromStr>(headers: &HeaderMap, name: &str) -> Option<T> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+pub fn parse_rate_limit(headers: &HeaderMap) -> Option<(String, RateLimit)> {
+    let rate_limit = RateLimit {
+        limit: header_value(headers, "x-ratelimit-limit")?,
+        remaining: header_value(headers, "x-ratelimit-remaining")?,
+        reset: header_value(headers, "x-ratelimit-reset")?,
+    };
+    let resource = header_value(headers, "x-ratelimit-resource").unwrap_or(String::from("core"));
+    Some((resource, rate_limit))
+}
+
+// How long to wait before retrying a rate limited response, or None when the
+// response isn't a rate limit at all (e.g. a 403 for missing permissions)
+pub fn rate_limit_wait(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+
+    // secondary rate limits say how long to back off
+    if let Some(seconds) = header_value::<u64>(headers, RETRY_AFTER.as_str()) {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    // primary rate limit: wait for the quota to refill
+    if let Some((_, rate_limit)) = parse_rate_limit(headers)
+        && rate_limit.remaining == 0
+    {
+        return Some(rate_limit.resets_in() + Duration::from_secs(1));
+    }
+
+    (status == StatusCode::TOO_MANY_REQUESTS).then_some(DEFAULT_RATE_LIMIT_WAIT)
+}
+
+// compact `1h02m` / `4m05s` / `12s` rendering for countdowns
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn in_secs(seconds: u64) -> String {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        (now.as_secs() + seconds).to_string()
+    }
+
+    #[test]
+    fn rate_limit_headers_are_read_per_resource() {
+        let parsed = parse_rate_limit(&headers(&[
+            ("x-ratelimit-limit", "30"),
+            ("x-ratelimit-remaining", "29"),
+            ("x-ratelimit-reset", "1700000000"),
+            ("x-ratelimit-resource", "search"),
+        ]));
+        let expected = RateLimit {
+            limit: 30,
+            remaining: 29,
+            reset: 1700000000,
+        };
+        assert_eq!(parsed, Some((String::from("search"), expected)));
+
+        let core = headers(&[
+            ("x-ratelimit-limit", "5000"),
+            ("x-ratelimit-remaining", "4999"),
+            ("x-ratelimit-reset", "1700000000"),
+        ]);
+        assert_eq!(parse_rate_limit(&core).unwrap().0, "core");
+        assert_eq!(
+            parse_rate_limit(&headers(&[("x-ratelimit-limit", "60")])),
+            None
+        );
+    }
+
+    #[test]
+    fn retry_after_says_how_long_to_wait() {
+        let retry_after = headers(&[("retry-after", "30")]);
+        assert_eq!(
+            rate_limit_wait(StatusCode::FORBIDDEN, &retry_after),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            rate_limit_wait(StatusCode::TOO_MANY_REQUESTS, &retry_after),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    fn an_exhausted_quota_waits_for_the_reset() {
+        let reset = in_secs(120);
+        let exhausted = headers(&[
+            ("x-ratelimit-limit", "60"),
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", &reset),
+        ]);
+        let wait = rate_limit_wait(StatusCode::FORBIDDEN, &exhausted).unwrap();
+        assert!(wait > Duration::from_secs(115) && wait <= Duration::from_secs(121));
+    }
+
+    #[test]
+    fn other_refusals_are_not_rate_limits() {
+        let left = headers(&[
+            ("x-ratelimit-limit", "60"),
+            ("x-ratelimit-remaining", "12"),
+            ("x-ratelimit-reset", "1700000000"),
+        ]);
+        assert_eq!(rate_limit_wait(StatusCode::FORBIDDEN, &left), None);
+        assert_eq!(rate_limit_wait(StatusCode::NOT_FOUND, &left), None);
+        assert_eq!(
+            rate_limit_wait(
+                StatusCode::SERVICE_UNAVAILABLE,
+                &headers(&[("retry-after", "5")])
+            ),
+            None
+        );
+        // a bare 429 still backs off
+        assert_eq!(
+            rate_limit_wait(StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new()),
+            Some(DEFAULT_RATE_LIMIT_WAIT)
+        );
+    }
+
+    #[test]
+    fn a_backoff_ends_with_its_guard() {
+        let status = ApiStatus::default();
+        let first = status.begin_backoff(Duration::from_secs(5), String::from("first"));
+        let second = status.begin_backoff(Duration::from_secs(60), String::from("second"));
+        // the earlier, shorter wait ending doesn't hide the later one
+        drop(first);
+        assert_eq!(status.snapshot().backoff.unwrap().reason, "second");
+        drop(second);
+        assert!(status.snapshot().backoff.is_none());
+    }
+
+    #[test]
+    fn recorded_headers_show_up_in_the_snapshot() {
+        let status = ApiStatus::default();
+        status.record_headers(&headers(&[
+            ("x-ratelimit-limit", "5000"),
+            ("x-ratelimit-remaining", "4321"),
+            ("x-ratelimit-reset", "1700000000"),
+        ]));
+        assert_eq!(status.snapshot().rate_limits["core"].remaining, 4321);
+    }
+}
@@ -1,13 +1,42 @@
-use crate::github::{fetch_repos, get_repo_issues, get_repo_prs, search_repos};
+use crate::api_status::StatusSnapshot;
+use crate::backend::{DetailQuery, GitHubBackend};
+use crate::code::{CodeFile, CodeTree, EntryKind, FileBody, RefKind, RefPicker, permalink};
+use crate::diff::DiffFile;
+use crate::editor::TextEditor;
+use crate::error::ApiError;
+use crate::filter::{CommitFilter, IssueFilter};
+use crate::markdown;
+use crate::picker::Picker;
+use crate::pull_action::{PullAction, PullActionField, PullActionKind};
+use crate::triage::{IssueChange, Triage, TriageAction};
+use ratatui::text::Text;
 use ratatui::widgets::TableState;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tokio::task::AbortHandle;
 
-use crate::models::{Issue, PR, Repo};
+use crate::models::{
+    Comment, Commit, Issue, IssueTemplate, Label, Milestone, NewIssue, NewReviewComment, PR, Page,
+    PullDetail, Repo, RepoCounts, RepoDetail, RepoTree, ReviewThread, User, oldest,
+};
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppMode {
     RepoList,
     RepoDetail,
     Search,
     IssuePopUp,
+    PRPopUp,
+    FilterPopUp,
+    DiffView,
+    CommentEditor,
+    IssueForm,
+    TriagePopUp,
+    PullActionPopUp,
+    RefPicker,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -15,7 +44,716 @@ pub enum LoadingState {
     Idle,
     Loading,
     Success,
-    Error(String),
+    Error(ApiError),
+}
+
+// Results sent back to the main loop by background fetch tasks
+#[derive(Debug)]
+pub enum Message {
+    ReposLoaded(Result<Page<Repo>, ApiError>),
+    // `more` is set for every page after the first one
+    IssuesLoaded {
+        repo: String,
+        more: bool,
+        result: Result<Page<Issue>, ApiError>,
+    },
+    PRsLoaded {
+        repo: String,
+        more: bool,
+        result: Result<Page<PR>, ApiError>,
+    },
+    CommentsLoaded {
+        repo: String,
+        number: u32,
+        more: bool,
+        result: Result<Page<Comment>, ApiError>,
+    },
+    IssueFormLoaded {
+        repo: String,
+        result: Result<Box<IssueFormOptions>, ApiError>,
+    },
+    IssueCreated {
+        repo: String,
+        result: Result<Box<Issue>, ApiError>,
+    },
+    TriageOptionsLoaded {
+        repo: String,
+        number: u32,
+        result: Result<Box<IssueFormOptions>, ApiError>,
+    },
+    // what `change` touched is put back as in `previous` if GitHub refuses it;
+    // locking returns no issue
+    IssueChanged {
+        repo: String,
+        number: u32,
+        change: IssueChange,
+        previous: Box<Issue>,
+        description: String,
+        result: Result<Option<Box<Issue>>, ApiError>,
+    },
+    CommentPosted {
+        repo: String,
+        number: u32,
+        result: Result<Comment, ApiError>,
+    },
+    PullDetailLoaded {
+        repo: String,
+        number: u32,
+        result: Result<Box<PullDetail>, ApiError>,
+    },
+    // a confirmed PR action went through; the notice says how it went, which
+    // may be only partly well (merged, but the branch couldn't be deleted)
+    PullActionDone {
+        repo: String,
+        number: u32,
+        result: Result<Notice, ApiError>,
+    },
+    // line comment threads of the PR in the diff view
+    ReviewThreadsLoaded {
+        repo: String,
+        number: u32,
+        result: Result<Vec<ReviewThread>, ApiError>,
+    },
+    // the thread was flipped on screen already and is flipped back on failure
+    ThreadResolved {
+        repo: String,
+        number: u32,
+        thread_id: String,
+        result: Result<(), ApiError>,
+    },
+    // changed files, already parsed and highlighted
+    PullFilesLoaded {
+        repo: String,
+        number: u32,
+        result: Result<Vec<DiffFile>, ApiError>,
+    },
+    // the code tab's tree of `git_ref`
+    CodeTreeLoaded {
+        repo: String,
+        git_ref: String,
+        result: Result<Box<RepoTree>, ApiError>,
+    },
+    // a file of the code tab, already decoded and highlighted
+    CodeFileLoaded {
+        repo: String,
+        commit: String,
+        path: String,
+        result: Result<Box<CodeFile>, ApiError>,
+    },
+    RefsLoaded {
+        repo: String,
+        result: Result<Vec<(String, RefKind)>, ApiError>,
+    },
+    // a page of the commits tab; `more` is set for every page after the first one
+    CommitsLoaded {
+        repo: String,
+        pr: Option<u32>,
+        filter: Box<CommitFilter>,
+        more: bool,
+        result: Result<Page<Commit>, ApiError>,
+    },
+    // a commit and its changed files, already parsed and highlighted
+    CommitLoaded {
+        repo: String,
+        sha: String,
+        result: Result<(Box<Commit>, Vec<DiffFile>), ApiError>,
+    },
+    // rendered README of the selected repo, None if it has none
+    ReadmeLoaded {
+        repo: String,
+        result: Result<Option<Text<'static>>, ApiError>,
+    },
+    // any combination of the above for one repo, fetched together
+    DetailLoaded {
+        repo: String,
+        query: Box<DetailQuery>,
+        result: Result<Box<RepoDetail>, ApiError>,
+    },
+}
+
+// Code tab of a repo: the tree of one ref, and the file open beside it
+pub struct CodeView {
+    pub repo: String,
+    // branch, tag or commit as chosen, and the commit it resolved to
+    pub git_ref: String,
+    pub commit: Option<String>,
+    pub tree: CodeTree,
+    pub truncated: bool,
+    pub state: LoadingState,
+    pub selected: usize,
+    pub path: Option<String>,
+    pub file: Option<CodeFile>,
+    pub file_state: LoadingState,
+    // keys move through the open file rather than the tree
+    pub viewing: bool,
+    // line of the file the cursor is on, and the first one shown
+    pub cursor: usize,
+    pub scroll: usize,
+}
+
+// Commits tab of a repo: the history of a branch, or the commits of a PR
+pub struct CommitLog {
+    pub repo: String,
+    // listing the commits of this PR rather than a history
+    pub pr: Option<u32>,
+    pub filter: CommitFilter,
+    pub commits: Vec<Commit>,
+    pub state: LoadingState,
+    pub pages: Pagination,
+    pub table_state: TableState,
+    // where Esc goes back to from a PR's commits
+    return_mode: Option<AppMode>,
+}
+
+impl CommitLog {
+    fn new(repo: String, pr: Option<u32>) -> Self {
+        let mut table_state = TableState::default();
+        table_state.select(Some(0));
+        Self {
+            repo,
+            pr,
+            filter: CommitFilter::default(),
+            commits: Vec::new(),
+            state: LoadingState::Idle,
+            pages: Pagination::default(),
+            table_state,
+            return_mode: None,
+        }
+    }
+}
+
+// Where a lazily loaded table stands in its listing
+#[derive(Debug, Clone, Default)]
+pub struct Pagination {
+    pub next: Option<String>,
+    pub loading_more: bool,
+    pub error: Option<ApiError>,
+    // oldest cached page shown, when browsing offline
+    pub stale_since: Option<u64>,
+}
+
+impl Pagination {
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    fn has_more(&self) -> bool {
+        self.next.is_some() || self.loading_more
+    }
+
+    // apply a page result, returning its items if there are any to add
+    fn receive<T>(&mut self, result: Result<Page<T>, ApiError>) -> Result<Vec<T>, ApiError> {
+        self.loading_more = false;
+        match result {
+            Ok(page) => {
+                self.next = page.next;
+                self.error = None;
+                self.stale_since = oldest(self.stale_since, page.stale_since);
+                Ok(page.items)
+            }
+            Err(e) => {
+                self.error = Some(e.clone());
+                Err(e)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterField {
+    State,
+    Path,
+    Labels,
+    Assignee,
+    Author,
+    Milestone,
+    Sort,
+    Direction,
+}
+
+impl FilterField {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FilterField::State => "State",
+            FilterField::Path => "Path",
+            FilterField::Labels => "Labels",
+            FilterField::Assignee => "Assignee",
+            FilterField::Author => "Author",
+            FilterField::Milestone => "Milestone",
+            FilterField::Sort => "Sort by",
+            FilterField::Direction => "Direction",
+        }
+    }
+}
+
+// Filter being edited for the issue, PR or commit table; text fields are kept
+// as typed and only parsed when the filter is applied
+#[derive(Debug, Clone)]
+pub struct FilterForm {
+    pub prs: bool,
+    pub commits: bool,
+    pub draft: IssueFilter,
+    pub selected: usize,
+    pub path: String,
+    pub labels: String,
+    pub assignee: String,
+    pub author: String,
+    pub milestone: String,
+}
+
+impl FilterForm {
+    fn new(filter: &IssueFilter, prs: bool) -> Self {
+        Self {
+            prs,
+            commits: false,
+            draft: filter.clone(),
+            selected: 0,
+            path: String::new(),
+            labels: filter.labels.join(","),
+            assignee: filter.assignee.clone().unwrap_or_default(),
+            author: filter.author.clone().unwrap_or_default(),
+            milestone: filter.milestone.clone().unwrap_or_default(),
+        }
+    }
+
+    fn for_commits(filter: &CommitFilter) -> Self {
+        Self {
+            commits: true,
+            path: filter.path.clone().unwrap_or_default(),
+            author: filter.author.clone().unwrap_or_default(),
+            ..Self::new(&IssueFilter::default(), false)
+        }
+    }
+
+    pub fn fields(&self) -> &'static [FilterField] {
+        if self.commits {
+            &[FilterField::Path, FilterField::Author]
+        } else {
+            &[
+                FilterField::State,
+                FilterField::Labels,
+                FilterField::Assignee,
+                FilterField::Author,
+                FilterField::Milestone,
+                FilterField::Sort,
+                FilterField::Direction,
+            ]
+        }
+    }
+
+    pub fn field(&self) -> FilterField {
+        self.fields()[self.selected]
+    }
+
+    pub fn value(&self, field: FilterField) -> String {
+        match field {
+            FilterField::State => self.draft.state.as_str().to_string(),
+            FilterField::Path => self.path.clone(),
+            FilterField::Labels => self.labels.clone(),
+            FilterField::Assignee => self.assignee.clone(),
+            FilterField::Author => self.author.clone(),
+            FilterField::Milestone => self.milestone.clone(),
+            FilterField::Sort => self.draft.sort.as_str().to_string(),
+            FilterField::Direction => self.draft.direction.as_str().to_string(),
+        }
+    }
+
+    fn editing_text(&self) -> bool {
+        matches!(
+            self.field(),
+            FilterField::Path
+                | FilterField::Labels
+                | FilterField::Assignee
+                | FilterField::Author
+                | FilterField::Milestone
+        )
+    }
+
+    fn text_mut(&mut self) -> Option<&mut String> {
+        match self.field() {
+            FilterField::Path => Some(&mut self.path),
+            FilterField::Labels => Some(&mut self.labels),
+            FilterField::Assignee => Some(&mut self.assignee),
+            FilterField::Author => Some(&mut self.author),
+            FilterField::Milestone => Some(&mut self.milestone),
+            _ => None,
+        }
+    }
+
+    pub fn to_filter(&self) -> IssueFilter {
+        let text = |value: &str| Some(value.trim().to_string()).filter(|v| !v.is_empty());
+        let milestone = text(&self.milestone);
+        // the title looked up for the old milestone doesn't name the new one
+        let milestone_title = self
+            .draft
+            .milestone_title
+            .clone()
+            .filter(|_| milestone == self.draft.milestone);
+        IssueFilter {
+            labels: self
+                .labels
+                .split(',')
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(str::to_string)
+                .collect(),
+            assignee: text(&self.assignee),
+            author: text(&self.author),
+            milestone,
+            milestone_title,
+            ..self.draft.clone()
+        }
+    }
+
+    // `filter` with the path and author as edited; the ref stays
+    fn to_commit_filter(&self, filter: &CommitFilter) -> CommitFilter {
+        let text = |value: &str| Some(value.trim().to_string()).filter(|v| !v.is_empty());
+        CommitFilter {
+            path: text(self.path.trim().trim_matches('/')),
+            author: text(&self.author),
+            ..filter.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DiffLayout {
+    // side by side when the terminal is wide enough
+    #[default]
+    Auto,
+    Unified,
+    SideBySide,
+}
+
+impl DiffLayout {
+    fn cycle(&self) -> Self {
+        match self {
+            DiffLayout::Auto => DiffLayout::Unified,
+            DiffLayout::Unified => DiffLayout::SideBySide,
+            DiffLayout::SideBySide => DiffLayout::Auto,
+        }
+    }
+}
+
+// The diff of one PR, or of one commit, browsed file by file
+#[derive(Debug)]
+pub struct DiffView {
+    pub repo: String,
+    // the PR; for a commit, the PR it was listed in, or 0
+    pub number: u32,
+    // set for the diff of a single commit, which has no review threads and
+    // takes no line comments; the commit itself arrives with its files
+    pub sha: Option<String>,
+    pub commit: Option<Commit>,
+    pub files: Vec<DiffFile>,
+    pub state: LoadingState,
+    pub selected: usize,
+    // first row shown of the selected file
+    pub scroll: usize,
+    // row line comments go on, and the other end of the range being selected
+    pub cursor: usize,
+    pub range_start: Option<usize>,
+    // in side-by-side rows, comment on the old version rather than the new
+    pub old_side: bool,
+    pub threads: Vec<ReviewThread>,
+    pub layout: DiffLayout,
+    // what `layout` came down to at the last render, set by the UI
+    pub side_by_side: bool,
+    // where Esc goes back to
+    return_mode: AppMode,
+}
+
+impl DiffView {
+    fn new(
+        repo: String,
+        number: u32,
+        sha: Option<String>,
+        layout: DiffLayout,
+        return_mode: AppMode,
+    ) -> Self {
+        Self {
+            repo,
+            number,
+            sha,
+            commit: None,
+            files: Vec::new(),
+            state: LoadingState::Loading,
+            selected: 0,
+            scroll: 0,
+            cursor: 0,
+            range_start: None,
+            old_side: false,
+            threads: Vec::new(),
+            layout,
+            side_by_side: false,
+            return_mode,
+        }
+    }
+
+    // the diff of a PR, as opposed to a commit
+    fn is_pull(&self, repo: &str, number: u32) -> bool {
+        self.sha.is_none() && self.repo == repo && self.number == number
+    }
+
+    pub fn file(&self) -> Option<&DiffFile> {
+        self.files.get(self.selected)
+    }
+
+    fn row_count(&self) -> usize {
+        self.file()
+            .map_or(0, |file| file.row_count(self.side_by_side))
+    }
+
+    fn select_file(&mut self, selected: usize) {
+        self.selected = selected;
+        self.scroll = 0;
+        self.cursor = 0;
+        self.range_start = None;
+    }
+
+    // the line comment the cursor, or the selected range, would get
+    fn line_comment(&self) -> Result<NewReviewComment, &'static str> {
+        let file = self.file().ok_or("No file selected")?;
+        let (side, line) = file
+            .anchor(self.cursor, self.side_by_side, self.old_side)
+            .ok_or("Pick a line to comment on")?;
+        let mut comment = NewReviewComment {
+            path: file.file.filename.clone(),
+            body: String::new(),
+            line,
+            side: side.to_string(),
+            start_line: None,
+            start_side: None,
+        };
+        if let Some(start) = self.range_start.filter(|&start| start != self.cursor) {
+            if !file.same_hunk(start, self.cursor, self.side_by_side) {
+                return Err("A comment range can't span hunks");
+            }
+            let (first, last) = (start.min(self.cursor), start.max(self.cursor));
+            let (start_side, start_line) = file
+                .anchor(first, self.side_by_side, self.old_side)
+                .ok_or("A comment range can't start on a hunk header")?;
+            let (side, line) = file
+                .anchor(last, self.side_by_side, self.old_side)
+                .ok_or("Pick a line to comment on")?;
+            comment.line = line;
+            comment.side = side.to_string();
+            comment.start_line = Some(start_line);
+            comment.start_side = Some(start_side.to_string());
+        }
+        Ok(comment)
+    }
+}
+
+// Outcome of the last change made, shown in the footer
+#[derive(Debug, Clone)]
+pub struct Notice {
+    pub text: String,
+    pub error: bool,
+    pub since: Instant,
+}
+
+// A comment being written for the issue or PR of the open popup
+#[derive(Debug)]
+pub struct CommentDraft {
+    pub repo: String,
+    pub number: u32,
+    pub editor: TextEditor,
+    pub preview: bool,
+    pub posting: bool,
+    pub error: Option<String>,
+    // where a line comment goes; it is queued for a review rather than posted
+    pub line: Option<NewReviewComment>,
+    return_mode: AppMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IssueField {
+    Template,
+    Title,
+    Body,
+    Labels,
+    Assignees,
+    Milestone,
+}
+
+// Everything a new issue can be filed with in a repo
+#[derive(Debug, Default)]
+pub struct IssueFormOptions {
+    pub labels: Vec<Label>,
+    pub assignees: Vec<User>,
+    pub milestones: Vec<Milestone>,
+    pub templates: Vec<IssueTemplate>,
+}
+
+// Issue being written in the new issue form
+#[derive(Debug)]
+pub struct IssueForm {
+    pub repo: String,
+    pub field: IssueField,
+    pub templates: Vec<IssueTemplate>,
+    // index into `templates`, None for a blank issue
+    pub template: Option<usize>,
+    pub title: String,
+    pub body: TextEditor,
+    pub labels: Picker,
+    pub assignees: Picker,
+    pub milestone: Picker,
+    milestones: Vec<Milestone>,
+    // labels, assignees, milestones and templates
+    pub options_state: LoadingState,
+    pub submitting: bool,
+    pub error: Option<String>,
+}
+
+impl Notice {
+    pub fn ok(text: String) -> Self {
+        Self {
+            text,
+            error: false,
+            since: Instant::now(),
+        }
+    }
+
+    pub fn error(text: String) -> Self {
+        Self {
+            text,
+            error: true,
+            since: Instant::now(),
+        }
+    }
+}
+
+impl IssueForm {
+    fn new(repo: String) -> Self {
+        Self {
+            repo,
+            field: IssueField::Title,
+            templates: Vec::new(),
+            template: None,
+            title: String::new(),
+            body: TextEditor::default(),
+            labels: Picker::default(),
+            assignees: Picker::default(),
+            milestone: Picker::default(),
+            milestones: Vec::new(),
+            options_state: LoadingState::Loading,
+            submitting: false,
+            error: None,
+        }
+    }
+
+    fn set_options(&mut self, options: IssueFormOptions) {
+        self.labels = Picker::new(
+            options
+                .labels
+                .into_iter()
+                .map(|label| (label.name, label.color))
+                .collect(),
+            true,
+        );
+        self.assignees = Picker::new(
+            options
+                .assignees
+                .into_iter()
+                .map(|user| (user.login, None))
+                .collect(),
+            true,
+        );
+        self.milestone = Picker::new(
+            options
+                .milestones
+                .iter()
+                .map(|milestone| (milestone.title.clone(), None))
+                .collect(),
+            false,
+        );
+        self.milestones = options.milestones;
+        self.templates = options.templates;
+    }
+
+    // the template field only shows up when the repo has templates
+    pub fn fields(&self) -> &'static [IssueField] {
+        if self.templates.is_empty() {
+            &[
+                IssueField::Title,
+                IssueField::Body,
+                IssueField::Labels,
+                IssueField::Assignees,
+                IssueField::Milestone,
+            ]
+        } else {
+            &[
+                IssueField::Template,
+                IssueField::Title,
+                IssueField::Body,
+                IssueField::Labels,
+                IssueField::Assignees,
+                IssueField::Milestone,
+            ]
+        }
+    }
+
+    fn step_field(&mut self, forward: bool) {
+        let fields = self.fields();
+        let i = fields.iter().position(|f| *f == self.field).unwrap_or(0);
+        let len = fields.len();
+        self.field = fields[if forward {
+            (i + 1) % len
+        } else {
+            (i + len - 1) % len
+        }];
+    }
+
+    pub fn picker_mut(&mut self) -> Option<&mut Picker> {
+        match self.field {
+            IssueField::Labels => Some(&mut self.labels),
+            IssueField::Assignees => Some(&mut self.assignees),
+            IssueField::Milestone => Some(&mut self.milestone),
+            _ => None,
+        }
+    }
+
+    // step through blank and each template, filling the form from it
+    fn cycle_template(&mut self, forward: bool) {
+        let len = self.templates.len();
+        if len == 0 {
+            return;
+        }
+        // blank is position 0, template i is position i + 1
+        let position = self.template.map_or(0, |i| i + 1);
+        let position = if forward {
+            (position + 1) % (len + 1)
+        } else {
+            (position + len) % (len + 1)
+        };
+        self.template = position.checked_sub(1);
+
+        let template = self
+            .template
+            .and_then(|i| self.templates.get(i))
+            .cloned()
+            .unwrap_or_default();
+        self.title = template.title;
+        self.body = TextEditor::from_text(&template.body);
+        self.labels.choose(&template.labels);
+        self.assignees.choose(&template.assignees);
+    }
+
+    fn to_new_issue(&self) -> NewIssue {
+        let milestone = self.milestone.chosen();
+        NewIssue {
+            title: self.title.trim().to_string(),
+            body: self.body.text(),
+            labels: self.labels.chosen(),
+            assignees: self.assignees.chosen(),
+            milestone: self
+                .milestones
+                .iter()
+                .find(|m| milestone.contains(&m.title))
+                .map(|m| m.number),
+        }
+    }
 }
 
 #[derive(PartialEq)]
@@ -23,12 +761,14 @@ pub enum RepoDetailState {
     Details,
     Issues,
     PRs,
+    Code,
+    Commits,
 }
 
 pub struct App {
     // auth
     pub user: String,
-    token: String,
+    backend: Arc<dyn GitHubBackend>,
 
     // navigation
     pub mode: AppMode,
@@ -36,9 +776,15 @@ pub struct App {
 
     // data
     pub repos: Vec<Repo>,
+    pub repos_stale_since: Option<u64>,
     pub selected_repo: Option<Repo>,
+    pub repo_counts: Option<RepoCounts>,
+    pub readme: Option<Text<'static>>,
+    pub readme_state: LoadingState,
     pub issues: Vec<Issue>,
     pub selected_issue: Option<Issue>,
+    // show PRs in the issue table, badged, instead of only in their own table
+    pub issues_include_prs: bool,
     pub prs: Vec<PR>,
     pub selected_pr: Option<PR>,
     pub detail_mode: RepoDetailState,
@@ -46,42 +792,719 @@ pub struct App {
     // UI State
     pub table_state: TableState,
     pub loading_state: LoadingState,
+    pub issues_state: LoadingState,
+    pub prs_state: LoadingState,
+    pub issues_pages: Pagination,
+    pub prs_pages: Pagination,
     pub search_input: String,
     pub issue_table_state: TableState,
     pub pr_table_state: TableState,
+    pub issue_filter: IssueFilter,
+    pub pr_filter: IssueFilter,
+    pub filter_form: Option<FilterForm>,
+
+    // comment thread of the issue or PR popup
+    pub comments: Vec<Comment>,
+    pub comments_state: LoadingState,
+    pub comments_pages: Pagination,
+    thread: Option<u32>,
+    pub draft: Option<CommentDraft>,
+
+    // new issue
+    pub issue_form: Option<IssueForm>,
+
+    // triage
+    pub triage: Option<Triage>,
+    pub notice: Option<Notice>,
+
+    // hand the active text field to $EDITOR before the next frame
+    external_edit: bool,
+
+    // PR popup
+    pub pull_detail: Option<PullDetail>,
+    pub pull_detail_state: LoadingState,
+    pub pull_action: Option<PullAction>,
+    // line comments waiting to go out with the next review, by repo and PR
+    pub pending_reviews: HashMap<(String, u32), Vec<NewReviewComment>>,
+
+    // PR diff
+    pub diff: Option<DiffView>,
+
+    // code tab
+    pub code: Option<CodeView>,
+    pub ref_picker: Option<RefPicker>,
+    // branches and tags, loaded when the ref picker is first opened
+    refs: Option<Vec<(String, RefKind)>>,
+    pub refs_state: LoadingState,
+
+    // commits tab
+    pub commits: Option<CommitLog>,
+
+    // text to put on the terminal's clipboard before the next frame
+    clipboard: Option<String>,
 
     // scrolling
     pub scroll_offset: u16,
+    pub popup_scroll: u16,
+
+    // background tasks
+    tx: UnboundedSender<Message>,
+    rx: UnboundedReceiver<Message>,
+    list_task: Option<AbortHandle>,
+    detail_tasks: Vec<AbortHandle>,
+    // the detail of the PR in the popup
+    popup_task: Option<AbortHandle>,
+    // the files of the diff being shown
+    diff_task: Option<AbortHandle>,
+    comments_task: Option<AbortHandle>,
+    code_task: Option<AbortHandle>,
+    commits_task: Option<AbortHandle>,
 }
 
 impl App {
-    pub fn new(username: String, token: String) -> Result<Self, reqwest::Error> {
+    pub fn new(username: String, backend: Arc<dyn GitHubBackend>) -> Self {
         let mut table_state = TableState::default();
         let mut issue_table_state = TableState::default();
         let mut pr_table_state = TableState::default();
         table_state.select(Some(0));
         issue_table_state.select(Some(0));
         pr_table_state.select(Some(0));
+        let (tx, rx) = unbounded_channel();
 
-        Ok(Self {
+        Self {
             user: username,
-            token: token,
+            backend,
             mode: AppMode::RepoList,
             should_quit: false,
             repos: Vec::new(),
+            repos_stale_since: None,
             prs: Vec::new(),
             selected_repo: None,
+            repo_counts: None,
+            readme: None,
+            readme_state: LoadingState::Idle,
             selected_issue: None,
             selected_pr: None,
             issues: Vec::new(),
+            issues_include_prs: false,
             table_state,
             issue_table_state,
             pr_table_state,
+            issue_filter: IssueFilter::default(),
+            pr_filter: IssueFilter::default(),
+            filter_form: None,
             loading_state: LoadingState::Idle,
+            issues_state: LoadingState::Idle,
+            prs_state: LoadingState::Idle,
+            issues_pages: Pagination::default(),
+            prs_pages: Pagination::default(),
             search_input: String::new(),
+            comments: Vec::new(),
+            comments_state: LoadingState::Idle,
+            comments_pages: Pagination::default(),
+            thread: None,
+            draft: None,
+            issue_form: None,
+            triage: None,
+            notice: None,
+            pull_action: None,
+            pending_reviews: HashMap::new(),
+            external_edit: false,
+            pull_detail: None,
+            pull_detail_state: LoadingState::Idle,
+            diff: None,
+            code: None,
+            ref_picker: None,
+            refs: None,
+            refs_state: LoadingState::Idle,
+            commits: None,
+            clipboard: None,
             scroll_offset: 0,
+            popup_scroll: 0,
             detail_mode: RepoDetailState::Details,
+            tx,
+            rx,
+            list_task: None,
+            detail_tasks: Vec::new(),
+            popup_task: None,
+            diff_task: None,
+            comments_task: None,
+            code_task: None,
+            commits_task: None,
+        }
+    }
+
+    // background tasks
+    fn spawn<F>(&self, task: F) -> AbortHandle
+    where
+        F: Future<Output = Message> + Send + 'static,
+    {
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            // the receiver only goes away when the app is shutting down
+            let _ = tx.send(task.await);
         })
+        .abort_handle()
+    }
+
+    fn spawn_list_task<F>(&mut self, task: F)
+    where
+        F: Future<Output = Message> + Send + 'static,
+    {
+        if let Some(handle) = self.list_task.take() {
+            handle.abort();
+        }
+        self.list_task = Some(self.spawn(task));
+    }
+
+    fn cancel_detail_tasks(&mut self) {
+        for handle in self.detail_tasks.drain(..) {
+            handle.abort();
+        }
+    }
+
+    // drain every result the background tasks have produced so far
+    pub fn handle_messages(&mut self) {
+        while let Ok(message) = self.rx.try_recv() {
+            self.handle_message(message);
+        }
+    }
+
+    pub fn handle_message(&mut self, message: Message) {
+        match message {
+            Message::ReposLoaded(result) => {
+                self.list_task = None;
+                match result {
+                    Ok(page) => {
+                        self.repos = page.items;
+                        self.repos_stale_since = page.stale_since;
+                        self.loading_state = LoadingState::Success;
+                        if !self.repos.is_empty() {
+                            self.table_state.select(Some(0));
+                        }
+                    }
+                    Err(e) => self.loading_state = LoadingState::Error(e),
+                }
+            }
+            Message::IssuesLoaded { repo, more, result } => {
+                // results for a repo the user already left are dropped
+                if !self.is_selected_repo(&repo) {
+                    return;
+                }
+                match self.issues_pages.receive(result) {
+                    Ok(issues) => {
+                        let visible = self.visible_issue_count();
+                        self.issues.extend(issues);
+                        self.issues_state = LoadingState::Success;
+                        self.select_current_issue();
+                        // a page of nothing but hidden PRs leaves no row to scroll to
+                        if self.visible_issue_count() == visible {
+                            self.load_more_issues();
+                        }
+                    }
+                    // a failed follow-up page keeps what is already shown
+                    Err(e) if !more => self.issues_state = LoadingState::Error(e),
+                    Err(_) => {}
+                }
+            }
+            Message::PRsLoaded { repo, more, result } => {
+                if !self.is_selected_repo(&repo) {
+                    return;
+                }
+                match self.prs_pages.receive(result) {
+                    Ok(prs) => {
+                        self.prs.extend(prs);
+                        self.prs_state = LoadingState::Success;
+                        self.select_current_pr();
+                    }
+                    Err(e) if !more => self.prs_state = LoadingState::Error(e),
+                    Err(_) => {}
+                }
+            }
+            Message::CommentsLoaded {
+                repo,
+                number,
+                more,
+                result,
+            } => {
+                self.comments_task = None;
+                if !self.is_selected_repo(&repo) || self.thread != Some(number) {
+                    return;
+                }
+                match self.comments_pages.receive(result) {
+                    Ok(comments) => {
+                        self.comments.extend(comments);
+                        self.comments_state = LoadingState::Success;
+                        // the whole conversation is shown at once
+                        if let Some(cursor) = self.comments_pages.next.clone() {
+                            self.comments_pages.loading_more = true;
+                            self.spawn_comments_page(repo, number, Some(cursor));
+                        }
+                    }
+                    Err(e) if !more => self.comments_state = LoadingState::Error(e),
+                    Err(_) => {}
+                }
+            }
+            Message::IssueFormLoaded { repo, result } => {
+                let Some(form) = self.issue_form.as_mut().filter(|form| form.repo == repo) else {
+                    return;
+                };
+                match result {
+                    Ok(options) => {
+                        form.set_options(*options);
+                        form.options_state = LoadingState::Success;
+                    }
+                    Err(e) => form.options_state = LoadingState::Error(e),
+                }
+            }
+            Message::IssueCreated { repo, result } => {
+                let Some(form) = self.issue_form.as_mut().filter(|form| form.repo == repo) else {
+                    return;
+                };
+                let issue = match result {
+                    Ok(issue) => issue,
+                    // keep the form so it can be sent again
+                    Err(e) => {
+                        form.submitting = false;
+                        form.error = Some(e.to_string());
+                        return;
+                    }
+                };
+                self.issue_form = None;
+                if self.mode == AppMode::IssueForm {
+                    self.mode = AppMode::RepoDetail;
+                }
+                if !self.is_selected_repo(&repo) {
+                    return;
+                }
+                // shown on top of the table without reloading it
+                self.issues.insert(0, *issue);
+                if let Some(counts) = &mut self.repo_counts {
+                    counts.open_issues += 1;
+                }
+                self.detail_mode = RepoDetailState::Issues;
+                self.issue_table_state.select(Some(0));
+                self.select_current_issue();
+            }
+            Message::TriageOptionsLoaded {
+                repo,
+                number,
+                result,
+            } => {
+                let Some(triage) = self
+                    .triage
+                    .as_mut()
+                    .filter(|t| t.repo == repo && t.number == number)
+                else {
+                    return;
+                };
+                match result {
+                    Ok(options) => {
+                        if let Some(issue) = self.selected_issue.as_ref() {
+                            triage.set_options(*options, issue);
+                        }
+                    }
+                    Err(e) => triage.state = LoadingState::Error(e),
+                }
+            }
+            Message::IssueChanged {
+                repo,
+                number,
+                change,
+                previous,
+                description,
+                result,
+            } => match result {
+                Ok(issue) => {
+                    self.notice = Some(Notice::ok(description));
+                    if let Some(issue) = issue
+                        && self.is_selected_repo(&repo)
+                    {
+                        self.replace_issue(*issue);
+                    }
+                }
+                Err(e) => {
+                    self.notice = Some(Notice::error(format!(
+                        "Couldn't update #{}: {}; undone",
+                        number, e
+                    )));
+                    if !self.is_selected_repo(&repo) {
+                        return;
+                    }
+                    // only this change is undone, other ones may have gone through since
+                    for shown in self
+                        .issues
+                        .iter_mut()
+                        .chain(self.selected_issue.as_mut())
+                        .filter(|shown| shown.number == number)
+                    {
+                        change.revert(shown, &previous);
+                    }
+                }
+            },
+            Message::CommentPosted {
+                repo,
+                number,
+                result,
+            } => {
+                let Some(draft) = &mut self.draft else {
+                    return;
+                };
+                if draft.repo != repo || draft.number != number {
+                    return;
+                }
+                match result {
+                    Ok(_) => {
+                        if self.mode == AppMode::CommentEditor {
+                            self.close_comment_editor();
+                        }
+                        self.draft = None;
+                        for issue in self
+                            .issues
+                            .iter_mut()
+                            .chain(self.selected_issue.as_mut())
+                            .filter(|issue| issue.number == number)
+                        {
+                            issue.comments += 1;
+                        }
+                        if self.is_selected_repo(&repo) && self.thread == Some(number) {
+                            self.load_thread(repo, number);
+                        }
+                    }
+                    // keep the text so it can be sent again
+                    Err(e) => {
+                        draft.posting = false;
+                        draft.error = Some(e.to_string());
+                    }
+                }
+            }
+            Message::PullDetailLoaded {
+                repo,
+                number,
+                result,
+            } => {
+                self.popup_task = None;
+                // kept while the editor, an action or the diff is open over the popup
+                if !self.is_selected_repo(&repo) || !self.is_selected_pr(number) {
+                    return;
+                }
+                match result {
+                    Ok(detail) => {
+                        // keep the table in step with what the popup shows
+                        if let Some(pr) = self.prs.iter_mut().find(|pr| pr.number == number) {
+                            pr.state.clone_from(&detail.pr.state);
+                            pr.draft = detail.pr.draft;
+                            pr.merged_at.clone_from(&detail.pr.merged_at);
+                        }
+                        self.pull_detail = Some(*detail);
+                        self.pull_detail_state = LoadingState::Success;
+                    }
+                    Err(e) => self.pull_detail_state = LoadingState::Error(e),
+                }
+            }
+            Message::PullActionDone {
+                repo,
+                number,
+                result,
+            } => {
+                let Some(action) = self
+                    .pull_action
+                    .as_mut()
+                    .filter(|a| a.repo == repo && a.number == number)
+                else {
+                    return;
+                };
+                match result {
+                    Ok(notice) => {
+                        if self.mode == AppMode::PullActionPopUp {
+                            self.close_pull_action();
+                        }
+                        // the queued line comments went out with the review
+                        if self
+                            .pull_action
+                            .take()
+                            .is_some_and(|a| !a.comments.is_empty())
+                        {
+                            self.pending_reviews.remove(&(repo.clone(), number));
+                            self.reload_threads(&repo, number);
+                        }
+                        self.notice = Some(notice);
+                        if self.is_selected_repo(&repo) && self.is_popup_pr(number) {
+                            self.load_pull_detail(repo, number);
+                        }
+                    }
+                    // keep the modal open so it can be sent again
+                    Err(e) => {
+                        action.sending = false;
+                        action.error = Some(e.to_string());
+                        self.notice = Some(Notice::error(format!(
+                            "{} #{} failed: {}",
+                            action.kind.title(),
+                            number,
+                            e
+                        )));
+                    }
+                }
+            }
+            Message::ReviewThreadsLoaded {
+                repo,
+                number,
+                result,
+            } => {
+                let Some(diff) = self
+                    .diff
+                    .as_mut()
+                    .filter(|diff| diff.is_pull(&repo, number))
+                else {
+                    return;
+                };
+                match result {
+                    Ok(threads) => diff.threads = threads,
+                    // the diff is still worth showing without them
+                    Err(e) => {
+                        self.notice = Some(Notice::error(format!(
+                            "Couldn't load review comments: {}",
+                            e
+                        )))
+                    }
+                }
+            }
+            Message::ThreadResolved {
+                repo,
+                number,
+                thread_id,
+                result,
+            } => {
+                let thread = self
+                    .diff
+                    .as_mut()
+                    .filter(|diff| diff.is_pull(&repo, number))
+                    .and_then(|diff| {
+                        diff.threads
+                            .iter_mut()
+                            .find(|thread| thread.id.as_ref() == Some(&thread_id))
+                    });
+                let Some(thread) = thread else {
+                    return;
+                };
+                self.notice = Some(match result {
+                    Ok(()) if thread.resolved => Notice::ok(String::from("Thread resolved")),
+                    Ok(()) => Notice::ok(String::from("Thread unresolved")),
+                    Err(e) => {
+                        thread.resolved = !thread.resolved;
+                        Notice::error(format!("Couldn't change the thread: {}; undone", e))
+                    }
+                });
+            }
+            Message::PullFilesLoaded {
+                repo,
+                number,
+                result,
+            } => {
+                self.diff_task = None;
+                let Some(diff) = self
+                    .diff
+                    .as_mut()
+                    .filter(|diff| diff.is_pull(&repo, number))
+                else {
+                    return;
+                };
+                match result {
+                    Ok(files) => {
+                        diff.files = files;
+                        diff.state = LoadingState::Success;
+                    }
+                    Err(e) => diff.state = LoadingState::Error(e),
+                }
+            }
+            Message::CodeTreeLoaded {
+                repo,
+                git_ref,
+                result,
+            } => {
+                let Some(code) = &mut self.code else {
+                    return;
+                };
+                if code.repo != repo || code.git_ref != git_ref {
+                    return;
+                }
+                self.code_task = None;
+                match result {
+                    Ok(tree) => {
+                        code.tree = CodeTree::new(&tree.entries);
+                        code.truncated = tree.truncated;
+                        code.commit = Some(tree.commit);
+                        code.state = LoadingState::Success;
+                    }
+                    Err(e) => code.state = LoadingState::Error(e),
+                }
+            }
+            Message::CodeFileLoaded {
+                repo,
+                commit,
+                path,
+                result,
+            } => {
+                let Some(code) = &mut self.code else {
+                    return;
+                };
+                if code.repo != repo
+                    || code.commit.as_ref() != Some(&commit)
+                    || code.path.as_ref() != Some(&path)
+                {
+                    return;
+                }
+                self.code_task = None;
+                match result {
+                    Ok(file) => {
+                        code.file = Some(*file);
+                        code.file_state = LoadingState::Success;
+                    }
+                    Err(e) => code.file_state = LoadingState::Error(e),
+                }
+            }
+            Message::CommitsLoaded {
+                repo,
+                pr,
+                filter,
+                more,
+                result,
+            } => {
+                let Some(log) = self
+                    .commits
+                    .as_mut()
+                    .filter(|log| log.repo == repo && log.pr == pr && log.filter == *filter)
+                else {
+                    return;
+                };
+                self.commits_task = None;
+                match log.pages.receive(result) {
+                    Ok(commits) => {
+                        log.commits.extend(commits);
+                        log.state = LoadingState::Success;
+                    }
+                    Err(e) if !more => log.state = LoadingState::Error(e),
+                    Err(_) => {}
+                }
+            }
+            Message::CommitLoaded { repo, sha, result } => {
+                let Some(diff) = self
+                    .diff
+                    .as_mut()
+                    .filter(|diff| diff.repo == repo && diff.sha.as_ref() == Some(&sha))
+                else {
+                    return;
+                };
+                self.diff_task = None;
+                match result {
+                    Ok((commit, files)) => {
+                        diff.commit = Some(*commit);
+                        diff.files = files;
+                        diff.state = LoadingState::Success;
+                    }
+                    Err(e) => diff.state = LoadingState::Error(e),
+                }
+            }
+            Message::ReadmeLoaded { repo, result } => {
+                if !self.is_selected_repo(&repo) {
+                    return;
+                }
+                match result {
+                    Ok(readme) => {
+                        self.readme = readme;
+                        self.readme_state = LoadingState::Success;
+                    }
+                    Err(e) => self.readme_state = LoadingState::Error(e),
+                }
+            }
+            Message::RefsLoaded { repo, result } => {
+                if !self.is_selected_repo(&repo) {
+                    return;
+                }
+                match result {
+                    Ok(refs) => {
+                        if let Some(picker) = &mut self.ref_picker {
+                            picker.set_refs(refs.clone());
+                        }
+                        self.refs = Some(refs);
+                        self.refs_state = LoadingState::Success;
+                    }
+                    Err(e) => self.refs_state = LoadingState::Error(e),
+                }
+            }
+            Message::DetailLoaded {
+                repo,
+                query,
+                result,
+            } => {
+                if !self.is_selected_repo(&repo) {
+                    return;
+                }
+                // the milestone titles looked up for this query serve the next pages too
+                for (looked_up, filter) in [
+                    (&query.issue_filter, &mut self.issue_filter),
+                    (&query.pr_filter, &mut self.pr_filter),
+                ] {
+                    if looked_up.milestone == filter.milestone
+                        && looked_up.milestone_title.is_some()
+                    {
+                        filter
+                            .milestone_title
+                            .clone_from(&looked_up.milestone_title);
+                    }
+                }
+                // split into the per-table messages, each part failing on its own
+                let (issues, prs) = match result {
+                    Ok(detail) => {
+                        if let Some(fresh) = detail.repo {
+                            self.selected_repo = Some(fresh);
+                        }
+                        if detail.counts.is_some() {
+                            self.repo_counts = detail.counts;
+                        }
+                        (detail.issues, detail.prs)
+                    }
+                    Err(e) => (
+                        query.issues.then(|| Err(e.clone())),
+                        query.prs.then_some(Err(e)),
+                    ),
+                };
+                if let Some(result) = issues {
+                    self.handle_message(Message::IssuesLoaded {
+                        repo: repo.clone(),
+                        more: query.issues_after.is_some(),
+                        result,
+                    });
+                }
+                if let Some(result) = prs {
+                    self.handle_message(Message::PRsLoaded {
+                        repo,
+                        more: query.prs_after.is_some(),
+                        result,
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn api_status(&self) -> StatusSnapshot {
+        self.backend.status()
+    }
+
+    fn is_selected_pr(&self, number: u32) -> bool {
+        self.selected_pr
+            .as_ref()
+            .is_some_and(|pr| pr.number == number)
+    }
+
+    fn is_popup_pr(&self, number: u32) -> bool {
+        self.mode == AppMode::PRPopUp && self.is_selected_pr(number)
+    }
+
+    fn is_selected_repo(&self, repo: &str) -> bool {
+        self.selected_repo
+            .as_ref()
+            .is_some_and(|selected| selected.full_name == repo)
     }
 
     // navigation
@@ -115,18 +1538,49 @@ impl App {
                     self.scroll_offset = self.scroll_offset.saturating_add(1)
                 }
                 RepoDetailState::Issues => {
-                    Self::select_next_in(&mut self.issue_table_state, self.issues.len());
+                    let len = self.visible_issue_count();
+                    // don't wrap around while there are more pages to come
+                    if !Self::at_bottom(&self.issue_table_state, len)
+                        || !self.issues_pages.has_more()
+                    {
+                        Self::select_next_in(&mut self.issue_table_state, len);
+                    }
                     self.select_current_issue();
+                    if Self::at_bottom(&self.issue_table_state, len) {
+                        self.load_more_issues();
+                    }
                 }
                 RepoDetailState::PRs => {
-                    Self::select_next_in(&mut self.pr_table_state, self.prs.len())
+                    if !Self::at_bottom(&self.pr_table_state, self.prs.len())
+                        || !self.prs_pages.has_more()
+                    {
+                        Self::select_next_in(&mut self.pr_table_state, self.prs.len());
+                    }
+                    self.select_current_pr();
+                    if Self::at_bottom(&self.pr_table_state, self.prs.len()) {
+                        self.load_more_prs();
+                    }
                 }
+                RepoDetailState::Code => self.move_in_code(1),
+                RepoDetailState::Commits => self.move_in_commits(true),
             },
-            AppMode::Search => {}
-            AppMode::IssuePopUp => {}
+            AppMode::Search
+            | AppMode::IssuePopUp
+            | AppMode::PRPopUp
+            | AppMode::FilterPopUp
+            | AppMode::DiffView
+            | AppMode::CommentEditor
+            | AppMode::IssueForm
+            | AppMode::TriagePopUp
+            | AppMode::PullActionPopUp
+            | AppMode::RefPicker => {}
         }
     }
 
+    fn at_bottom(state: &TableState, len: usize) -> bool {
+        len > 0 && state.selected() == Some(len - 1)
+    }
+
     pub fn previous(&mut self) {
         match self.mode {
             AppMode::RepoList => Self::select_previous_in(&mut self.table_state, self.repos.len()),
@@ -135,41 +1589,108 @@ impl App {
                     self.scroll_offset = self.scroll_offset.saturating_sub(1)
                 }
                 RepoDetailState::Issues => {
-                    Self::select_previous_in(&mut self.issue_table_state, self.issues.len());
+                    let len = self.visible_issue_count();
+                    Self::select_previous_in(&mut self.issue_table_state, len);
                     self.select_current_issue();
                 }
                 RepoDetailState::PRs => {
-                    Self::select_previous_in(&mut self.pr_table_state, self.prs.len())
+                    Self::select_previous_in(&mut self.pr_table_state, self.prs.len());
+                    self.select_current_pr();
                 }
+                RepoDetailState::Code => self.move_in_code(-1),
+                RepoDetailState::Commits => self.move_in_commits(false),
             },
-            AppMode::Search => {}
-            AppMode::IssuePopUp => {}
+            AppMode::Search
+            | AppMode::IssuePopUp
+            | AppMode::PRPopUp
+            | AppMode::FilterPopUp
+            | AppMode::DiffView
+            | AppMode::CommentEditor
+            | AppMode::IssueForm
+            | AppMode::TriagePopUp
+            | AppMode::PullActionPopUp
+            | AppMode::RefPicker => {}
         }
     }
 
-    pub async fn select_current_repo(&mut self) {
+    pub fn select_current_repo(&mut self) {
         if let Some(i) = self.table_state.selected() {
             self.selected_repo = self.repos.get(i).cloned();
-            self.load_selected_repo_issues().await;
-            self.load_selected_repo_prs().await;
+            if self.selected_repo.is_none() {
+                return;
+            }
+            self.cancel_detail_tasks();
+            self.repo_counts = None;
+            // labels and milestones belong to the repo they were picked in
+            self.issue_filter = IssueFilter::default();
+            self.pr_filter = IssueFilter::default();
+            self.reset_issues();
+            self.reset_prs();
+            self.close_code();
+            self.clear_commits();
+            self.refs = None;
+            self.refs_state = LoadingState::Idle;
+            // metadata, issues and PRs in one request where the backend allows
+            self.spawn_detail(DetailQuery::all());
+            self.load_readme();
             self.mode = AppMode::RepoDetail;
 
             self.scroll_offset = 0;
         }
     }
 
+    // issues as the table shows them, with or without PRs; filtered as they
+    // are walked, since the table asks for them on every frame
+    pub fn visible_issues(&self) -> impl Iterator<Item = &Issue> {
+        self.issues
+            .iter()
+            .filter(|issue| self.issues_include_prs || !issue.is_pull_request())
+    }
+
+    pub fn visible_issue_count(&self) -> usize {
+        self.visible_issues().count()
+    }
+
     pub fn select_current_issue(&mut self) {
         if let Some(i) = self.issue_table_state.selected() {
-            self.selected_issue = self.issues.get(i).cloned();
+            let issue = self.visible_issues().nth(i).cloned();
+            self.selected_issue = issue;
+        }
+    }
+
+    pub fn select_current_pr(&mut self) {
+        if let Some(i) = self.pr_table_state.selected() {
+            self.selected_pr = self.prs.get(i).cloned();
+        }
+    }
+
+    // show or hide PRs in the issue table; the listing is reloaded since not
+    // every backend returns them in the same listing
+    pub fn toggle_issue_prs(&mut self) {
+        if self.detail_mode != RepoDetailState::Issues {
+            return;
         }
+        self.issues_include_prs = !self.issues_include_prs;
+        self.load_selected_repo_issues();
     }
 
     pub fn back_to_list(&mut self) {
+        self.cancel_detail_tasks();
+        self.close_code();
+        self.clear_commits();
         self.mode = AppMode::RepoList;
         self.selected_repo = None;
+        self.repo_counts = None;
     }
 
     pub fn back_to_details(&mut self) {
+        for handle in [self.popup_task.take(), self.comments_task.take()]
+            .into_iter()
+            .flatten()
+        {
+            handle.abort();
+        }
+        self.thread = None;
         self.mode = AppMode::RepoDetail;
     }
 
@@ -178,24 +1699,15 @@ impl App {
         self.search_input.clear();
     }
 
-    pub async fn load_user_repos(&mut self) {
+    pub fn load_user_repos(&mut self) {
         self.loading_state = LoadingState::Loading;
 
-        match fetch_repos(&self.user).await {
-            Ok(repos) => {
-                self.repos = repos;
-                self.loading_state = LoadingState::Success;
-                if !self.repos.is_empty() {
-                    self.table_state.select(Some(0));
-                }
-            }
-            Err(e) => {
-                self.loading_state = LoadingState::Error(e.to_string());
-            }
-        }
+        let backend = self.backend.clone();
+        let user = self.user.clone();
+        self.spawn_list_task(async move { Message::ReposLoaded(backend.fetch_repos(&user).await) });
     }
 
-    pub async fn search_repositories(&mut self) {
+    pub fn search_repositories(&mut self) {
         if self.search_input.is_empty() {
             return;
         }
@@ -203,59 +1715,1232 @@ impl App {
         self.loading_state = LoadingState::Loading;
         self.mode = AppMode::RepoList;
 
-        match search_repos(&self.search_input).await {
-            Ok(repos) => {
-                self.repos = repos;
-                self.loading_state = LoadingState::Success;
-                if !self.repos.is_empty() {
-                    self.table_state.select(Some(0));
+        let backend = self.backend.clone();
+        let query = self.search_input.clone();
+        self.spawn_list_task(
+            async move { Message::ReposLoaded(backend.search_repos(&query).await) },
+        );
+    }
+
+    fn spawn_detail(&mut self, mut query: DetailQuery) {
+        let Some(repo) = self.selected_repo.as_ref().map(|r| r.full_name.clone()) else {
+            return;
+        };
+        query.issues_include_prs = self.issues_include_prs;
+        query.issue_filter = self.issue_filter.clone();
+        query.pr_filter = self.pr_filter.clone();
+        let backend = self.backend.clone();
+        let handle = self.spawn(async move {
+            // search matches milestones by title, so a search for issues and PRs,
+            // or for narrowed PRs, needs the title of the milestone number first
+            let searches_issues = query.issues && query.issues_include_prs;
+            let searches_prs = query.prs && query.pr_filter.narrows();
+            for (searched, prs) in [(searches_issues, false), (searches_prs, true)] {
+                let filter = if prs {
+                    &mut query.pr_filter
+                } else {
+                    &mut query.issue_filter
+                };
+                if !searched || filter.milestone_title.is_some() {
+                    continue;
+                }
+                let Some(number) = filter.milestone_number() else {
+                    continue;
+                };
+                match backend.get_milestone(&repo, number).await {
+                    Ok(milestone) => filter.milestone_title = Some(milestone.title),
+                    Err(e) => {
+                        return Message::DetailLoaded {
+                            repo,
+                            query: Box::new(query),
+                            result: Err(e),
+                        };
+                    }
                 }
             }
-            Err(e) => {
-                self.loading_state = LoadingState::Error(e.to_string());
+            let result = backend
+                .get_repo_detail(&repo, query.clone())
+                .await
+                .map(Box::new);
+            Message::DetailLoaded {
+                repo,
+                query: Box::new(query),
+                result,
             }
-        }
+        });
+        self.detail_tasks.push(handle);
     }
 
-    pub async fn load_selected_repo_issues(&mut self) {
-        if self.selected_repo.is_none() {
+    fn load_readme(&mut self) {
+        let Some(repo) = self.selected_repo.as_ref().map(|r| r.full_name.clone()) else {
             return;
-        }
+        };
+        self.readme = None;
+        self.readme_state = LoadingState::Loading;
+        let backend = self.backend.clone();
+        let handle = self.spawn(async move {
+            // rendered here, since long READMEs take a moment
+            let result = backend
+                .get_readme(&repo)
+                .await
+                .map(|readme| readme.as_deref().map(markdown::render));
+            Message::ReadmeLoaded { repo, result }
+        });
+        self.detail_tasks.push(handle);
+    }
 
-        self.loading_state = LoadingState::Loading;
+    fn reset_issues(&mut self) {
+        self.issues.clear();
+        self.issue_table_state.select(Some(0));
+        self.selected_issue = None;
+        self.issues_state = LoadingState::Loading;
+        self.issues_pages.reset();
+    }
 
-        match get_repo_issues(&self.selected_repo.clone().unwrap().full_name, &self.token).await {
-            Ok(issues) => {
-                self.issues = issues;
-                self.loading_state = LoadingState::Success;
-            }
-            Err(e) => {
-                self.loading_state = LoadingState::Error(e.to_string());
-            }
-        }
+    fn reset_prs(&mut self) {
+        self.prs.clear();
+        self.pr_table_state.select(Some(0));
+        self.selected_pr = None;
+        self.prs_state = LoadingState::Loading;
+        self.prs_pages.reset();
     }
 
-    pub async fn load_selected_repo_prs(&mut self) {
+    pub fn load_selected_repo_issues(&mut self) {
         if self.selected_repo.is_none() {
             return;
         }
+        self.reset_issues();
+        self.spawn_detail(DetailQuery::issues(None));
+    }
 
-        self.loading_state = LoadingState::Loading;
-
-        match get_repo_prs(&self.selected_repo.clone().unwrap().full_name, &self.token).await {
-            Ok(prs) => {
-                self.prs = prs;
-                self.loading_state = LoadingState::Success;
+    // fetch the next issue page once the cursor reaches the bottom of the table
+    pub fn load_more_issues(&mut self) {
+        if self.issues_pages.loading_more {
+            return;
+        }
+        // keep the cursor until the page arrives, so a failed page can be retried
+        let Some(cursor) = self.issues_pages.next.clone() else {
+            return;
+        };
+
+        self.issues_pages.loading_more = true;
+        self.spawn_detail(DetailQuery::issues(Some(cursor)));
+    }
+
+    pub fn load_selected_repo_prs(&mut self) {
+        if self.selected_repo.is_none() {
+            return;
+        }
+        self.reset_prs();
+        self.spawn_detail(DetailQuery::prs(None));
+    }
+
+    // fetch the next PR page once the cursor reaches the bottom of the table
+    pub fn load_more_prs(&mut self) {
+        if self.prs_pages.loading_more {
+            return;
+        }
+        let Some(cursor) = self.prs_pages.next.clone() else {
+            return;
+        };
+
+        self.prs_pages.loading_more = true;
+        self.spawn_detail(DetailQuery::prs(Some(cursor)));
+    }
+
+    pub fn open_issue_popup(&mut self) {
+        if self.detail_mode != RepoDetailState::Issues {
+            return;
+        }
+        let (Some(repo), Some(issue)) = (&self.selected_repo, &self.selected_issue) else {
+            return;
+        };
+        let (repo, number) = (repo.full_name.clone(), issue.number);
+
+        self.mode = AppMode::IssuePopUp;
+        self.popup_scroll = 0;
+        self.load_thread(repo, number);
+    }
+
+    pub fn open_pr_popup(&mut self) {
+        if self.detail_mode != RepoDetailState::PRs {
+            return;
+        }
+        let (Some(repo), Some(pr)) = (&self.selected_repo, &self.selected_pr) else {
+            return;
+        };
+        let (repo, number) = (repo.full_name.clone(), pr.number);
+
+        self.mode = AppMode::PRPopUp;
+        self.popup_scroll = 0;
+        self.pull_detail = None;
+        self.load_thread(repo.clone(), number);
+        self.load_pull_detail(repo, number);
+    }
+
+    fn load_pull_detail(&mut self, repo: String, number: u32) {
+        self.pull_detail_state = LoadingState::Loading;
+        let backend = self.backend.clone();
+        let handle = self.spawn(async move {
+            let result = backend.get_pull_detail(&repo, number).await.map(Box::new);
+            Message::PullDetailLoaded {
+                repo,
+                number,
+                result,
             }
-            Err(e) => {
-                self.loading_state = LoadingState::Error(e.to_string());
+        });
+        if let Some(previous) = self.popup_task.replace(handle) {
+            previous.abort();
+        }
+    }
+
+    // Enter on a table row: the issue or PR popup, depending on the table
+    pub fn open_selected(&mut self) {
+        match self.detail_mode {
+            RepoDetailState::Issues => self.open_issue_popup(),
+            RepoDetailState::PRs => self.open_pr_popup(),
+            RepoDetailState::Code => self.open_code_entry(true),
+            RepoDetailState::Commits => self.open_commit(),
+            RepoDetailState::Details => {}
+        }
+    }
+
+    // Ask to confirm an action on the PR in the popup. A modal left with Esc
+    // is picked up again for the same PR and action.
+    pub fn open_pull_action(&mut self, kind: PullActionKind) {
+        if self.mode != AppMode::PRPopUp {
+            return;
+        }
+        let (Some(repo), Some(detail)) = (&self.selected_repo, &self.pull_detail) else {
+            return;
+        };
+        let pr = &detail.pr;
+        let problem = match kind {
+            PullActionKind::Merge | PullActionKind::AutoMerge if pr.merged_at.is_some() => {
+                Some("is already merged")
+            }
+            PullActionKind::DeleteBranch if pr.state == "open" => Some("is still open"),
+            PullActionKind::DeleteBranch => None,
+            PullActionKind::ReadyForReview if !pr.draft => Some("is not a draft"),
+            PullActionKind::ConvertToDraft if pr.draft => Some("is already a draft"),
+            _ if pr.state != "open" => Some("is closed"),
+            _ => None,
+        };
+        if let Some(problem) = problem {
+            self.notice = Some(Notice::error(format!("#{} {}", pr.number, problem)));
+            return;
+        }
+
+        let kept = self.pull_action.as_ref().is_some_and(|action| {
+            action.repo == repo.full_name && action.number == pr.number && action.kind == kind
+        });
+        if !kept {
+            self.pull_action = Some(PullAction::new(
+                repo.full_name.clone(),
+                &detail.pr,
+                kind,
+                AppMode::PRPopUp,
+            ));
+        }
+        // queued line comments go out with any review of their PR
+        let comments = self
+            .pending_reviews
+            .get(&(repo.full_name.clone(), pr.number))
+            .filter(|_| kind.is_review())
+            .cloned()
+            .unwrap_or_default();
+        if let Some(action) = &mut self.pull_action {
+            action.comments = comments;
+            action.return_mode = AppMode::PRPopUp;
+        }
+        self.mode = AppMode::PullActionPopUp;
+    }
+
+    // draft PRs get marked ready, others converted back to drafts
+    pub fn toggle_pull_draft(&mut self) {
+        match &self.pull_detail {
+            Some(detail) if detail.pr.draft => {
+                self.open_pull_action(PullActionKind::ReadyForReview)
+            }
+            Some(_) => self.open_pull_action(PullActionKind::ConvertToDraft),
+            None => {}
+        }
+    }
+
+    pub fn close_pull_action(&mut self) {
+        if let Some(action) = &self.pull_action {
+            self.mode = action.return_mode.clone();
+        }
+    }
+
+    pub fn pull_action_mut(&mut self) -> Option<&mut PullAction> {
+        self.pull_action.as_mut().filter(|action| !action.sending)
+    }
+
+    pub fn pull_action_field(&self) -> Option<PullActionField> {
+        self.pull_action.as_ref().and_then(|action| action.field)
+    }
+
+    fn pull_action_editor(&mut self) -> Option<&mut TextEditor> {
+        self.pull_action_mut()
+            .filter(|action| action.field == Some(PullActionField::Message))
+            .map(|action| &mut action.editor)
+    }
+
+    pub fn confirm_pull_action(&mut self) {
+        let Some(action) = &mut self.pull_action else {
+            return;
+        };
+        if action.sending {
+            return;
+        }
+        if let Some(problem) = action.problem() {
+            action.error = Some(problem.to_string());
+            return;
+        }
+        action.sending = true;
+        action.error = None;
+
+        let backend = self.backend.clone();
+        let action = action.clone();
+        // not kept for aborting, like every other change
+        self.spawn(async move {
+            let (repo, number) = (action.repo.as_str(), action.number);
+            let done = Notice::ok(action.describe());
+            let result = match action.kind {
+                kind if kind.is_review() => backend
+                    .submit_review(repo, number, &action.review())
+                    .await
+                    .map(|_| done),
+                PullActionKind::Merge => match backend
+                    .merge_pull(repo, number, &action.merge_request())
+                    .await
+                {
+                    Ok(_) if !action.delete_branch => Ok(done),
+                    Ok(_) => match &action.head {
+                        Some((head_repo, branch)) => {
+                            match backend.delete_branch(head_repo, branch).await {
+                                Ok(()) => Ok(Notice::ok(format!(
+                                    "{} and deleted {}",
+                                    action.describe(),
+                                    branch
+                                ))),
+                                Err(e) => Ok(Notice::error(format!(
+                                    "{}, but couldn't delete {}: {}",
+                                    action.describe(),
+                                    branch,
+                                    e
+                                ))),
+                            }
+                        }
+                        None => Ok(done),
+                    },
+                    Err(e) => Err(e),
+                },
+                PullActionKind::AutoMerge => backend
+                    .enable_auto_merge(repo, number, action.method)
+                    .await
+                    .map(|_| done),
+                PullActionKind::ReadyForReview | PullActionKind::ConvertToDraft => backend
+                    .set_pull_draft(repo, number, action.kind == PullActionKind::ConvertToDraft)
+                    .await
+                    .map(|_| done),
+                _ => match &action.head {
+                    Some((head_repo, branch)) => {
+                        backend.delete_branch(head_repo, branch).await.map(|_| done)
+                    }
+                    None => Ok(done),
+                },
+            };
+            Message::PullActionDone {
+                repo: action.repo.clone(),
+                number,
+                result,
+            }
+        });
+    }
+
+    // the diff of the PR selected in the table, or shown in the popup
+    pub fn open_diff(&mut self) {
+        let from_table =
+            self.mode == AppMode::RepoDetail && self.detail_mode == RepoDetailState::PRs;
+        if !from_table && self.mode != AppMode::PRPopUp {
+            return;
+        }
+        let (Some(repo), Some(pr)) = (&self.selected_repo, &self.selected_pr) else {
+            return;
+        };
+        let (repo, number) = (repo.full_name.clone(), pr.number);
+
+        self.diff = Some(DiffView::new(
+            repo.clone(),
+            number,
+            None,
+            self.diff_layout(),
+            self.mode.clone(),
+        ));
+        self.mode = AppMode::DiffView;
+
+        let backend = self.backend.clone();
+        let (threads_repo, threads_backend) = (repo.clone(), backend.clone());
+        self.spawn(async move {
+            let result = threads_backend
+                .get_review_threads(&threads_repo, number)
+                .await;
+            Message::ReviewThreadsLoaded {
+                repo: threads_repo,
+                number,
+                result,
+            }
+        });
+        let handle = self.spawn(async move {
+            // parsing and highlighting happen here rather than on the UI thread
+            let result = backend
+                .get_pull_files(&repo, number)
+                .await
+                .map(|page| page.items.into_iter().map(DiffFile::new).collect());
+            Message::PullFilesLoaded {
+                repo,
+                number,
+                result,
+            }
+        });
+        if let Some(previous) = self.diff_task.replace(handle) {
+            previous.abort();
+        }
+    }
+
+    // the layout of the last diff shown, kept for the next one
+    fn diff_layout(&self) -> DiffLayout {
+        self.diff.as_ref().map(|d| d.layout).unwrap_or_default()
+    }
+
+    pub fn close_diff(&mut self) {
+        if let Some(handle) = self.diff_task.take() {
+            handle.abort();
+        }
+        self.mode = match self.diff.take() {
+            Some(diff) => diff.return_mode,
+            None => AppMode::RepoDetail,
+        };
+    }
+
+    pub fn next_diff_file(&mut self) {
+        if let Some(diff) = &mut self.diff
+            && !diff.files.is_empty()
+        {
+            diff.select_file((diff.selected + 1) % diff.files.len());
+        }
+    }
+
+    pub fn previous_diff_file(&mut self) {
+        if let Some(diff) = &mut self.diff
+            && !diff.files.is_empty()
+        {
+            let len = diff.files.len();
+            diff.select_file((diff.selected + len - 1) % len);
+        }
+    }
+
+    // move the line cursor; the view follows it when drawn
+    pub fn scroll_diff(&mut self, rows: isize) {
+        if let Some(diff) = &mut self.diff {
+            let last = diff.row_count().saturating_sub(1);
+            diff.cursor = diff.cursor.saturating_add_signed(rows).min(last);
+            diff.scroll = diff.scroll.min(diff.cursor);
+        }
+    }
+
+    fn reload_threads(&mut self, repo: &str, number: u32) {
+        if !self
+            .diff
+            .as_ref()
+            .is_some_and(|diff| diff.is_pull(repo, number))
+        {
+            return;
+        }
+        let backend = self.backend.clone();
+        let repo = repo.to_string();
+        self.spawn(async move {
+            let result = backend.get_review_threads(&repo, number).await;
+            Message::ReviewThreadsLoaded {
+                repo,
+                number,
+                result,
+            }
+        });
+    }
+
+    // start selecting a range of lines at the cursor, or stop
+    pub fn toggle_diff_range(&mut self) {
+        if let Some(diff) = &mut self.diff {
+            diff.range_start = match diff.range_start {
+                Some(_) => None,
+                None => Some(diff.cursor),
+            };
+        }
+    }
+
+    pub fn set_diff_side(&mut self, old_side: bool) {
+        if let Some(diff) = &mut self.diff {
+            diff.old_side = old_side;
+        }
+    }
+
+    // Resolve the first thread on the cursor's line, or unresolve it. Shown
+    // right away and undone if GitHub refuses.
+    pub fn toggle_thread_resolved(&mut self) {
+        let Some(diff) = &mut self.diff else {
+            return;
+        };
+        let Some(file) = diff.files.get(diff.selected) else {
+            return;
+        };
+        let (side_by_side, cursor) = (diff.side_by_side, diff.cursor);
+        let Some(thread) = diff.threads.iter_mut().find(|thread| {
+            thread.path == file.file.filename
+                && thread
+                    .line
+                    .and_then(|line| file.row_of(&thread.side, line, side_by_side))
+                    == Some(cursor)
+        }) else {
+            return;
+        };
+        let Some(thread_id) = thread.id.clone() else {
+            self.notice = Some(Notice::error(String::from(
+                "Resolving threads needs a GitHub token",
+            )));
+            return;
+        };
+        thread.resolved = !thread.resolved;
+        let resolved = thread.resolved;
+        let (repo, number) = (diff.repo.clone(), diff.number);
+
+        let backend = self.backend.clone();
+        self.spawn(async move {
+            let result = backend.set_thread_resolved(&thread_id, resolved).await;
+            Message::ThreadResolved {
+                repo,
+                number,
+                thread_id,
+                result,
+            }
+        });
+    }
+
+    // Send the queued line comments as one review, after confirming it
+    pub fn submit_pending_review(&mut self) {
+        let (Some(repo), Some(pr)) = (&self.selected_repo, &self.selected_pr) else {
+            return;
+        };
+        if self.pending_comments().is_empty() {
+            self.notice = Some(Notice::error(String::from(
+                "No pending comments: c comments on the line under the cursor",
+            )));
+            return;
+        }
+        let kept = self.pull_action.as_ref().is_some_and(|action| {
+            action.repo == repo.full_name
+                && action.number == pr.number
+                && action.kind == PullActionKind::Comment
+        });
+        if !kept {
+            self.pull_action = Some(PullAction::new(
+                repo.full_name.clone(),
+                pr,
+                PullActionKind::Comment,
+                AppMode::DiffView,
+            ));
+        }
+        let comments = self.pending_comments().to_vec();
+        if let Some(action) = &mut self.pull_action {
+            action.comments = comments;
+            action.return_mode = AppMode::DiffView;
+        }
+        self.mode = AppMode::PullActionPopUp;
+    }
+
+    // jump to the next hunk header below the top row, or the last one above it
+    pub fn next_hunk(&mut self) {
+        let Some(diff) = &mut self.diff else {
+            return;
+        };
+        let Some(file) = diff.files.get(diff.selected) else {
+            return;
+        };
+        if let Some(row) = file
+            .hunk_rows(diff.side_by_side)
+            .into_iter()
+            .find(|&row| row > diff.cursor)
+        {
+            diff.scroll = row;
+            diff.cursor = row;
+        }
+    }
+
+    pub fn previous_hunk(&mut self) {
+        let Some(diff) = &mut self.diff else {
+            return;
+        };
+        let Some(file) = diff.files.get(diff.selected) else {
+            return;
+        };
+        if let Some(row) = file
+            .hunk_rows(diff.side_by_side)
+            .into_iter()
+            .rfind(|&row| row < diff.cursor)
+        {
+            diff.scroll = row;
+            diff.cursor = row;
+        }
+    }
+
+    pub fn cycle_diff_layout(&mut self) {
+        if let Some(diff) = &mut self.diff {
+            diff.layout = diff.layout.cycle();
+            // rows differ between layouts
+            diff.scroll = 0;
+            diff.cursor = 0;
+            diff.range_start = None;
+        }
+    }
+
+    // (re)load the whole comment thread of an issue or PR
+    fn load_thread(&mut self, repo: String, number: u32) {
+        self.thread = Some(number);
+        self.comments.clear();
+        self.comments_pages.reset();
+        self.comments_state = LoadingState::Loading;
+        self.spawn_comments_page(repo, number, None);
+    }
+
+    fn spawn_comments_page(&mut self, repo: String, number: u32, cursor: Option<String>) {
+        let backend = self.backend.clone();
+        let handle = self.spawn(async move {
+            let result = backend
+                .get_issue_comments(&repo, number, cursor.as_deref())
+                .await;
+            Message::CommentsLoaded {
+                repo,
+                number,
+                more: cursor.is_some(),
+                result,
+            }
+        });
+        if let Some(previous) = self.comments_task.replace(handle) {
+            previous.abort();
+        }
+    }
+
+    // start a comment on the issue or PR of the open popup
+    pub fn open_comment_editor(&mut self) {
+        let number = match self.mode {
+            AppMode::IssuePopUp => self.selected_issue.as_ref().map(|issue| issue.number),
+            AppMode::PRPopUp => self.selected_pr.as_ref().map(|pr| pr.number),
+            _ => None,
+        };
+        let (Some(repo), Some(number)) = (&self.selected_repo, number) else {
+            return;
+        };
+        // a draft left for the same thread is picked up again
+        if self.draft.as_ref().is_none_or(|draft| {
+            draft.repo != repo.full_name || draft.number != number || draft.line.is_some()
+        }) {
+            self.draft = Some(CommentDraft {
+                repo: repo.full_name.clone(),
+                number,
+                editor: TextEditor::default(),
+                preview: false,
+                posting: false,
+                error: None,
+                line: None,
+                return_mode: self.mode.clone(),
+            });
+        }
+        self.mode = AppMode::CommentEditor;
+    }
+
+    // Write a comment on the diff line under the cursor, or the selected range
+    pub fn open_line_comment(&mut self) {
+        let Some(diff) = &self.diff else {
+            return;
+        };
+        let line = match diff.line_comment() {
+            Ok(line) => line,
+            Err(problem) => {
+                self.notice = Some(Notice::error(problem.to_string()));
+                return;
+            }
+        };
+        let (repo, number) = (diff.repo.clone(), diff.number);
+        if self.draft.as_ref().is_none_or(|draft| {
+            draft.repo != repo || draft.number != number || draft.line.as_ref() != Some(&line)
+        }) {
+            self.draft = Some(CommentDraft {
+                repo,
+                number,
+                editor: TextEditor::default(),
+                preview: false,
+                posting: false,
+                error: None,
+                line: Some(line),
+                return_mode: AppMode::DiffView,
+            });
+        }
+        if let Some(diff) = &mut self.diff {
+            diff.range_start = None;
+        }
+        self.mode = AppMode::CommentEditor;
+    }
+
+    // line comments queued for the PR of the diff view
+    pub fn pending_comments(&self) -> &[NewReviewComment] {
+        self.diff
+            .as_ref()
+            .filter(|diff| diff.sha.is_none())
+            .and_then(|diff| self.pending_reviews.get(&(diff.repo.clone(), diff.number)))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    // drop the queued comment on the cursor's line
+    pub fn discard_line_comment(&mut self) {
+        let Some(diff) = self.diff.as_ref().filter(|diff| diff.sha.is_none()) else {
+            return;
+        };
+        let key = (diff.repo.clone(), diff.number);
+        let Some(pending) = self.pending_reviews.get_mut(&key) else {
+            return;
+        };
+        let Some(file) = diff.file() else {
+            return;
+        };
+        let Some(i) = pending.iter().position(|comment| {
+            comment.path == file.file.filename
+                && file.row_of(&comment.side, comment.line, diff.side_by_side) == Some(diff.cursor)
+        }) else {
+            return;
+        };
+        pending.remove(i);
+        let left = pending.len();
+        if left == 0 {
+            self.pending_reviews.remove(&key);
+        }
+        self.notice = Some(Notice::ok(format!(
+            "Discarded the comment, {} still pending",
+            left
+        )));
+    }
+
+    // back to the popup; the draft is kept until it is sent
+    pub fn close_comment_editor(&mut self) {
+        if let Some(draft) = &self.draft {
+            self.mode = draft.return_mode.clone();
+        }
+    }
+
+    // the draft's text, unless it is being previewed or sent
+    pub fn comment_editor(&mut self) -> Option<&mut TextEditor> {
+        self.draft
+            .as_mut()
+            .filter(|draft| !draft.preview && !draft.posting)
+            .map(|draft| &mut draft.editor)
+    }
+
+    // the popup the comment editor was opened from
+    pub fn comment_return_mode(&self) -> Option<&AppMode> {
+        self.draft.as_ref().map(|draft| &draft.return_mode)
+    }
+
+    pub fn toggle_comment_preview(&mut self) {
+        if let Some(draft) = &mut self.draft {
+            draft.preview = !draft.preview;
+        }
+    }
+
+    // the text field being edited, in the comment editor or the issue form
+    fn active_editor(&mut self) -> Option<&mut TextEditor> {
+        match self.mode {
+            AppMode::CommentEditor => self.comment_editor(),
+            AppMode::IssueForm => self.issue_body_editor(),
+            AppMode::PullActionPopUp => self.pull_action_editor(),
+            _ => None,
+        }
+    }
+
+    pub fn request_external_edit(&mut self) {
+        if self.active_editor().is_some() {
+            self.external_edit = true;
+        }
+    }
+
+    // text to open in $EDITOR, once per request
+    pub fn take_external_edit(&mut self) -> Option<String> {
+        if !std::mem::take(&mut self.external_edit) {
+            return None;
+        }
+        self.active_editor().map(|editor| editor.text())
+    }
+
+    pub fn finish_external_edit(&mut self, result: io::Result<String>) {
+        let error = match result {
+            Ok(text) => {
+                if let Some(editor) = self.active_editor() {
+                    *editor = TextEditor::from_text(&text);
+                }
+                None
+            }
+            Err(e) => Some(format!("Editor failed: {}", e)),
+        };
+        match self.mode {
+            AppMode::CommentEditor => {
+                if let Some(draft) = &mut self.draft {
+                    draft.error = error;
+                }
+            }
+            AppMode::IssueForm => {
+                if let Some(form) = &mut self.issue_form {
+                    form.error = error;
+                }
+            }
+            AppMode::PullActionPopUp => {
+                if let Some(action) = &mut self.pull_action {
+                    action.error = error;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn submit_comment(&mut self) {
+        let Some(draft) = &mut self.draft else {
+            return;
+        };
+        if draft.posting || draft.editor.is_blank() {
+            return;
+        }
+        if let Some(mut line) = draft.line.clone() {
+            line.body = draft.editor.text();
+            return self.queue_line_comment(line);
+        }
+        draft.posting = true;
+        draft.error = None;
+
+        let backend = self.backend.clone();
+        let (repo, number, body) = (draft.repo.clone(), draft.number, draft.editor.text());
+        // not kept for aborting: a comment already on its way can't be taken back
+        self.spawn(async move {
+            let result = backend.post_comment(&repo, number, &body).await;
+            Message::CommentPosted {
+                repo,
+                number,
+                result,
+            }
+        });
+    }
+
+    fn queue_line_comment(&mut self, comment: NewReviewComment) {
+        let Some(draft) = self.draft.take() else {
+            return;
+        };
+        self.mode = draft.return_mode;
+        // every PR keeps its own queue until its review goes out
+        let pending = self
+            .pending_reviews
+            .entry((draft.repo, draft.number))
+            .or_default();
+        let place = match comment.start_line {
+            Some(start) => format!("{}:{}-{}", comment.path, start, comment.line),
+            None => format!("{}:{}", comment.path, comment.line),
+        };
+        pending.push(comment);
+        self.notice = Some(Notice::ok(format!(
+            "Comment on {} added to the review ({} pending, S submits)",
+            place,
+            pending.len()
+        )));
+    }
+
+    pub fn scroll_popup_down(&mut self) {
+        self.popup_scroll = self.popup_scroll.saturating_add(1);
+    }
+
+    pub fn scroll_popup_up(&mut self) {
+        self.popup_scroll = self.popup_scroll.saturating_sub(1);
+    }
+
+    // whether keys should go into a text field rather than trigger commands
+    pub fn is_typing(&self) -> bool {
+        match self.mode {
+            AppMode::Search | AppMode::CommentEditor | AppMode::RefPicker => true,
+            AppMode::IssueForm => self
+                .issue_form
+                .as_ref()
+                .is_some_and(|form| matches!(form.field, IssueField::Title | IssueField::Body)),
+            AppMode::PullActionPopUp => matches!(
+                self.pull_action_field(),
+                Some(PullActionField::Title | PullActionField::Message)
+            ),
+            AppMode::FilterPopUp => self
+                .filter_form
+                .as_ref()
+                .is_some_and(|form| form.editing_text()),
+            _ => false,
+        }
+    }
+
+    // File a new issue in the selected repo. A form left with Esc is picked up
+    // again, otherwise the repo's labels, assignees, milestones and templates
+    // are loaded for it.
+    pub fn open_issue_form(&mut self) {
+        let Some(repo) = self.selected_repo.as_ref().map(|r| r.full_name.clone()) else {
+            return;
+        };
+        self.mode = AppMode::IssueForm;
+        if self
+            .issue_form
+            .as_ref()
+            .is_some_and(|form| form.repo == repo)
+        {
+            return;
+        }
+        self.issue_form = Some(IssueForm::new(repo.clone()));
+
+        let backend = self.backend.clone();
+        self.spawn(async move {
+            let (labels, assignees, milestones, templates) = tokio::join!(
+                backend.get_labels(&repo),
+                backend.get_assignees(&repo),
+                backend.get_milestones(&repo),
+                backend.get_issue_templates(&repo),
+            );
+            let result = match (labels, assignees, milestones) {
+                (Ok(labels), Ok(assignees), Ok(milestones)) => Ok(Box::new(IssueFormOptions {
+                    labels: labels.items,
+                    assignees: assignees.items,
+                    milestones: milestones.items,
+                    // templates are a convenience: the form works without them
+                    templates: templates.unwrap_or_default(),
+                })),
+                (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => Err(e),
+            };
+            Message::IssueFormLoaded { repo, result }
+        });
+    }
+
+    // back to the repo; the form is kept until the issue is created
+    pub fn close_issue_form(&mut self) {
+        self.mode = AppMode::RepoDetail;
+    }
+
+    pub fn issue_field(&self) -> Option<IssueField> {
+        self.issue_form.as_ref().map(|form| form.field)
+    }
+
+    pub fn next_issue_field(&mut self) {
+        if let Some(form) = &mut self.issue_form {
+            form.step_field(true);
+        }
+    }
+
+    pub fn previous_issue_field(&mut self) {
+        if let Some(form) = &mut self.issue_form {
+            form.step_field(false);
+        }
+    }
+
+    pub fn cycle_issue_template(&mut self, forward: bool) {
+        if let Some(form) = &mut self.issue_form {
+            form.cycle_template(forward);
+        }
+    }
+
+    pub fn issue_title_input(&mut self, c: char) {
+        if let Some(form) = &mut self.issue_form {
+            form.title.push(c);
+        }
+    }
+
+    pub fn issue_title_backspace(&mut self) {
+        if let Some(form) = &mut self.issue_form {
+            form.title.pop();
+        }
+    }
+
+    pub fn issue_body_editor(&mut self) -> Option<&mut TextEditor> {
+        self.issue_form
+            .as_mut()
+            .filter(|form| form.field == IssueField::Body && !form.submitting)
+            .map(|form| &mut form.body)
+    }
+
+    pub fn issue_picker(&mut self) -> Option<&mut Picker> {
+        self.issue_form.as_mut().and_then(IssueForm::picker_mut)
+    }
+
+    pub fn submit_issue(&mut self) {
+        let Some(form) = &mut self.issue_form else {
+            return;
+        };
+        if form.submitting {
+            return;
+        }
+        if form.title.trim().is_empty() {
+            form.error = Some(String::from("An issue needs a title"));
+            form.field = IssueField::Title;
+            return;
+        }
+        form.submitting = true;
+        form.error = None;
+
+        let backend = self.backend.clone();
+        let (repo, issue) = (form.repo.clone(), form.to_new_issue());
+        self.spawn(async move {
+            let result = backend.create_issue(&repo, &issue).await.map(Box::new);
+            Message::IssueCreated { repo, result }
+        });
+    }
+
+    // the issue triage keys act on: the one in the popup or selected in the table
+    fn triage_target(&self) -> Option<&Issue> {
+        match self.mode {
+            AppMode::IssuePopUp => self.selected_issue.as_ref(),
+            AppMode::RepoDetail if self.detail_mode == RepoDetailState::Issues => {
+                self.selected_issue.as_ref()
+            }
+            _ => None,
+        }
+    }
+
+    // Start a triage action. Closing a closed issue reopens it and locking a
+    // locked one unlocks it, without a picker.
+    pub fn open_triage(&mut self, action: TriageAction) {
+        let (Some(repo), Some(issue)) = (&self.selected_repo, self.triage_target()) else {
+            return;
+        };
+        let (repo, number) = (repo.full_name.clone(), issue.number);
+        match action {
+            TriageAction::Close if issue.state == "closed" => {
+                return self.change_issue(number, IssueChange::Reopen);
+            }
+            TriageAction::Lock if issue.locked => {
+                return self.change_issue(number, IssueChange::Unlock);
+            }
+            _ => {}
+        }
+
+        self.triage = Some(Triage::new(repo.clone(), issue, action, self.mode.clone()));
+        self.mode = AppMode::TriagePopUp;
+        if !action.needs_options() {
+            return;
+        }
+
+        let backend = self.backend.clone();
+        self.spawn(async move {
+            let result = match action {
+                TriageAction::Labels => {
+                    backend
+                        .get_labels(&repo)
+                        .await
+                        .map(|page| IssueFormOptions {
+                            labels: page.items,
+                            ..IssueFormOptions::default()
+                        })
+                }
+                TriageAction::Assignees => {
+                    backend
+                        .get_assignees(&repo)
+                        .await
+                        .map(|page| IssueFormOptions {
+                            assignees: page.items,
+                            ..IssueFormOptions::default()
+                        })
+                }
+                _ => backend
+                    .get_milestones(&repo)
+                    .await
+                    .map(|page| IssueFormOptions {
+                        milestones: page.items,
+                        ..IssueFormOptions::default()
+                    }),
+            };
+            Message::TriageOptionsLoaded {
+                repo,
+                number,
+                result: result.map(Box::new),
+            }
+        });
+    }
+
+    pub fn triage_return_mode(&self) -> Option<&AppMode> {
+        self.triage.as_ref().map(|triage| &triage.return_mode)
+    }
+
+    pub fn close_triage(&mut self) {
+        if let Some(triage) = self.triage.take() {
+            self.mode = triage.return_mode;
+        }
+    }
+
+    pub fn triage_picker(&mut self) -> Option<&mut Picker> {
+        self.triage
+            .as_mut()
+            .filter(|triage| triage.state == LoadingState::Success)
+            .map(|triage| &mut triage.picker)
+    }
+
+    pub fn apply_triage(&mut self) {
+        let Some(triage) = &self.triage else {
+            return;
+        };
+        if triage.state != LoadingState::Success {
+            return;
+        }
+        let (number, change) = (triage.number, triage.change());
+        self.close_triage();
+        self.change_issue(number, change);
+    }
+
+    // Apply `change` to the issue on screen right away, then send it
+    fn change_issue(&mut self, number: u32, change: IssueChange) {
+        let Some(repo) = self.selected_repo.as_ref().map(|r| r.full_name.clone()) else {
+            return;
+        };
+        let Some(previous) = self
+            .selected_issue
+            .clone()
+            .filter(|issue| issue.number == number)
+        else {
+            return;
+        };
+        let mut changed = previous.clone();
+        change.apply(&mut changed);
+        self.replace_issue(changed);
+
+        let backend = self.backend.clone();
+        let description = change.describe(number);
+        self.spawn(async move {
+            let result = match &change {
+                IssueChange::Lock(reason) => backend
+                    .lock_issue(&repo, number, reason.as_deref())
+                    .await
+                    .map(|_| None),
+                IssueChange::Unlock => backend.unlock_issue(&repo, number).await.map(|_| None),
+                change => match change.update() {
+                    Some(update) => backend
+                        .update_issue(&repo, number, &update)
+                        .await
+                        .map(|issue| Some(Box::new(issue))),
+                    None => Ok(None),
+                },
+            };
+            Message::IssueChanged {
+                repo,
+                number,
+                change,
+                previous: Box::new(previous),
+                description,
+                result,
             }
+        });
+    }
+
+    // swap in a new version of an issue wherever it is shown
+    fn replace_issue(&mut self, issue: Issue) {
+        for shown in self
+            .issues
+            .iter_mut()
+            .chain(self.selected_issue.as_mut())
+            .filter(|shown| shown.number == issue.number)
+        {
+            *shown = issue.clone();
         }
     }
 
-    pub fn open_issue_popup(&mut self) {
-        if self.detail_mode == RepoDetailState::Issues {
-            self.mode = AppMode::IssuePopUp;
+    // edit the filter of the issue or PR table, whichever is focused
+    pub fn open_filter_form(&mut self) {
+        let prs = match self.detail_mode {
+            RepoDetailState::Issues => false,
+            RepoDetailState::PRs => true,
+            RepoDetailState::Commits => return self.open_commit_filter(),
+            RepoDetailState::Details | RepoDetailState::Code => return,
+        };
+        let filter = if prs {
+            &self.pr_filter
+        } else {
+            &self.issue_filter
+        };
+        self.filter_form = Some(FilterForm::new(filter, prs));
+        self.mode = AppMode::FilterPopUp;
+    }
+
+    pub fn close_filter_form(&mut self) {
+        self.filter_form = None;
+        self.mode = AppMode::RepoDetail;
+    }
+
+    pub fn next_filter_field(&mut self) {
+        if let Some(form) = &mut self.filter_form {
+            form.selected = (form.selected + 1) % form.fields().len();
+        }
+    }
+
+    pub fn previous_filter_field(&mut self) {
+        if let Some(form) = &mut self.filter_form {
+            let len = form.fields().len();
+            form.selected = (form.selected + len - 1) % len;
+        }
+    }
+
+    // step through the choices of the state, sort and direction fields
+    pub fn cycle_filter_value(&mut self) {
+        let Some(form) = &mut self.filter_form else {
+            return;
+        };
+        match form.field() {
+            FilterField::State => form.draft.state = form.draft.state.cycle(),
+            FilterField::Sort => form.draft.sort = form.draft.sort.cycle(form.prs),
+            FilterField::Direction => form.draft.direction = form.draft.direction.cycle(),
+            _ => {}
+        }
+    }
+
+    pub fn filter_input(&mut self, c: char) {
+        if let Some(text) = self.filter_form.as_mut().and_then(FilterForm::text_mut) {
+            text.push(c);
+        }
+    }
+
+    pub fn filter_backspace(&mut self) {
+        if let Some(text) = self.filter_form.as_mut().and_then(FilterForm::text_mut) {
+            text.pop();
+        }
+    }
+
+    // reload the edited table with the new filter
+    pub fn apply_filter(&mut self) {
+        let Some(form) = self.filter_form.take() else {
+            return;
+        };
+        self.mode = AppMode::RepoDetail;
+        if form.commits {
+            if let Some(log) = &mut self.commits {
+                let filter = form.to_commit_filter(&log.filter);
+                if filter != log.filter {
+                    log.filter = filter;
+                    self.load_commits();
+                }
+            }
+            return;
+        }
+        let filter = form.to_filter();
+        if form.prs {
+            if filter != self.pr_filter {
+                self.pr_filter = filter;
+                self.load_selected_repo_prs();
+            }
+        } else if filter != self.issue_filter {
+            self.issue_filter = filter;
+            self.load_selected_repo_issues();
         }
     }
 
@@ -263,7 +2948,847 @@ impl App {
         match self.detail_mode {
             RepoDetailState::Details => self.detail_mode = RepoDetailState::Issues,
             RepoDetailState::Issues => self.detail_mode = RepoDetailState::PRs,
-            RepoDetailState::PRs => self.detail_mode = RepoDetailState::Details,
+            RepoDetailState::PRs => {
+                self.detail_mode = RepoDetailState::Code;
+                self.open_code();
+            }
+            RepoDetailState::Code => {
+                self.detail_mode = RepoDetailState::Commits;
+                self.open_commits();
+            }
+            RepoDetailState::Commits => self.detail_mode = RepoDetailState::Details,
+        }
+    }
+
+    // Show the code of the selected repo, from its default branch the first
+    // time; coming back to the tab keeps where it was left
+    fn open_code(&mut self) {
+        let Some(repo) = &self.selected_repo else {
+            return;
+        };
+        if self
+            .code
+            .as_ref()
+            .is_some_and(|code| code.repo == repo.full_name)
+        {
+            return;
+        }
+        let git_ref = repo
+            .default_branch
+            .clone()
+            .unwrap_or_else(|| String::from("HEAD"));
+        self.code = Some(CodeView {
+            repo: repo.full_name.clone(),
+            git_ref: git_ref.clone(),
+            commit: None,
+            tree: CodeTree::default(),
+            truncated: false,
+            state: LoadingState::Idle,
+            selected: 0,
+            path: None,
+            file: None,
+            file_state: LoadingState::Idle,
+            viewing: false,
+            cursor: 0,
+            scroll: 0,
+        });
+        self.load_code_tree(git_ref);
+    }
+
+    fn close_code(&mut self) {
+        if let Some(handle) = self.code_task.take() {
+            handle.abort();
+        }
+        self.code = None;
+        self.ref_picker = None;
+        if self.detail_mode == RepoDetailState::Code {
+            self.detail_mode = RepoDetailState::Details;
+        }
+    }
+
+    // switch the code tab to another branch, tag or commit
+    fn load_code_tree(&mut self, git_ref: String) {
+        let Some(code) = &mut self.code else {
+            return;
+        };
+        code.git_ref = git_ref.clone();
+        code.commit = None;
+        code.tree = CodeTree::default();
+        code.state = LoadingState::Loading;
+        code.selected = 0;
+        code.path = None;
+        code.file = None;
+        code.file_state = LoadingState::Idle;
+        code.viewing = false;
+
+        let repo = code.repo.clone();
+        let backend = self.backend.clone();
+        let handle = self.spawn(async move {
+            let result = backend.get_tree(&repo, &git_ref).await.map(Box::new);
+            Message::CodeTreeLoaded {
+                repo,
+                git_ref,
+                result,
+            }
+        });
+        if let Some(previous) = self.code_task.replace(handle) {
+            previous.abort();
+        }
+    }
+
+    // through the tree, or the lines of the open file
+    pub fn move_in_code(&mut self, rows: isize) {
+        let Some(code) = &mut self.code else {
+            return;
+        };
+        if code.viewing {
+            let last = code.file.as_ref().map_or(0, CodeFile::line_count);
+            code.cursor = code
+                .cursor
+                .saturating_add_signed(rows)
+                .min(last.saturating_sub(1));
+            code.scroll = code.scroll.min(code.cursor);
+        } else {
+            let last = code.tree.rows.len().saturating_sub(1);
+            code.selected = code.selected.saturating_add_signed(rows).min(last);
+        }
+    }
+
+    // Open the file under the cursor, or open the directory; with `toggle`
+    // an open directory is closed again
+    pub fn open_code_entry(&mut self, toggle: bool) {
+        let Some(code) = &mut self.code else {
+            return;
+        };
+        if code.viewing {
+            return;
+        }
+        let Some(row) = code.tree.rows.get(code.selected) else {
+            return;
+        };
+        match row.kind {
+            EntryKind::Dir => {
+                if toggle || !row.expanded {
+                    code.tree.toggle(code.selected);
+                }
+            }
+            EntryKind::Submodule => {
+                self.notice = Some(Notice::error(format!(
+                    "{} is a submodule, browse its own repo instead",
+                    row.path
+                )));
+            }
+            EntryKind::File => {
+                let path = row.path.clone();
+                code.viewing = true;
+                // reopening the file shown keeps its place
+                if code.path.as_ref() == Some(&path) && code.file_state != LoadingState::Loading {
+                    if matches!(code.file_state, LoadingState::Error(_)) {
+                        self.load_code_file(path);
+                    }
+                    return;
+                }
+                self.load_code_file(path);
+            }
+        }
+    }
+
+    fn load_code_file(&mut self, path: String) {
+        let Some(code) = &mut self.code else {
+            return;
+        };
+        let Some(commit) = code.commit.clone() else {
+            return;
+        };
+        code.path = Some(path.clone());
+        code.file = None;
+        code.file_state = LoadingState::Loading;
+        code.cursor = 0;
+        code.scroll = 0;
+
+        let repo = code.repo.clone();
+        let backend = self.backend.clone();
+        let handle = self.spawn(async move {
+            // decoding and highlighting happen here rather than on the UI thread
+            let result = backend
+                .get_file(&repo, &path, &commit)
+                .await
+                .map(|content| Box::new(CodeFile::new(content)));
+            Message::CodeFileLoaded {
+                repo,
+                commit,
+                path,
+                result,
+            }
+        });
+        if let Some(previous) = self.code_task.replace(handle) {
+            previous.abort();
+        }
+    }
+
+    // back from the file to the tree, or close the directory the cursor is in
+    pub fn collapse_code(&mut self) {
+        let Some(code) = &mut self.code else {
+            return;
+        };
+        if code.viewing {
+            code.viewing = false;
+        } else {
+            code.selected = code.tree.collapse(code.selected);
+        }
+    }
+
+    // first or last line of the file, or row of the tree
+    pub fn code_jump(&mut self, end: bool) {
+        self.move_in_code(if end { isize::MAX } else { isize::MIN });
+    }
+
+    // Copy the link to the cursor line of the open file, pinned to the
+    // commit, so it keeps pointing at the same code
+    pub fn copy_permalink(&mut self) {
+        let (Some(repo), Some(code)) = (&self.selected_repo, &self.code) else {
+            return;
+        };
+        let (Some(commit), Some(file)) = (&code.commit, &code.file) else {
+            return;
+        };
+        let line = match file.body {
+            FileBody::Text(_) if code.viewing => code.cursor + 1,
+            _ => 1,
+        };
+        let link = permalink(&repo.html_url, commit, &file.path, line);
+        self.notice = Some(Notice::ok(format!("Copied {}", link)));
+        self.clipboard = Some(link);
+    }
+
+    pub fn take_clipboard(&mut self) -> Option<String> {
+        self.clipboard.take()
+    }
+
+    // pick the branch, tag or commit the code or commits tab shows
+    pub fn open_ref_picker(&mut self) {
+        let Some(repo) = &self.selected_repo else {
+            return;
+        };
+        let shown = match self.detail_mode {
+            RepoDetailState::Code => self.code.is_some(),
+            // a PR's commits come from its own branch
+            RepoDetailState::Commits => self.commits.as_ref().is_some_and(|log| log.pr.is_none()),
+            _ => false,
+        };
+        if !shown {
+            return;
+        }
+        let mut picker = RefPicker::default();
+        match &self.refs {
+            Some(refs) => picker.set_refs(refs.clone()),
+            None => self.load_refs(repo.full_name.clone()),
+        }
+        self.ref_picker = Some(picker);
+        self.mode = AppMode::RefPicker;
+    }
+
+    fn load_refs(&mut self, repo: String) {
+        self.refs_state = LoadingState::Loading;
+        let backend = self.backend.clone();
+        self.spawn(async move {
+            let (branches, tags) =
+                tokio::join!(backend.get_branches(&repo), backend.get_tags(&repo));
+            let result = branches.and_then(|branches| {
+                let tags = tags?;
+                let branches = branches
+                    .items
+                    .into_iter()
+                    .map(|b| (b.name, RefKind::Branch));
+                let tags = tags.items.into_iter().map(|t| (t.name, RefKind::Tag));
+                Ok(branches.chain(tags).collect())
+            });
+            Message::RefsLoaded { repo, result }
+        });
+    }
+
+    pub fn close_ref_picker(&mut self) {
+        self.ref_picker = None;
+        self.mode = AppMode::RepoDetail;
+    }
+
+    pub fn ref_picker_mut(&mut self) -> Option<&mut RefPicker> {
+        self.ref_picker.as_mut()
+    }
+
+    pub fn choose_ref(&mut self) {
+        let Some(git_ref) = self.ref_picker.as_ref().and_then(RefPicker::choice) else {
+            return;
+        };
+        self.close_ref_picker();
+        if self.detail_mode == RepoDetailState::Commits {
+            if let Some(log) = &mut self.commits {
+                log.filter.git_ref = Some(git_ref);
+            }
+            self.load_commits();
+        } else {
+            self.load_code_tree(git_ref);
+        }
+    }
+
+    // Show the history of the selected repo's default branch the first time;
+    // coming back to the tab keeps where it was left, unless that was a PR
+    fn open_commits(&mut self) {
+        let Some(repo) = &self.selected_repo else {
+            return;
+        };
+        if self
+            .commits
+            .as_ref()
+            .is_some_and(|log| log.repo == repo.full_name && log.pr.is_none())
+        {
+            return;
+        }
+        self.commits = Some(CommitLog::new(repo.full_name.clone(), None));
+        self.load_commits();
+    }
+
+    // List the commits of the PR selected in the table, or shown in the
+    // popup, in the commits tab; Esc goes back to the PR
+    pub fn open_pull_commits(&mut self) {
+        let from_table =
+            self.mode == AppMode::RepoDetail && self.detail_mode == RepoDetailState::PRs;
+        if !from_table && self.mode != AppMode::PRPopUp {
+            return;
+        }
+        let (Some(repo), Some(pr)) = (&self.selected_repo, &self.selected_pr) else {
+            return;
+        };
+        let mut log = CommitLog::new(repo.full_name.clone(), Some(pr.number));
+        log.return_mode = Some(self.mode.clone());
+        self.commits = Some(log);
+        self.mode = AppMode::RepoDetail;
+        self.detail_mode = RepoDetailState::Commits;
+        self.load_commits();
+    }
+
+    // back from a PR's commits to the PR, or else to the repo list
+    pub fn close_commits(&mut self) {
+        let Some(return_mode) = self.commits.as_mut().and_then(|log| log.return_mode.take()) else {
+            return self.back_to_list();
+        };
+        self.clear_commits();
+        self.detail_mode = RepoDetailState::PRs;
+        self.mode = return_mode;
+    }
+
+    fn clear_commits(&mut self) {
+        if let Some(handle) = self.commits_task.take() {
+            handle.abort();
+        }
+        self.commits = None;
+        if self.detail_mode == RepoDetailState::Commits {
+            self.detail_mode = RepoDetailState::Details;
+        }
+    }
+
+    // (re)load the commits tab from its first page
+    fn load_commits(&mut self) {
+        let Some(log) = &mut self.commits else {
+            return;
+        };
+        log.commits.clear();
+        log.pages.reset();
+        log.state = LoadingState::Loading;
+        log.table_state.select(Some(0));
+        let (repo, pr, filter) = (log.repo.clone(), log.pr, log.filter.clone());
+        self.spawn_commits_page(repo, pr, filter, None);
+    }
+
+    // fetch the next page once the cursor reaches the bottom of the table
+    fn load_more_commits(&mut self) {
+        let Some(log) = &mut self.commits else {
+            return;
+        };
+        if log.pages.loading_more {
+            return;
+        }
+        let Some(cursor) = log.pages.next.clone() else {
+            return;
+        };
+        log.pages.loading_more = true;
+        let (repo, pr, filter) = (log.repo.clone(), log.pr, log.filter.clone());
+        self.spawn_commits_page(repo, pr, filter, Some(cursor));
+    }
+
+    fn spawn_commits_page(
+        &mut self,
+        repo: String,
+        pr: Option<u32>,
+        filter: CommitFilter,
+        cursor: Option<String>,
+    ) {
+        let backend = self.backend.clone();
+        let handle = self.spawn(async move {
+            let result = match pr {
+                Some(number) => backend.get_pull_commits(&repo, number).await,
+                None => backend.get_commits(&repo, &filter, cursor.as_deref()).await,
+            };
+            Message::CommitsLoaded {
+                repo,
+                pr,
+                filter: Box::new(filter),
+                more: cursor.is_some(),
+                result,
+            }
+        });
+        if let Some(previous) = self.commits_task.replace(handle) {
+            previous.abort();
+        }
+    }
+
+    fn move_in_commits(&mut self, forward: bool) {
+        let Some(log) = &mut self.commits else {
+            return;
+        };
+        let len = log.commits.len();
+        if !forward {
+            Self::select_previous_in(&mut log.table_state, len);
+            return;
+        }
+        // don't wrap around while there are more pages to come
+        if !Self::at_bottom(&log.table_state, len) || !log.pages.has_more() {
+            Self::select_next_in(&mut log.table_state, len);
+        }
+        if Self::at_bottom(&log.table_state, len) {
+            self.load_more_commits();
+        }
+    }
+
+    fn open_commit_filter(&mut self) {
+        let Some(log) = &self.commits else {
+            return;
+        };
+        if log.pr.is_some() {
+            self.notice = Some(Notice::error(String::from(
+                "A PR's commits can't be filtered",
+            )));
+            return;
+        }
+        self.filter_form = Some(FilterForm::for_commits(&log.filter));
+        self.mode = AppMode::FilterPopUp;
+    }
+
+    // the message, stats and diff of the commit selected in the commits tab
+    fn open_commit(&mut self) {
+        let Some(log) = &self.commits else {
+            return;
+        };
+        let Some(commit) = log.table_state.selected().and_then(|i| log.commits.get(i)) else {
+            return;
+        };
+        let (repo, sha) = (log.repo.clone(), commit.sha.clone());
+        self.diff = Some(DiffView::new(
+            repo.clone(),
+            log.pr.unwrap_or_default(),
+            Some(sha.clone()),
+            self.diff_layout(),
+            self.mode.clone(),
+        ));
+        self.mode = AppMode::DiffView;
+
+        let backend = self.backend.clone();
+        let handle = self.spawn(async move {
+            // parsing and highlighting happen here rather than on the UI thread
+            let result = backend.get_commit(&repo, &sha).await.map(|mut commit| {
+                let files = std::mem::take(&mut commit.files)
+                    .into_iter()
+                    .map(DiffFile::new)
+                    .collect();
+                (Box::new(commit), files)
+            });
+            Message::CommitLoaded { repo, sha, result }
+        });
+        if let Some(previous) = self.diff_task.replace(handle) {
+            previous.abort();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorResponse;
+    use crate::fixture::{self, FixtureBackend};
+    use tokio::time::{Duration, timeout};
+
+    fn app_with(backend: FixtureBackend) -> App {
+        App::new(String::from("octocat"), Arc::new(backend))
+    }
+
+    // apply results until the background tasks go quiet
+    async fn settle(app: &mut App) {
+        while let Ok(Some(message)) = timeout(Duration::from_millis(100), app.rx.recv()).await {
+            app.handle_message(message);
+        }
+    }
+
+    async fn open_first_repo(app: &mut App) {
+        app.load_user_repos();
+        settle(app).await;
+        app.table_state.select(Some(0));
+        app.select_current_repo();
+        settle(app).await;
+    }
+
+    fn issue_numbers(app: &App) -> Vec<u32> {
+        app.visible_issues().map(|issue| issue.number).collect()
+    }
+
+    #[tokio::test]
+    async fn selecting_a_repo_loads_its_issues_prs_and_readme() {
+        let mut app = app_with(FixtureBackend::sample());
+        app.load_user_repos();
+        settle(&mut app).await;
+        assert_eq!(app.loading_state, LoadingState::Success);
+        assert_eq!(app.repos.len(), 2);
+
+        app.table_state.select(Some(0));
+        app.select_current_repo();
+        assert_eq!(app.mode, AppMode::RepoDetail);
+        assert_eq!(app.issues_state, LoadingState::Loading);
+        settle(&mut app).await;
+
+        assert_eq!(
+            app.selected_repo
+                .as_ref()
+                .map(|repo| repo.full_name.as_str()),
+            Some("octocat/hello-world")
+        );
+        assert_eq!(app.issues_state, LoadingState::Success);
+        assert_eq!(app.prs_state, LoadingState::Success);
+        // the PR the issue listing includes stays out of the issue table
+        assert_eq!(issue_numbers(&app), vec![2, 1]);
+        assert_eq!(app.selected_issue.as_ref().map(|i| i.number), Some(2));
+        assert_eq!(app.selected_pr.as_ref().map(|pr| pr.number), Some(3));
+        assert!(app.readme.is_some());
+    }
+
+    #[tokio::test]
+    async fn results_for_a_repo_left_behind_are_dropped() {
+        let mut app = app_with(FixtureBackend::sample());
+        app.load_user_repos();
+        settle(&mut app).await;
+        app.select_current_repo();
+        app.back_to_list();
+        settle(&mut app).await;
+
+        assert!(app.issues.is_empty());
+        assert!(app.prs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn issues_page_in_as_the_cursor_reaches_the_bottom() {
+        let mut app = app_with(FixtureBackend::sample().with_page_size(1));
+        open_first_repo(&mut app).await;
+        // the first page only holds the hidden PR, so the next one follows
+        assert_eq!(issue_numbers(&app), vec![2]);
+        assert!(app.issues_pages.has_more());
+
+        app.detail_mode = RepoDetailState::Issues;
+        app.next();
+        assert!(app.issues_pages.loading_more);
+        settle(&mut app).await;
+        assert_eq!(issue_numbers(&app), vec![2, 1]);
+        // the cursor waits at the bottom instead of wrapping around
+        assert_eq!(app.issue_table_state.selected(), Some(0));
+        assert!(!app.issues_pages.has_more());
+
+        app.next();
+        assert_eq!(app.selected_issue.as_ref().map(|i| i.number), Some(1));
+        app.next();
+        assert_eq!(app.selected_issue.as_ref().map(|i| i.number), Some(2));
+    }
+
+    #[tokio::test]
+    async fn prs_page_in_as_the_cursor_reaches_the_bottom() {
+        let prs = (1..=5).rev().map(|n| fixture::pr(n, "Change")).collect();
+        let backend = FixtureBackend::sample()
+            .with_prs("octocat/hello-world", prs)
+            .with_page_size(2);
+        let mut app = app_with(backend);
+        open_first_repo(&mut app).await;
+        assert_eq!(app.prs.len(), 2);
+
+        app.detail_mode = RepoDetailState::PRs;
+        app.next();
+        assert_eq!(app.pr_table_state.selected(), Some(1));
+        settle(&mut app).await;
+        assert_eq!(app.prs.len(), 4);
+
+        // a second request for the same page is not sent
+        app.load_more_prs();
+        app.load_more_prs();
+        settle(&mut app).await;
+        let numbers: Vec<u32> = app.prs.iter().map(|pr| pr.number).collect();
+        assert_eq!(numbers, vec![5, 4, 3, 2, 1]);
+        assert!(!app.prs_pages.has_more());
+    }
+
+    #[tokio::test]
+    async fn a_refused_change_leaves_later_ones_alone() {
+        let mut app = app_with(FixtureBackend::sample());
+        open_first_repo(&mut app).await;
+        let previous = app.selected_issue.clone().unwrap();
+        let labels = IssueChange::Labels(Vec::new());
+        // labels, then assignees are changed before the first answer arrives
+        for shown in app.issues.iter_mut().chain(app.selected_issue.as_mut()) {
+            if shown.number == previous.number {
+                labels.apply(shown);
+                IssueChange::Assignees(vec![String::from("monalisa")]).apply(shown);
+            }
+        }
+
+        app.handle_message(Message::IssueChanged {
+            repo: String::from("octocat/hello-world"),
+            number: previous.number,
+            change: labels,
+            previous: Box::new(previous.clone()),
+            description: String::new(),
+            result: Err(ApiError::Http(Box::new(ErrorResponse::new(
+                422,
+                "Validation Failed",
+            )))),
+        });
+
+        let shown = app.selected_issue.as_ref().unwrap();
+        assert_eq!(shown.labels.len(), previous.labels.len());
+        assert_eq!(shown.assignees[0].login, "monalisa");
+        let listed = app
+            .issues
+            .iter()
+            .find(|i| i.number == previous.number)
+            .unwrap();
+        assert_eq!(listed.assignees[0].login, "monalisa");
+        assert!(app.notice.as_ref().is_some_and(|notice| notice.error));
+    }
+
+    #[tokio::test]
+    async fn searching_by_milestone_looks_up_its_title() {
+        let mut app = app_with(FixtureBackend::sample());
+        open_first_repo(&mut app).await;
+        app.detail_mode = RepoDetailState::Issues;
+        app.open_filter_form();
+        app.filter_form.as_mut().unwrap().milestone = String::from("1");
+        app.apply_filter();
+        settle(&mut app).await;
+        // only a search needs it
+        assert_eq!(app.issue_filter.milestone_title, None);
+
+        app.toggle_issue_prs();
+        settle(&mut app).await;
+        assert_eq!(app.issue_filter.milestone_title.as_deref(), Some("v1.0"));
+        assert_eq!(app.issues_state, LoadingState::Success);
+
+        // another milestone drops the title of the old one
+        app.open_filter_form();
+        app.filter_form.as_mut().unwrap().milestone = String::from("9");
+        app.apply_filter();
+        assert_eq!(app.issue_filter.milestone_title, None);
+        settle(&mut app).await;
+        assert!(matches!(app.issues_state, LoadingState::Error(_)));
+    }
+
+    #[tokio::test]
+    async fn each_pr_keeps_its_own_pending_review() {
+        let mut app = app_with(FixtureBackend::new());
+        for (number, path) in [(3, "src/main.rs"), (7, "README.md"), (3, "src/lib.rs")] {
+            let line = NewReviewComment {
+                path: String::from(path),
+                body: String::new(),
+                line: 1,
+                side: String::from("RIGHT"),
+                start_line: None,
+                start_side: None,
+            };
+            app.draft = Some(CommentDraft {
+                repo: String::from("octocat/hello-world"),
+                number,
+                editor: TextEditor::from_text("looks off"),
+                preview: false,
+                posting: false,
+                error: None,
+                line: Some(line),
+                return_mode: AppMode::DiffView,
+            });
+            app.submit_comment();
+        }
+
+        let queued = |number| {
+            app.pending_reviews[&(String::from("octocat/hello-world"), number)]
+                .iter()
+                .map(|comment| comment.path.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(queued(3), ["src/main.rs", "src/lib.rs"]);
+        assert_eq!(queued(7), ["README.md"]);
+    }
+
+    #[tokio::test]
+    async fn a_diff_opened_from_the_popup_leaves_its_detail_loading() {
+        let mut app = app_with(FixtureBackend::sample());
+        open_first_repo(&mut app).await;
+        app.detail_mode = RepoDetailState::PRs;
+        app.open_pr_popup();
+        app.open_diff();
+        assert_eq!(app.mode, AppMode::DiffView);
+        app.close_diff();
+        assert_eq!(app.mode, AppMode::PRPopUp);
+        settle(&mut app).await;
+
+        assert_eq!(app.pull_detail_state, LoadingState::Success);
+        assert_eq!(app.pull_detail.as_ref().map(|d| d.pr.number), Some(3));
+    }
+
+    #[tokio::test]
+    async fn the_popup_detail_arriving_under_the_diff_is_kept() {
+        let mut app = app_with(FixtureBackend::sample());
+        open_first_repo(&mut app).await;
+        app.detail_mode = RepoDetailState::PRs;
+        app.open_pr_popup();
+        app.open_diff();
+        settle(&mut app).await;
+        app.close_diff();
+
+        assert_eq!(app.mode, AppMode::PRPopUp);
+        assert_eq!(app.pull_detail_state, LoadingState::Success);
+    }
+
+    #[tokio::test]
+    async fn filters_are_left_behind_with_their_repo() {
+        let mut app = app_with(FixtureBackend::sample());
+        open_first_repo(&mut app).await;
+        app.detail_mode = RepoDetailState::Issues;
+        app.open_filter_form();
+        app.filter_form.as_mut().unwrap().milestone = String::from("1");
+        app.apply_filter();
+        app.toggle_issue_prs();
+        settle(&mut app).await;
+        assert_eq!(app.issue_filter.milestone_title.as_deref(), Some("v1.0"));
+
+        app.back_to_list();
+        app.table_state.select(Some(1));
+        app.select_current_repo();
+        assert_eq!(app.issue_filter.milestone, None);
+        assert_eq!(app.issue_filter.milestone_title, None);
+        settle(&mut app).await;
+        assert_eq!(app.issues_state, LoadingState::Success);
+    }
+
+    #[test]
+    fn the_commit_form_trims_its_fields_and_keeps_the_ref() {
+        let filter = CommitFilter {
+            git_ref: Some(String::from("main")),
+            path: Some(String::from("docs")),
+            author: Some(String::from("octocat")),
+        };
+        let mut form = FilterForm::for_commits(&filter);
+        assert_eq!(
+            (form.path.as_str(), form.author.as_str()),
+            ("docs", "octocat")
+        );
+        form.path = String::from(" /src/app/ ");
+        form.author = String::from("  ");
+        let edited = form.to_commit_filter(&filter);
+        assert_eq!(edited.git_ref.as_deref(), Some("main"));
+        assert_eq!(edited.path.as_deref(), Some("src/app"));
+        assert_eq!(edited.author, None);
+        assert_eq!(edited.params(), "sha=main&path=src%2Fapp");
+    }
+
+    #[tokio::test]
+    async fn a_failed_listing_leaves_the_other_half_of_the_detail() {
+        let backend = FixtureBackend::sample();
+        // the fixture rejects a cursor it didn't hand out
+        let query = DetailQuery {
+            issues: true,
+            issues_after: Some(String::from("bogus")),
+            prs: true,
+            ..DetailQuery::default()
+        };
+        let detail = crate::backend::rest_repo_detail(&backend, "octocat/hello-world", &query)
+            .await
+            .unwrap();
+        assert!(matches!(detail.issues, Some(Err(ApiError::Decode(_)))));
+        assert_eq!(
+            detail.prs.as_ref().unwrap().as_ref().unwrap().items.len(),
+            1
+        );
+
+        let mut app = app_with(backend);
+        open_first_repo(&mut app).await;
+        app.issues.clear();
+        app.prs.clear();
+        app.handle_message(Message::DetailLoaded {
+            repo: String::from("octocat/hello-world"),
+            query: Box::new(DetailQuery::all()),
+            result: Ok(Box::new(detail)),
+        });
+        assert!(matches!(app.issues_state, LoadingState::Error(_)));
+        assert_eq!(app.prs_state, LoadingState::Success);
+        assert_eq!(app.prs.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_thread_outcome_only_touches_the_pull_it_was_sent_for() {
+        let mut app = app_with(FixtureBackend::sample());
+        open_first_repo(&mut app).await;
+        app.detail_mode = RepoDetailState::PRs;
+        app.open_pr_popup();
+        app.open_diff();
+        settle(&mut app).await;
+        let thread_id = String::from("thread-src/main.rs-3");
+        let resolved = |app: &App| app.diff.as_ref().unwrap().threads[0].resolved;
+        app.diff.as_mut().unwrap().threads[0].resolved = true;
+
+        // PR #3 of another repo failing leaves this one's thread alone
+        let failed = || Err(ApiError::Decode(String::from("refused")));
+        app.handle_message(Message::ThreadResolved {
+            repo: String::from("octocat/spoon-knife"),
+            number: 3,
+            thread_id: thread_id.clone(),
+            result: failed(),
+        });
+        assert!(resolved(&app));
+
+        app.handle_message(Message::ThreadResolved {
+            repo: String::from("octocat/hello-world"),
+            number: 3,
+            thread_id,
+            result: failed(),
+        });
+        assert!(!resolved(&app));
+    }
+
+    #[tokio::test]
+    async fn prs_are_narrowed_like_issues() {
+        let mut app = app_with(FixtureBackend::sample());
+        open_first_repo(&mut app).await;
+        app.detail_mode = RepoDetailState::PRs;
+        app.open_filter_form();
+        let form = app.filter_form.as_mut().unwrap();
+        assert!(form.fields().contains(&FilterField::Milestone));
+        form.author = String::from("monalisa");
+        app.apply_filter();
+        settle(&mut app).await;
+        assert_eq!(app.pr_filter.summary(), "open, author:monalisa");
+        assert!(app.prs.is_empty());
+
+        app.open_filter_form();
+        let form = app.filter_form.as_mut().unwrap();
+        form.author = String::from("octocat");
+        form.milestone = String::from("1");
+        app.apply_filter();
+        settle(&mut app).await;
+        // PRs narrowed this way are searched for, which needs the milestone's title
+        assert_eq!(app.pr_filter.milestone_title.as_deref(), Some("v1.0"));
+        assert_eq!(app.issue_filter.milestone_title, None);
+        assert_eq!(app.prs_state, LoadingState::Success);
+        assert_eq!(app.prs.len(), 1);
+    }
+}
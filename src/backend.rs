@@ -0,0 +1,443 @@
+use crate::api_status::StatusSnapshot;
+use crate::error::ApiError;
+use crate::filter::{CommitFilter, IssueFilter};
+use crate::github::GitHubClient;
+use crate::graphql;
+use crate::models::{
+    Comment, Commit, FileContent, GitRef, Issue, IssueTemplate, IssueUpdate, Label, MergeMethod,
+    MergeRequest, MergeResult, Milestone, NewIssue, NewReview, PR, Page, PullDetail, PullFile,
+    Repo, RepoDetail, RepoTree, Review, ReviewThread, User,
+};
+use async_trait::async_trait;
+
+// Which parts of a repo's detail view to fetch. Listings start from the first
+// page unless given the `next` cursor of a previous one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DetailQuery {
+    pub metadata: bool,
+    pub issues: bool,
+    pub issues_after: Option<String>,
+    // list PRs in the issue listing too, as the REST issues endpoint does
+    pub issues_include_prs: bool,
+    pub issue_filter: IssueFilter,
+    pub pr_filter: IssueFilter,
+    pub prs: bool,
+    pub prs_after: Option<String>,
+}
+
+impl DetailQuery {
+    // everything shown when a repo is opened
+    pub fn all() -> Self {
+        Self {
+            metadata: true,
+            issues: true,
+            prs: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn issues(after: Option<String>) -> Self {
+        Self {
+            issues: true,
+            issues_after: after,
+            ..Self::default()
+        }
+    }
+
+    pub fn prs(after: Option<String>) -> Self {
+        Self {
+            prs: true,
+            prs_after: after,
+            ..Self::default()
+        }
+    }
+}
+
+// Everything the app needs from GitHub. `App` only talks to this trait, so the
+// network can be swapped out for `fixture::FixtureBackend` when testing.
+#[async_trait]
+pub trait GitHubBackend: Send + Sync {
+    // repo listings come back complete, as a single page
+    async fn fetch_repos(&self, username: &str) -> Result<Page<Repo>, ApiError>;
+    async fn search_repos(&self, query: &str) -> Result<Page<Repo>, ApiError>;
+
+    // Issues and PRs are paged: pass `None` for the first page, then the `next`
+    // cursor of the previous page, which already carries the filter
+    async fn get_repo_issues(
+        &self,
+        repo: &str,
+        filter: &IssueFilter,
+        cursor: Option<&str>,
+    ) -> Result<Page<Issue>, ApiError>;
+    async fn get_repo_prs(
+        &self,
+        repo: &str,
+        filter: &IssueFilter,
+        cursor: Option<&str>,
+    ) -> Result<Page<PR>, ApiError>;
+
+    // Conversation of an issue or PR, oldest first, paged like the listings
+    async fn get_issue_comments(
+        &self,
+        repo: &str,
+        number: u32,
+        cursor: Option<&str>,
+    ) -> Result<Page<Comment>, ApiError>;
+
+    // What a new issue can be labelled with, assigned to and filed under
+    async fn get_labels(&self, repo: &str) -> Result<Page<Label>, ApiError>;
+    async fn get_assignees(&self, repo: &str) -> Result<Page<User>, ApiError>;
+    async fn get_milestones(&self, repo: &str) -> Result<Page<Milestone>, ApiError>;
+    async fn get_milestone(&self, repo: &str, number: u32) -> Result<Milestone, ApiError>;
+    async fn get_issue_templates(&self, repo: &str) -> Result<Vec<IssueTemplate>, ApiError>;
+
+    // File an issue, returning it as created
+    async fn create_issue(&self, repo: &str, issue: &NewIssue) -> Result<Issue, ApiError>;
+
+    // Triage: change an issue (or PR) and get it back as it now is
+    async fn update_issue(
+        &self,
+        repo: &str,
+        number: u32,
+        update: &IssueUpdate,
+    ) -> Result<Issue, ApiError>;
+    async fn lock_issue(
+        &self,
+        repo: &str,
+        number: u32,
+        reason: Option<&str>,
+    ) -> Result<(), ApiError>;
+    async fn unlock_issue(&self, repo: &str, number: u32) -> Result<(), ApiError>;
+
+    // Comment on an issue or PR, returning the comment as posted
+    async fn post_comment(&self, repo: &str, number: u32, body: &str) -> Result<Comment, ApiError>;
+
+    // A PR with its reviews and CI checks
+    async fn get_pull_detail(&self, repo: &str, number: u32) -> Result<PullDetail, ApiError>;
+
+    // Files changed by a PR, each with its unified diff
+    async fn get_pull_files(&self, repo: &str, number: u32) -> Result<Page<PullFile>, ApiError>;
+
+    // Line comment threads of a PR's diff, and marking them resolved, which
+    // needs a token like the other GraphQL-only changes
+    async fn get_review_threads(
+        &self,
+        repo: &str,
+        number: u32,
+    ) -> Result<Vec<ReviewThread>, ApiError>;
+    async fn set_thread_resolved(&self, thread_id: &str, resolved: bool) -> Result<(), ApiError>;
+
+    // PR actions. Auto-merge and drafts can only be changed over GraphQL, so
+    // they need a token.
+    async fn submit_review(
+        &self,
+        repo: &str,
+        number: u32,
+        review: &NewReview,
+    ) -> Result<Review, ApiError>;
+    async fn merge_pull(
+        &self,
+        repo: &str,
+        number: u32,
+        merge: &MergeRequest,
+    ) -> Result<MergeResult, ApiError>;
+    async fn enable_auto_merge(
+        &self,
+        repo: &str,
+        number: u32,
+        method: MergeMethod,
+    ) -> Result<(), ApiError>;
+    async fn set_pull_draft(&self, repo: &str, number: u32, draft: bool) -> Result<(), ApiError>;
+    async fn delete_branch(&self, repo: &str, branch: &str) -> Result<(), ApiError>;
+
+    // README of the repo as Markdown, if it has one
+    async fn get_readme(&self, repo: &str) -> Result<Option<String>, ApiError>;
+
+    // Code browser: refs to choose from, the tree of one, and files of the
+    // commit it resolved to
+    async fn get_branches(&self, repo: &str) -> Result<Page<GitRef>, ApiError>;
+    async fn get_tags(&self, repo: &str) -> Result<Page<GitRef>, ApiError>;
+    async fn get_tree(&self, repo: &str, git_ref: &str) -> Result<RepoTree, ApiError>;
+    async fn get_file(&self, repo: &str, path: &str, commit: &str)
+    -> Result<FileContent, ApiError>;
+
+    // Commit history, paged like the listings, and the commits of a PR, which
+    // come complete. A single commit comes with its stats and diff.
+    async fn get_commits(
+        &self,
+        repo: &str,
+        filter: &CommitFilter,
+        cursor: Option<&str>,
+    ) -> Result<Page<Commit>, ApiError>;
+    async fn get_pull_commits(&self, repo: &str, number: u32) -> Result<Page<Commit>, ApiError>;
+    async fn get_commit(&self, repo: &str, sha: &str) -> Result<Commit, ApiError>;
+
+    // Everything `query` asks for, ideally in a single round-trip. Cursors are
+    // only meaningful to the backend that produced them.
+    async fn get_repo_detail(
+        &self,
+        repo: &str,
+        query: DetailQuery,
+    ) -> Result<RepoDetail, ApiError> {
+        rest_repo_detail(self, repo, &query).await
+    }
+
+    // rate limit quota and any ongoing backoff, polled by the UI every frame
+    fn status(&self) -> StatusSnapshot {
+        StatusSnapshot::default()
+    }
+}
+
+// Detail view assembled from the separate issue and PR listings, run concurrently.
+// Metadata is left out: the app already has it from the repo list.
+pub async fn rest_repo_detail<B: GitHubBackend + ?Sized>(
+    backend: &B,
+    repo: &str,
+    query: &DetailQuery,
+) -> Result<RepoDetail, ApiError> {
+    let issues = async {
+        if query.issues {
+            Some(
+                backend
+                    .get_repo_issues(repo, &query.issue_filter, query.issues_after.as_deref())
+                    .await,
+            )
+        } else {
+            None
+        }
+    };
+    let prs = async {
+        if query.prs {
+            Some(
+                backend
+                    .get_repo_prs(repo, &query.pr_filter, query.prs_after.as_deref())
+                    .await,
+            )
+        } else {
+            None
+        }
+    };
+    let (issues, prs) = tokio::join!(issues, prs);
+
+    // a listing that failed leaves the other one standing
+    Ok(RepoDetail {
+        issues,
+        prs,
+        ..RepoDetail::default()
+    })
+}
+
+// The REST client is the production backend, with detail views batched over
+// GraphQL when there is a token to use it with
+#[async_trait]
+impl GitHubBackend for GitHubClient {
+    async fn fetch_repos(&self, username: &str) -> Result<Page<Repo>, ApiError> {
+        GitHubClient::fetch_repos(self, username).await
+    }
+
+    async fn search_repos(&self, query: &str) -> Result<Page<Repo>, ApiError> {
+        GitHubClient::search_repos(self, query).await
+    }
+
+    async fn get_repo_issues(
+        &self,
+        repo: &str,
+        filter: &IssueFilter,
+        cursor: Option<&str>,
+    ) -> Result<Page<Issue>, ApiError> {
+        GitHubClient::get_repo_issues(self, repo, filter, cursor).await
+    }
+
+    async fn get_repo_prs(
+        &self,
+        repo: &str,
+        filter: &IssueFilter,
+        cursor: Option<&str>,
+    ) -> Result<Page<PR>, ApiError> {
+        GitHubClient::get_repo_prs(self, repo, filter, cursor).await
+    }
+
+    async fn get_issue_comments(
+        &self,
+        repo: &str,
+        number: u32,
+        cursor: Option<&str>,
+    ) -> Result<Page<Comment>, ApiError> {
+        GitHubClient::get_issue_comments(self, repo, number, cursor).await
+    }
+
+    async fn get_labels(&self, repo: &str) -> Result<Page<Label>, ApiError> {
+        GitHubClient::get_labels(self, repo).await
+    }
+
+    async fn get_assignees(&self, repo: &str) -> Result<Page<User>, ApiError> {
+        GitHubClient::get_assignees(self, repo).await
+    }
+
+    async fn get_milestones(&self, repo: &str) -> Result<Page<Milestone>, ApiError> {
+        GitHubClient::get_milestones(self, repo).await
+    }
+
+    async fn get_milestone(&self, repo: &str, number: u32) -> Result<Milestone, ApiError> {
+        GitHubClient::get_milestone(self, repo, number).await
+    }
+
+    async fn get_issue_templates(&self, repo: &str) -> Result<Vec<IssueTemplate>, ApiError> {
+        GitHubClient::get_issue_templates(self, repo).await
+    }
+
+    async fn create_issue(&self, repo: &str, issue: &NewIssue) -> Result<Issue, ApiError> {
+        GitHubClient::create_issue(self, repo, issue).await
+    }
+
+    async fn update_issue(
+        &self,
+        repo: &str,
+        number: u32,
+        update: &IssueUpdate,
+    ) -> Result<Issue, ApiError> {
+        GitHubClient::update_issue(self, repo, number, update).await
+    }
+
+    async fn lock_issue(
+        &self,
+        repo: &str,
+        number: u32,
+        reason: Option<&str>,
+    ) -> Result<(), ApiError> {
+        GitHubClient::lock_issue(self, repo, number, reason).await
+    }
+
+    async fn unlock_issue(&self, repo: &str, number: u32) -> Result<(), ApiError> {
+        GitHubClient::unlock_issue(self, repo, number).await
+    }
+
+    async fn post_comment(&self, repo: &str, number: u32, body: &str) -> Result<Comment, ApiError> {
+        GitHubClient::post_comment(self, repo, number, body).await
+    }
+
+    async fn get_pull_detail(&self, repo: &str, number: u32) -> Result<PullDetail, ApiError> {
+        GitHubClient::get_pull_detail(self, repo, number).await
+    }
+
+    async fn get_pull_files(&self, repo: &str, number: u32) -> Result<Page<PullFile>, ApiError> {
+        GitHubClient::get_pull_files(self, repo, number).await
+    }
+
+    async fn get_review_threads(
+        &self,
+        repo: &str,
+        number: u32,
+    ) -> Result<Vec<ReviewThread>, ApiError> {
+        // only GraphQL knows which threads are resolved; offline, a session
+        // without a token finds what one with a token cached there
+        if self.is_authenticated() || self.is_offline() {
+            match graphql::fetch_review_threads(self, repo, number).await {
+                Err(ApiError::NotCached(_)) if self.is_offline() => {}
+                result => return result,
+            }
+        }
+        self.get_review_comments(repo, number).await
+    }
+
+    async fn set_thread_resolved(&self, thread_id: &str, resolved: bool) -> Result<(), ApiError> {
+        graphql::set_thread_resolved(self, thread_id, resolved).await
+    }
+
+    async fn submit_review(
+        &self,
+        repo: &str,
+        number: u32,
+        review: &NewReview,
+    ) -> Result<Review, ApiError> {
+        GitHubClient::submit_review(self, repo, number, review).await
+    }
+
+    async fn merge_pull(
+        &self,
+        repo: &str,
+        number: u32,
+        merge: &MergeRequest,
+    ) -> Result<MergeResult, ApiError> {
+        GitHubClient::merge_pull(self, repo, number, merge).await
+    }
+
+    async fn enable_auto_merge(
+        &self,
+        repo: &str,
+        number: u32,
+        method: MergeMethod,
+    ) -> Result<(), ApiError> {
+        graphql::enable_auto_merge(self, repo, number, method).await
+    }
+
+    async fn set_pull_draft(&self, repo: &str, number: u32, draft: bool) -> Result<(), ApiError> {
+        graphql::set_draft(self, repo, number, draft).await
+    }
+
+    async fn delete_branch(&self, repo: &str, branch: &str) -> Result<(), ApiError> {
+        GitHubClient::delete_branch(self, repo, branch).await
+    }
+
+    async fn get_readme(&self, repo: &str) -> Result<Option<String>, ApiError> {
+        GitHubClient::get_readme(self, repo).await
+    }
+
+    async fn get_branches(&self, repo: &str) -> Result<Page<GitRef>, ApiError> {
+        GitHubClient::get_branches(self, repo).await
+    }
+
+    async fn get_tags(&self, repo: &str) -> Result<Page<GitRef>, ApiError> {
+        GitHubClient::get_tags(self, repo).await
+    }
+
+    async fn get_tree(&self, repo: &str, git_ref: &str) -> Result<RepoTree, ApiError> {
+        GitHubClient::get_tree(self, repo, git_ref).await
+    }
+
+    async fn get_file(
+        &self,
+        repo: &str,
+        path: &str,
+        commit: &str,
+    ) -> Result<FileContent, ApiError> {
+        GitHubClient::get_file(self, repo, path, commit).await
+    }
+
+    async fn get_commits(
+        &self,
+        repo: &str,
+        filter: &CommitFilter,
+        cursor: Option<&str>,
+    ) -> Result<Page<Commit>, ApiError> {
+        GitHubClient::get_commits(self, repo, filter, cursor).await
+    }
+
+    async fn get_pull_commits(&self, repo: &str, number: u32) -> Result<Page<Commit>, ApiError> {
+        GitHubClient::get_pull_commits(self, repo, number).await
+    }
+
+    async fn get_commit(&self, repo: &str, sha: &str) -> Result<Commit, ApiError> {
+        GitHubClient::get_commit(self, repo, sha).await
+    }
+
+    async fn get_repo_detail(
+        &self,
+        repo: &str,
+        query: DetailQuery,
+    ) -> Result<RepoDetail, ApiError> {
+        // GraphQL doesn't accept anonymous requests. Offline the cache decides:
+        // what a session with a token fetched sits under the GraphQL entry.
+        if self.is_authenticated() || self.is_offline() {
+            match graphql::fetch_repo_detail(self, repo, &query).await {
+                Err(ApiError::NotCached(_)) if self.is_offline() => {}
+                result => return result,
+            }
+        }
+        rest_repo_detail(self, repo, &query).await
+    }
+
+    fn status(&self) -> StatusSnapshot {
+        GitHubClient::status(self)
+    }
+}
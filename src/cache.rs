@@ -0,0 +1,403 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// A successful response kept around for conditional requests and offline use
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: String,
+    // `Link: rel="next"` of the cached page, since a 304 doesn't repeat it
+    pub next: Option<String>,
+    // unix timestamp (seconds) of the last time the server confirmed this body
+    pub fetched_at: u64,
+}
+
+// On-disk layout of one entry; the URL guards against file name collisions
+#[derive(Serialize, Deserialize)]
+struct DiskEntry {
+    url: String,
+    response: CachedResponse,
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+// `$XDG_CACHE_HOME/gte`, falling back to `~/.cache/gte`
+pub fn default_cache_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("gte"))
+}
+
+// stable FNV-1a hash, so file names survive across builds
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// Disk entries beyond this many bytes are evicted, least recently written first
+const MAX_DISK_BYTES: u64 = 64 * 1024 * 1024;
+// Bodies kept in memory beyond this many bytes are dropped, least recently used
+// first; with a directory they are read back from disk when asked for again
+const MAX_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+// how many writes go by between evictions; the first write evicts too
+const PRUNE_EVERY: usize = 256;
+// leftover temporary files older than this are from a crashed session
+const STALE_TMP: Duration = Duration::from_secs(3600);
+
+// HTTP cache keyed by request URL. Entries are revalidated with
+// `If-None-Match`/`If-Modified-Since`; a 304 answer reuses the stored body and
+// doesn't count against the rate limit. With a directory configured, entries
+// are also persisted so they can be browsed offline in a later session. They
+// hold private repos' data, so only the user can read them.
+#[derive(Debug, Clone, Default)]
+pub struct HttpCache {
+    entries: Arc<Mutex<Memory>>,
+    dir: Option<PathBuf>,
+    writes: Arc<AtomicUsize>,
+}
+
+// In-memory entries, each with the tick it was last used at
+#[derive(Debug, Default)]
+struct Memory {
+    entries: HashMap<String, (CachedResponse, u64)>,
+    bytes: usize,
+    tick: u64,
+}
+
+// what an entry holds in memory, roughly
+fn size_of(url: &str, response: &CachedResponse) -> usize {
+    url.len()
+        + response.body.len()
+        + [&response.etag, &response.last_modified, &response.next]
+            .iter()
+            .map(|text| text.as_ref().map_or(0, String::len))
+            .sum::<usize>()
+}
+
+impl Memory {
+    fn get(&mut self, url: &str) -> Option<CachedResponse> {
+        self.tick += 1;
+        let (response, used) = self.entries.get_mut(url)?;
+        *used = self.tick;
+        Some(response.clone())
+    }
+
+    fn insert(&mut self, url: &str, response: CachedResponse, max_bytes: usize) {
+        self.tick += 1;
+        self.bytes += size_of(url, &response);
+        if let Some((old, _)) = self.entries.insert(url.to_string(), (response, self.tick)) {
+            self.bytes -= size_of(url, &old);
+        }
+        while self.bytes > max_bytes {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(url, _)| url.clone())
+            else {
+                break;
+            };
+            if let Some((old, _)) = self.entries.remove(&oldest) {
+                self.bytes -= size_of(&oldest, &old);
+            }
+        }
+    }
+}
+
+impl HttpCache {
+    pub fn with_dir(dir: PathBuf) -> Self {
+        Self {
+            dir: Some(dir),
+            ..Self::default()
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Memory> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn path_for(&self, url: &str) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+        Some(dir.join(format!("{:016x}.json", fnv1a(url))))
+    }
+
+    pub async fn get(&self, url: &str) -> Option<CachedResponse> {
+        if let Some(response) = self.lock().get(url) {
+            return Some(response);
+        }
+
+        let text = tokio::fs::read_to_string(self.path_for(url)?).await.ok()?;
+        let entry: DiskEntry = serde_json::from_str(&text).ok()?;
+        if entry.url != url {
+            return None;
+        }
+        self.lock()
+            .insert(url, entry.response.clone(), MAX_MEMORY_BYTES);
+        Some(entry.response)
+    }
+
+    pub async fn insert(&self, url: &str, response: CachedResponse) {
+        self.lock().insert(url, response.clone(), MAX_MEMORY_BYTES);
+        self.persist(url, response).await;
+    }
+
+    // the server confirmed the cached body is still current
+    pub async fn touch(&self, url: &str) -> Option<CachedResponse> {
+        let mut response = self.get(url).await?;
+        response.fetched_at = now_secs();
+        self.insert(url, response.clone()).await;
+        Some(response)
+    }
+
+    // best effort: a cache that can't be written just behaves like memory only
+    async fn persist(&self, url: &str, response: CachedResponse) {
+        let (Some(dir), Some(path)) = (self.dir.clone(), self.path_for(url)) else {
+            return;
+        };
+        let entry = DiskEntry {
+            url: url.to_string(),
+            response,
+        };
+        let Ok(text) = serde_json::to_string(&entry) else {
+            return;
+        };
+        let write = self.writes.fetch_add(1, Ordering::Relaxed);
+        // a name of its own, so concurrent writes of one URL don't collide
+        let tmp = path.with_extension(format!("{}-{}.tmp", std::process::id(), write));
+        let _ = tokio::task::spawn_blocking(move || {
+            // write then rename, so a crash never leaves a truncated entry behind
+            let written = private_dir(&dir)
+                .and_then(|_| private_file(&tmp)?.write_all(text.as_bytes()))
+                .and_then(|_| fs::rename(&tmp, &path));
+            if written.is_err() {
+                let _ = fs::remove_file(&tmp);
+            }
+            if write.is_multiple_of(PRUNE_EVERY) {
+                prune(&dir, MAX_DISK_BYTES);
+            }
+        })
+        .await;
+    }
+}
+
+// `dir`, created or narrowed down to the user only
+fn private_dir(dir: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+    }
+    #[cfg(not(unix))]
+    fs::create_dir_all(dir)
+}
+
+// a new file only the user can read; an existing one (or a link) is never reused
+fn private_file(path: &Path) -> io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+// Delete the least recently written entries until the rest fit in
+// `max_bytes`, along with temporary files a crash left behind. Entries from
+// before the cache was private are narrowed down on the way.
+fn prune(dir: &Path, max_bytes: u64) {
+    let Ok(listing) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries = Vec::new();
+    for file in listing.flatten() {
+        let path = file.path();
+        let Ok(metadata) = file.metadata() else {
+            continue;
+        };
+        let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => {
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    if metadata.permissions().mode() & 0o077 != 0 {
+                        let _ = fs::set_permissions(&path, fs::Permissions::from_mode(0o600));
+                    }
+                }
+                entries.push((modified, metadata.len(), path));
+            }
+            Some("tmp") if modified.elapsed().is_ok_and(|age| age > STALE_TMP) => {
+                let _ = fs::remove_file(path);
+            }
+            _ => {}
+        }
+    }
+
+    let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+    entries.sort();
+    for (_, len, path) in entries {
+        if total <= max_bytes {
+            break;
+        }
+        if fs::remove_file(path).is_ok() {
+            total -= len;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a fresh directory under the system temp dir, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = env::temp_dir().join(format!("gte-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn response(body: &str) -> CachedResponse {
+        CachedResponse {
+            etag: Some(String::from("\"abc\"")),
+            last_modified: None,
+            body: body.to_string(),
+            next: None,
+            fetched_at: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn entries_survive_into_a_new_session() {
+        let dir = TempDir::new("cache-reload");
+        let url = "https://api.github.com/users/octocat/repos";
+        HttpCache::with_dir(dir.0.clone())
+            .insert(url, response("[]"))
+            .await;
+
+        let cache = HttpCache::with_dir(dir.0.clone());
+        assert_eq!(
+            cache.get(url).await.map(|r| r.body),
+            Some(String::from("[]"))
+        );
+        assert!(cache.get("https://api.github.com/other").await.is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn only_the_user_can_read_the_cache() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = TempDir::new("cache-private");
+        let cache = HttpCache::with_dir(dir.0.clone());
+        cache
+            .insert("https://api.github.com/x", response("{}"))
+            .await;
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dir.0), 0o700);
+        let files: Vec<PathBuf> = fs::read_dir(&dir.0)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+        assert_eq!(mode(&files[0]), 0o600);
+    }
+
+    #[test]
+    fn prune_evicts_the_oldest_entries() {
+        let dir = TempDir::new("cache-prune");
+        fs::create_dir_all(&dir.0).unwrap();
+        for name in ["old", "middle", "new"] {
+            fs::write(dir.0.join(format!("{}.json", name)), [0; 100]).unwrap();
+            // modification times need to differ
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        fs::write(dir.0.join("other.txt"), [0; 100]).unwrap();
+
+        prune(&dir.0, 250);
+        let mut left: Vec<String> = fs::read_dir(&dir.0)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["middle.json", "new.json", "other.txt"]);
+    }
+
+    #[tokio::test]
+    async fn touching_an_entry_marks_it_as_just_confirmed() {
+        let dir = TempDir::new("cache-touch");
+        let url = "https://api.github.com/repos/octocat/hello-world";
+        let cache = HttpCache::with_dir(dir.0.clone());
+        assert!(cache.touch(url).await.is_none());
+
+        cache.insert(url, response("{}")).await;
+        let touched = cache.touch(url).await.unwrap();
+        assert!(touched.fetched_at >= now_secs() - 1);
+        assert_eq!(touched.body, "{}");
+        // the new timestamp is what a later session reads back
+        let reloaded = HttpCache::with_dir(dir.0.clone()).get(url).await.unwrap();
+        assert_eq!(reloaded.fetched_at, touched.fetched_at);
+    }
+
+    #[test]
+    fn memory_drops_the_least_recently_used_bodies() {
+        let mut memory = Memory::default();
+        let entry = |body: &str| CachedResponse {
+            etag: None,
+            ..response(body)
+        };
+        // each entry is 1 byte of URL and 10 of body
+        memory.insert("a", entry("0123456789"), 30);
+        memory.insert("b", entry("0123456789"), 30);
+        assert!(memory.get("a").is_some());
+        memory.insert("c", entry("0123456789"), 30);
+        assert_eq!(memory.bytes, 22);
+        assert!(memory.get("b").is_none());
+        assert!(memory.get("a").is_some());
+
+        // replacing an entry only counts its new size
+        memory.insert("a", entry("01"), 30);
+        assert_eq!(memory.bytes, 14);
+    }
+
+    #[tokio::test]
+    async fn entries_dropped_from_memory_are_read_back_from_disk() {
+        let dir = TempDir::new("cache-memory");
+        let url = "https://api.github.com/repos/octocat/hello-world";
+        let cache = HttpCache::with_dir(dir.0.clone());
+        cache.insert(url, response("{}")).await;
+        cache.lock().entries.clear();
+        assert_eq!(
+            cache.get(url).await.map(|r| r.body),
+            Some(String::from("{}"))
+        );
+    }
+}
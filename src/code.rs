@@ -0,0 +1,325 @@
+use crate::highlight::Highlighter;
+use crate::models::{FileContent, TreeEntry};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ratatui::text::Span;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryKind {
+    Dir,
+    File,
+    // another repo pinned at a commit, which can't be browsed from here
+    Submodule,
+}
+
+impl EntryKind {
+    fn of(entry: &TreeEntry) -> Self {
+        match entry.kind.as_str() {
+            "tree" => EntryKind::Dir,
+            "commit" => EntryKind::Submodule,
+            _ => EntryKind::File,
+        }
+    }
+}
+
+// One line of the directory tree as it is currently unfolded
+#[derive(Debug, Clone)]
+pub struct TreeRow {
+    pub path: String,
+    pub name: String,
+    pub depth: usize,
+    pub kind: EntryKind,
+    pub expanded: bool,
+}
+
+// Every path of a commit, shown as a tree whose directories open and close
+#[derive(Debug, Clone, Default)]
+pub struct CodeTree {
+    // entries under each directory ("" for the root), directories first
+    children: HashMap<String, Vec<(String, EntryKind)>>,
+    expanded: HashSet<String>,
+    pub rows: Vec<TreeRow>,
+}
+
+impl CodeTree {
+    pub fn new(entries: &[TreeEntry]) -> Self {
+        let mut children: HashMap<String, Vec<(String, EntryKind)>> = HashMap::new();
+        for entry in entries {
+            let parent = entry.path.rsplit_once('/').map_or("", |(parent, _)| parent);
+            children
+                .entry(parent.to_string())
+                .or_default()
+                .push((entry.path.clone(), EntryKind::of(entry)));
+        }
+        for entries in children.values_mut() {
+            entries.sort_by(|(a, a_kind), (b, b_kind)| {
+                (*b_kind == EntryKind::Dir)
+                    .cmp(&(*a_kind == EntryKind::Dir))
+                    .then_with(|| a.to_lowercase().cmp(&b.to_lowercase()))
+            });
+        }
+
+        let mut tree = Self {
+            children,
+            ..Self::default()
+        };
+        tree.build_rows();
+        tree
+    }
+
+    fn build_rows(&mut self) {
+        let mut rows = Vec::new();
+        self.push_rows("", 0, &mut rows);
+        self.rows = rows;
+    }
+
+    fn push_rows(&self, dir: &str, depth: usize, rows: &mut Vec<TreeRow>) {
+        for (path, kind) in self.children.get(dir).into_iter().flatten() {
+            let expanded = self.expanded.contains(path);
+            rows.push(TreeRow {
+                path: path.clone(),
+                name: path.rsplit('/').next().unwrap_or(path).to_string(),
+                depth,
+                kind: *kind,
+                expanded,
+            });
+            if expanded {
+                self.push_rows(path, depth + 1, rows);
+            }
+        }
+    }
+
+    // open or close the directory at `row`
+    pub fn toggle(&mut self, row: usize) {
+        let Some(row) = self.rows.get(row).filter(|row| row.kind == EntryKind::Dir) else {
+            return;
+        };
+        let path = row.path.clone();
+        if !self.expanded.remove(&path) {
+            self.expanded.insert(path);
+        }
+        self.build_rows();
+    }
+
+    // Close the open directory at `row`, or else the one it is in; returns the
+    // row to select afterwards
+    pub fn collapse(&mut self, row: usize) -> usize {
+        let Some(current) = self.rows.get(row) else {
+            return row;
+        };
+        let dir = if current.expanded {
+            current.path.clone()
+        } else {
+            match current.path.rsplit_once('/') {
+                Some((parent, _)) => parent.to_string(),
+                None => return row,
+            }
+        };
+        self.expanded.remove(&dir);
+        self.build_rows();
+        self.rows
+            .iter()
+            .position(|row| row.path == dir)
+            .unwrap_or(0)
+    }
+}
+
+// What the file viewer shows
+#[derive(Debug, Clone)]
+pub enum FileBody {
+    // syntax colored lines
+    Text(Vec<Vec<Span<'static>>>),
+    Binary,
+    // over the 1 MB the contents API returns
+    TooLarge,
+}
+
+#[derive(Debug, Clone)]
+pub struct CodeFile {
+    pub path: String,
+    pub size: u64,
+    pub body: FileBody,
+}
+
+impl CodeFile {
+    // decode and highlight a file, off the UI thread since big files take a while
+    pub fn new(content: FileContent) -> Self {
+        let body = match decode(&content) {
+            None => FileBody::TooLarge,
+            Some(bytes) => match text_of(bytes) {
+                Some(text) => {
+                    let mut highlighter = Highlighter::for_path(&content.path);
+                    // tabs have no width of their own in the terminal buffer
+                    FileBody::Text(
+                        text.lines()
+                            .map(|line| highlighter.highlight(&line.replace('\t', "    ")))
+                            .collect(),
+                    )
+                }
+                None => FileBody::Binary,
+            },
+        };
+        Self {
+            path: content.path,
+            size: content.size,
+            body,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        match &self.body {
+            FileBody::Text(lines) => lines.len(),
+            _ => 0,
+        }
+    }
+}
+
+// raw bytes of a file; None when GitHub sent none because it is too large
+fn decode(content: &FileContent) -> Option<Vec<u8>> {
+    if content.encoding.as_deref() != Some("base64") {
+        return None;
+    }
+    // the content is wrapped every 60 characters
+    let packed: String = content
+        .content
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    STANDARD.decode(packed).ok()
+}
+
+// The file as text, unless it looks binary: like git, a NUL byte in the first
+// 8000 bytes gives it away, and so does anything that isn't UTF-8
+fn text_of(bytes: Vec<u8>) -> Option<String> {
+    if bytes.iter().take(8000).any(|&b| b == 0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+// link to a line of a file as of one commit, so it keeps pointing at the same code
+pub fn permalink(html_url: &str, commit: &str, path: &str, line: usize) -> String {
+    let path: Vec<_> = path.split('/').map(urlencoding::encode).collect();
+    format!("{}/blob/{}/{}#L{}", html_url, commit, path.join("/"), line)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RefKind {
+    Branch,
+    Tag,
+}
+
+// Chooser for the ref the code tab shows: the repo's branches and tags,
+// narrowed down by what is typed; anything else typed is taken as a commit
+#[derive(Debug, Clone, Default)]
+pub struct RefPicker {
+    pub input: String,
+    refs: Vec<(String, RefKind)>,
+    pub cursor: usize,
+}
+
+impl RefPicker {
+    pub fn set_refs(&mut self, refs: Vec<(String, RefKind)>) {
+        self.refs = refs;
+        self.cursor = 0;
+    }
+
+    pub fn matches(&self) -> Vec<&(String, RefKind)> {
+        let input = self.input.to_lowercase();
+        self.refs
+            .iter()
+            .filter(|(name, _)| name.to_lowercase().contains(&input))
+            .collect()
+    }
+
+    pub fn input(&mut self, c: char) {
+        self.input.push(c);
+        self.cursor = 0;
+    }
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+        self.cursor = 0;
+    }
+
+    pub fn down(&mut self) {
+        if self.cursor + 1 < self.matches().len() {
+            self.cursor += 1;
+        }
+    }
+
+    pub fn up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    // the highlighted ref, or what was typed when nothing matches
+    pub fn choice(&self) -> Option<String> {
+        match self.matches().get(self.cursor) {
+            Some((name, _)) => Some(name.clone()),
+            None => Some(self.input.trim().to_string()).filter(|input| !input.is_empty()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> CodeTree {
+        let entry = |path: &str, kind: &str| TreeEntry {
+            path: path.to_string(),
+            kind: kind.to_string(),
+            size: None,
+        };
+        CodeTree::new(&[
+            entry("README.md", "blob"),
+            entry("src", "tree"),
+            entry("src/main.rs", "blob"),
+            entry("src/ui", "tree"),
+            entry("src/ui/table.rs", "blob"),
+        ])
+    }
+
+    fn paths(tree: &CodeTree) -> Vec<&str> {
+        tree.rows.iter().map(|row| row.path.as_str()).collect()
+    }
+
+    #[test]
+    fn collapse_closes_an_open_directory() {
+        let mut tree = tree();
+        tree.toggle(0);
+        assert_eq!(
+            paths(&tree),
+            vec!["src", "src/ui", "src/main.rs", "README.md"]
+        );
+        assert_eq!(tree.collapse(0), 0);
+        assert_eq!(paths(&tree), vec!["src", "README.md"]);
+    }
+
+    #[test]
+    fn collapse_on_a_child_closes_its_parent() {
+        let mut tree = tree();
+        tree.toggle(0);
+        tree.toggle(1);
+        assert_eq!(tree.rows[2].path, "src/ui/table.rs");
+        assert_eq!(tree.rows[2].depth, 2);
+        // from the file, src/ui closes and stays selected
+        assert_eq!(tree.collapse(2), 1);
+        assert_eq!(
+            paths(&tree),
+            vec!["src", "src/ui", "src/main.rs", "README.md"]
+        );
+        // src/ui is closed, so its parent goes next
+        assert_eq!(tree.collapse(1), 0);
+        assert_eq!(paths(&tree), vec!["src", "README.md"]);
+    }
+
+    #[test]
+    fn collapse_leaves_top_level_files_alone() {
+        let mut tree = tree();
+        assert_eq!(tree.collapse(1), 1);
+        assert_eq!(tree.collapse(9), 9);
+        assert_eq!(paths(&tree), vec!["src", "README.md"]);
+    }
+}
@@ -0,0 +1,309 @@
+use crate::cache::default_cache_dir;
+use anyhow::{Context, anyhow, bail};
+use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+pub const DEFAULT_GRAPHQL_URL: &str = "https://api.github.com/graphql";
+
+const USAGE: &str = "Usage: github-tui-explorer [options]
+
+Options:
+  --username <name>      GitHub user whose repos are listed  [env: GITHUB_USERNAME]
+  --token <token>        personal access token                [env: GITHUB_TOKEN]
+  --host <hostname>      GitHub Enterprise Server host, e.g. ghe.example.com
+                         (REST at /api/v3, GraphQL at /api/graphql) [env: GITHUB_HOST]
+  --api-url <url>        REST API base URL, overrides --host  [env: GITHUB_API_URL]
+  --graphql-url <url>    GraphQL endpoint, overrides --host   [env: GITHUB_GRAPHQL_URL]
+  --cache-dir <dir>      response cache location, defaults to $XDG_CACHE_HOME/gte
+                                                              [env: GTE_CACHE_DIR]
+  --offline              browse previously fetched data only, no network
+                                                              [env: GTE_OFFLINE=1]
+  --connect-timeout <s>  seconds to establish a connection (default 10)
+                                                              [env: GTE_CONNECT_TIMEOUT]
+  --read-timeout <s>     seconds to wait for response data (default 30)
+                                                              [env: GTE_READ_TIMEOUT]
+  --retries <n>          retries for 5xx and connection errors (default 3)
+                                                              [env: GTE_MAX_RETRIES]
+  -h, --help             print this help";
+
+// Timeouts and retry policy for API requests
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkSettings {
+    pub connect_timeout: Duration,
+    pub read_timeout: Duration,
+    // retries of a GET that failed with a 5xx or never got a response
+    pub max_retries: u32,
+    // first retry delay, doubled on every further attempt
+    pub retry_base_delay: Duration,
+    pub retry_max_delay: Duration,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(30),
+            max_retries: 3,
+            retry_base_delay: Duration::from_millis(500),
+            retry_max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+// Runtime settings, resolved from CLI flags first, then the environment (and `.env`)
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub username: String,
+    pub token: String,
+    // REST base URL without trailing slash, e.g. `https://ghe.example.com/api/v3`
+    pub api_url: String,
+    pub graphql_url: String,
+    // None when no cache location could be determined: memory cache only
+    pub cache_dir: Option<PathBuf>,
+    pub offline: bool,
+    pub network: NetworkSettings,
+}
+
+impl Config {
+    pub fn load() -> anyhow::Result<Self> {
+        let args: Vec<String> = env::args().skip(1).collect();
+        if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+            println!("{}", USAGE);
+            std::process::exit(0);
+        }
+        Self::parse(args, |key| env::var(key).ok())
+    }
+
+    pub fn parse<I, F>(args: I, env: F) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut username = None;
+        let mut token = None;
+        let mut host = None;
+        let mut api_url = None;
+        let mut graphql_url = None;
+        let mut cache_dir = None;
+        let mut offline = false;
+        let mut connect_timeout = None;
+        let mut read_timeout = None;
+        let mut retries = None;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--offline" {
+                offline = true;
+                continue;
+            }
+
+            // accept both `--flag value` and `--flag=value`
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg.clone(), None),
+            };
+            let slot = match flag.as_str() {
+                "--username" => &mut username,
+                "--token" => &mut token,
+                "--host" => &mut host,
+                "--api-url" => &mut api_url,
+                "--graphql-url" => &mut graphql_url,
+                "--cache-dir" => &mut cache_dir,
+                "--connect-timeout" => &mut connect_timeout,
+                "--read-timeout" => &mut read_timeout,
+                "--retries" => &mut retries,
+                _ => bail!("unknown argument `{}`\n\n{}", arg, USAGE),
+            };
+            let value = match inline {
+                Some(value) => value,
+                None => args
+                    .next()
+                    .with_context(|| format!("missing value for `{}`", flag))?,
+            };
+            *slot = Some(value);
+        }
+
+        let username = username
+            .or_else(|| env("GITHUB_USERNAME"))
+            .ok_or_else(|| anyhow!("missing GITHUB_USERNAME"))?;
+        let offline = offline || env("GTE_OFFLINE").is_some_and(|v| v == "1" || v == "true");
+        // offline sessions never authenticate
+        let token = match token.or_else(|| env("GITHUB_TOKEN")) {
+            Some(token) => token,
+            None if offline => String::new(),
+            None => bail!("missing GITHUB_TOKEN"),
+        };
+        let cache_dir = cache_dir
+            .or_else(|| env("GTE_CACHE_DIR"))
+            .map(PathBuf::from)
+            .or_else(default_cache_dir);
+        let host = host
+            .or_else(|| env("GITHUB_HOST"))
+            .filter(|host| host != "github.com");
+
+        let api_url = api_url
+            .or_else(|| env("GITHUB_API_URL"))
+            .or_else(|| host.as_deref().map(enterprise_api_url))
+            .unwrap_or_else(|| DEFAULT_API_URL.to_string());
+        let graphql_url = graphql_url
+            .or_else(|| env("GITHUB_GRAPHQL_URL"))
+            .or_else(|| host.as_deref().map(enterprise_graphql_url))
+            .unwrap_or_else(|| graphql_url_for(&api_url));
+
+        let defaults = NetworkSettings::default();
+        let network = NetworkSettings {
+            connect_timeout: parse_setting(connect_timeout, &env, "GTE_CONNECT_TIMEOUT")?
+                .map_or(defaults.connect_timeout, Duration::from_secs),
+            read_timeout: parse_setting(read_timeout, &env, "GTE_READ_TIMEOUT")?
+                .map_or(defaults.read_timeout, Duration::from_secs),
+            max_retries: parse_setting(retries, &env, "GTE_MAX_RETRIES")?
+                .unwrap_or(defaults.max_retries),
+            ..defaults
+        };
+
+        Ok(Self {
+            username,
+            token,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            graphql_url: graphql_url.trim_end_matches('/').to_string(),
+            cache_dir,
+            offline,
+            network,
+        })
+    }
+}
+
+// numeric setting from its flag, falling back to the environment
+fn parse_setting<T, F>(flag: Option<String>, env: &F, key: &str) -> anyhow::Result<Option<T>>
+where
+    T: FromStr,
+    F: Fn(&str) -> Option<String>,
+{
+    let Some(value) = flag.or_else(|| env(key)) else {
+        return Ok(None);
+    };
+    match value.trim().parse() {
+        Ok(parsed) => Ok(Some(parsed)),
+        Err(_) => bail!("invalid value `{}` for {}", value, key),
+    }
+}
+
+fn host_base(host: &str) -> String {
+    let host = host.trim_end_matches('/');
+    if host.contains("://") {
+        host.to_string()
+    } else {
+        format!("https://{}", host)
+    }
+}
+
+fn enterprise_api_url(host: &str) -> String {
+    format!("{}/api/v3", host_base(host))
+}
+
+fn enterprise_graphql_url(host: &str) -> String {
+    format!("{}/api/graphql", host_base(host))
+}
+
+// GHES serves GraphQL at `/api/graphql` next to REST's `/api/v3`;
+// everything else (github.com, mock servers) at `<base>/graphql`
+fn graphql_url_for(api_url: &str) -> String {
+    let api_url = api_url.trim_end_matches('/');
+    if api_url == DEFAULT_API_URL {
+        return DEFAULT_GRAPHQL_URL.to_string();
+    }
+    match api_url.strip_suffix("/api/v3") {
+        Some(base) => format!("{}/api/graphql", base),
+        None => format!("{}/graphql", api_url),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn parse(args: &[&str], env: &[(&str, &str)]) -> anyhow::Result<Config> {
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        Config::parse(args.iter().map(|arg| arg.to_string()), |key| {
+            env.get(key).cloned()
+        })
+    }
+
+    #[test]
+    fn flags_win_over_the_environment() {
+        let config = parse(
+            &["--username", "octocat", "--token=flag-token"],
+            &[
+                ("GITHUB_USERNAME", "monalisa"),
+                ("GITHUB_TOKEN", "env-token"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(config.username, "octocat");
+        assert_eq!(config.token, "flag-token");
+        assert_eq!(config.api_url, DEFAULT_API_URL);
+        assert_eq!(config.graphql_url, DEFAULT_GRAPHQL_URL);
+        assert_eq!(config.network, NetworkSettings::default());
+    }
+
+    #[test]
+    fn enterprise_host_sets_both_endpoints() {
+        let config = parse(
+            &["--host", "ghe.example.com"],
+            &[("GITHUB_USERNAME", "octocat"), ("GITHUB_TOKEN", "t")],
+        )
+        .unwrap();
+        assert_eq!(config.api_url, "https://ghe.example.com/api/v3");
+        assert_eq!(config.graphql_url, "https://ghe.example.com/api/graphql");
+    }
+
+    #[test]
+    fn graphql_url_follows_a_custom_api_url() {
+        let config = parse(
+            &["--api-url", "http://localhost:8080/"],
+            &[("GITHUB_USERNAME", "octocat"), ("GITHUB_TOKEN", "t")],
+        )
+        .unwrap();
+        assert_eq!(config.api_url, "http://localhost:8080");
+        assert_eq!(config.graphql_url, "http://localhost:8080/graphql");
+    }
+
+    #[test]
+    fn network_settings_are_parsed() {
+        let config = parse(
+            &["--read-timeout", "5", "--retries=0"],
+            &[
+                ("GITHUB_USERNAME", "octocat"),
+                ("GITHUB_TOKEN", "t"),
+                ("GTE_CONNECT_TIMEOUT", "2"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(config.network.connect_timeout, Duration::from_secs(2));
+        assert_eq!(config.network.read_timeout, Duration::from_secs(5));
+        assert_eq!(config.network.max_retries, 0);
+    }
+
+    #[test]
+    fn offline_needs_no_token() {
+        let config = parse(&["--offline"], &[("GITHUB_USERNAME", "octocat")]).unwrap();
+        assert!(config.offline);
+        assert!(config.token.is_empty());
+        assert!(parse(&[], &[("GITHUB_USERNAME", "octocat")]).is_err());
+    }
+
+    #[test]
+    fn bad_arguments_are_rejected() {
+        let env = [("GITHUB_USERNAME", "octocat"), ("GITHUB_TOKEN", "t")];
+        assert!(parse(&["--verbose"], &env).is_err());
+        assert!(parse(&["--host"], &env).is_err());
+        assert!(parse(&["--retries", "many"], &env).is_err());
+    }
+}
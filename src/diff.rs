@@ -0,0 +1,323 @@
+use crate::highlight::Highlighter;
+use crate::models::PullFile;
+use ratatui::text::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineKind {
+    // `@@ -1,4 +1,5 @@ fn main()`
+    Hunk,
+    Context,
+    Added,
+    Removed,
+}
+
+// One line of a unified diff with its line numbers on either side
+#[derive(Debug, Clone)]
+pub struct DiffLine {
+    pub kind: LineKind,
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+    pub text: String,
+    // syntax colored `text`, empty for hunk headers
+    pub spans: Vec<Span<'static>>,
+}
+
+// A changed file, parsed and highlighted once when the diff arrives
+#[derive(Debug, Clone)]
+pub struct DiffFile {
+    pub file: PullFile,
+    pub lines: Vec<DiffLine>,
+    // rows of the side-by-side layout: (old side, new side) as indices into `lines`
+    pub pairs: Vec<(Option<usize>, Option<usize>)>,
+}
+
+impl DiffFile {
+    pub fn new(file: PullFile) -> Self {
+        let lines = file
+            .patch
+            .as_deref()
+            .map(|patch| parse_patch(&file.filename, patch))
+            .unwrap_or_default();
+        let pairs = pair_lines(&lines);
+        Self { file, lines, pairs }
+    }
+
+    // row of every hunk header, in the unified or side-by-side layout
+    pub fn hunk_rows(&self, side_by_side: bool) -> Vec<usize> {
+        if side_by_side {
+            self.pairs
+                .iter()
+                .enumerate()
+                .filter(|(_, (old, _))| old.is_some_and(|i| self.lines[i].kind == LineKind::Hunk))
+                .map(|(row, _)| row)
+                .collect()
+        } else {
+            self.lines
+                .iter()
+                .enumerate()
+                .filter(|(_, line)| line.kind == LineKind::Hunk)
+                .map(|(row, _)| row)
+                .collect()
+        }
+    }
+
+    pub fn row_count(&self, side_by_side: bool) -> usize {
+        if side_by_side {
+            self.pairs.len()
+        } else {
+            self.lines.len()
+        }
+    }
+
+    // the line shown by a row; in a side-by-side row the old or new cell,
+    // falling back to the other one where it is blank
+    pub fn row_line(&self, row: usize, side_by_side: bool, old_side: bool) -> Option<&DiffLine> {
+        let index = if side_by_side {
+            let &(old, new) = self.pairs.get(row)?;
+            if old_side { old.or(new) } else { new.or(old) }
+        } else {
+            Some(row)
+        };
+        self.lines.get(index?)
+    }
+
+    // where a comment on a row goes, as GitHub's side and line; None for hunk headers
+    pub fn anchor(
+        &self,
+        row: usize,
+        side_by_side: bool,
+        old_side: bool,
+    ) -> Option<(&'static str, u32)> {
+        let line = self.row_line(row, side_by_side, old_side)?;
+        match line.kind {
+            LineKind::Hunk => None,
+            LineKind::Removed => Some(("LEFT", line.old_line?)),
+            LineKind::Added => Some(("RIGHT", line.new_line?)),
+            // unchanged lines can be commented on from either version
+            LineKind::Context if side_by_side && old_side => Some(("LEFT", line.old_line?)),
+            LineKind::Context => Some(("RIGHT", line.new_line?)),
+        }
+    }
+
+    // row showing `line` of the old (LEFT) or new (RIGHT) version, if it is in the diff
+    pub fn row_of(&self, side: &str, line: u32, side_by_side: bool) -> Option<usize> {
+        let index = self.lines.iter().position(|l| match l.kind {
+            LineKind::Hunk => false,
+            LineKind::Removed => side == "LEFT" && l.old_line == Some(line),
+            LineKind::Added => side == "RIGHT" && l.new_line == Some(line),
+            LineKind::Context if side == "LEFT" => l.old_line == Some(line),
+            LineKind::Context => l.new_line == Some(line),
+        })?;
+        if side_by_side {
+            self.pairs
+                .iter()
+                .position(|&(old, new)| old == Some(index) || new == Some(index))
+        } else {
+            Some(index)
+        }
+    }
+
+    // whether the rows from `a` to `b` belong to one hunk, as a comment range must
+    pub fn same_hunk(&self, a: usize, b: usize, side_by_side: bool) -> bool {
+        let (first, last) = (a.min(b), a.max(b));
+        !self
+            .hunk_rows(side_by_side)
+            .into_iter()
+            .any(|row| row > first && row <= last)
+    }
+}
+
+// `@@ -12,7 +12,9 @@` -> (12, 12)
+fn hunk_start(header: &str) -> Option<(u32, u32)> {
+    let mut ranges = header.strip_prefix("@@ ")?.split_whitespace();
+    let old = ranges.next()?.strip_prefix('-')?;
+    let new = ranges.next()?.strip_prefix('+')?;
+    let start = |range: &str| range.split(',').next()?.parse().ok();
+    Some((start(old)?, start(new)?))
+}
+
+// Parse a `patch` from the pull request files API. Removed and added lines get
+// their own highlighters, since they belong to two different versions of the file.
+pub fn parse_patch(path: &str, patch: &str) -> Vec<DiffLine> {
+    let mut old_highlighter = Highlighter::for_path(path);
+    let mut new_highlighter = Highlighter::for_path(path);
+    let (mut old_line, mut new_line) = (0, 0);
+    let mut lines = Vec::new();
+
+    for raw in patch.lines() {
+        if raw.starts_with("@@") {
+            if let Some((old, new)) = hunk_start(raw) {
+                (old_line, new_line) = (old, new);
+            }
+            lines.push(DiffLine {
+                kind: LineKind::Hunk,
+                old_line: None,
+                new_line: None,
+                text: raw.to_string(),
+                spans: Vec::new(),
+            });
+            continue;
+        }
+        // `\ No newline at end of file`
+        if raw.starts_with('\\') {
+            continue;
+        }
+
+        let (kind, text) = match raw.split_at_checked(1) {
+            Some(("+", text)) => (LineKind::Added, text),
+            Some(("-", text)) => (LineKind::Removed, text),
+            Some((_, text)) => (LineKind::Context, text),
+            None => (LineKind::Context, ""),
+        };
+        let line = match kind {
+            LineKind::Added => {
+                new_line += 1;
+                DiffLine {
+                    kind,
+                    old_line: None,
+                    new_line: Some(new_line - 1),
+                    text: text.to_string(),
+                    spans: new_highlighter.highlight(text),
+                }
+            }
+            LineKind::Removed => {
+                old_line += 1;
+                DiffLine {
+                    kind,
+                    old_line: Some(old_line - 1),
+                    new_line: None,
+                    text: text.to_string(),
+                    spans: old_highlighter.highlight(text),
+                }
+            }
+            _ => {
+                old_line += 1;
+                new_line += 1;
+                // context exists in both versions: keep both parsers in step
+                old_highlighter.highlight(text);
+                DiffLine {
+                    kind,
+                    old_line: Some(old_line - 1),
+                    new_line: Some(new_line - 1),
+                    text: text.to_string(),
+                    spans: new_highlighter.highlight(text),
+                }
+            }
+        };
+        lines.push(line);
+    }
+
+    lines
+}
+
+// Line up removals with the additions that replace them, so a changed block
+// reads across the two columns; context and hunk headers span both sides
+fn pair_lines(lines: &[DiffLine]) -> Vec<(Option<usize>, Option<usize>)> {
+    let mut pairs = Vec::new();
+    let mut removed: Vec<usize> = Vec::new();
+    let mut added: Vec<usize> = Vec::new();
+
+    let flush = |pairs: &mut Vec<_>, removed: &mut Vec<usize>, added: &mut Vec<usize>| {
+        for i in 0..removed.len().max(added.len()) {
+            pairs.push((removed.get(i).copied(), added.get(i).copied()));
+        }
+        removed.clear();
+        added.clear();
+    };
+
+    for (i, line) in lines.iter().enumerate() {
+        match line.kind {
+            LineKind::Removed => {
+                // a removal after additions starts a new block
+                if !added.is_empty() {
+                    flush(&mut pairs, &mut removed, &mut added);
+                }
+                removed.push(i);
+            }
+            LineKind::Added => added.push(i),
+            LineKind::Context | LineKind::Hunk => {
+                flush(&mut pairs, &mut removed, &mut added);
+                pairs.push((Some(i), Some(i)));
+            }
+        }
+    }
+    flush(&mut pairs, &mut removed, &mut added);
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATCH: &str = "@@ -1,4 +1,5 @@ fn main()\n fn main() {\n-    old();\n+    new();\n+    more();\n }\n\\ No newline at end of file";
+
+    fn kinds(lines: &[DiffLine]) -> Vec<LineKind> {
+        lines.iter().map(|line| line.kind).collect()
+    }
+
+    #[test]
+    fn parse_patch_numbers_both_sides() {
+        let lines = parse_patch("main.rs", PATCH);
+        assert_eq!(
+            kinds(&lines),
+            vec![
+                LineKind::Hunk,
+                LineKind::Context,
+                LineKind::Removed,
+                LineKind::Added,
+                LineKind::Added,
+                LineKind::Context,
+            ]
+        );
+        let numbers: Vec<(Option<u32>, Option<u32>)> = lines
+            .iter()
+            .map(|line| (line.old_line, line.new_line))
+            .collect();
+        assert_eq!(
+            numbers,
+            vec![
+                (None, None),
+                (Some(1), Some(1)),
+                (Some(2), None),
+                (None, Some(2)),
+                (None, Some(3)),
+                (Some(3), Some(4)),
+            ]
+        );
+        assert_eq!(lines[3].text, "    new();");
+    }
+
+    #[test]
+    fn parse_patch_restarts_at_every_hunk() {
+        let lines = parse_patch("notes.txt", "@@ -10,2 +12,2 @@\n a\n-b\n+c");
+        assert_eq!(lines[1].old_line, Some(10));
+        assert_eq!(lines[1].new_line, Some(12));
+        assert_eq!(lines[2].old_line, Some(11));
+        assert_eq!(lines[3].new_line, Some(13));
+    }
+
+    #[test]
+    fn pair_lines_puts_replacements_side_by_side() {
+        let lines = parse_patch("main.rs", PATCH);
+        assert_eq!(
+            pair_lines(&lines),
+            vec![
+                (Some(0), Some(0)),
+                (Some(1), Some(1)),
+                (Some(2), Some(3)),
+                (None, Some(4)),
+                (Some(5), Some(5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn pair_lines_splits_blocks_at_a_removal_after_additions() {
+        let lines = parse_patch("notes.txt", "@@ -1,2 +1,2 @@\n-a\n+b\n-c\n+d");
+        assert_eq!(
+            pair_lines(&lines),
+            vec![(Some(0), Some(0)), (Some(1), Some(2)), (Some(3), Some(4))]
+        );
+    }
+}
@@ -0,0 +1,392 @@
+use crate::api_status::format_duration;
+use crate::cache::now_secs;
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use serde::Deserialize;
+use std::fmt;
+use std::time::Duration;
+
+// Error body GitHub sends with non-2xx responses
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ErrorBody {
+    message: Option<String>,
+    documentation_url: Option<String>,
+    #[serde(default)]
+    errors: Vec<serde_json::Value>,
+}
+
+// What GitHub said about a failed request
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorResponse {
+    pub status: u16,
+    pub message: String,
+    pub documentation_url: Option<String>,
+    // field-level details of a 422
+    pub details: Vec<String>,
+    // `X-OAuth-Scopes`: what the token has
+    pub token_scopes: Option<String>,
+    // `X-Accepted-OAuth-Scopes`: what the endpoint wants
+    pub accepted_scopes: Option<String>,
+    // path of the request that failed, e.g. `/repos/o/r/issues/5`
+    pub resource: Option<String>,
+}
+
+impl ErrorResponse {
+    pub fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+            documentation_url: None,
+            details: Vec::new(),
+            token_scopes: None,
+            accepted_scopes: None,
+            resource: None,
+        }
+    }
+
+    pub fn from_parts(status: StatusCode, headers: &HeaderMap, body: &str, path: &str) -> Self {
+        let parsed: ErrorBody = serde_json::from_str(body).unwrap_or_default();
+        let scopes = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+
+        Self {
+            status: status.as_u16(),
+            message: parsed.message.unwrap_or_else(|| {
+                status
+                    .canonical_reason()
+                    .unwrap_or("unknown error")
+                    .to_string()
+            }),
+            documentation_url: parsed.documentation_url,
+            details: parsed.errors.iter().map(describe_detail).collect(),
+            token_scopes: scopes("x-oauth-scopes"),
+            accepted_scopes: scopes("x-accepted-oauth-scopes"),
+            resource: Some(path.to_string()),
+        }
+    }
+
+    // scopes the endpoint accepts that the token doesn't have
+    fn missing_scopes(&self) -> Vec<String> {
+        let Some(accepted) = &self.accepted_scopes else {
+            return Vec::new();
+        };
+        let granted: Vec<&str> = self
+            .token_scopes
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .collect();
+        let accepted: Vec<&str> = accepted
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        // any one accepted scope is enough
+        if accepted.iter().any(|scope| granted.contains(scope)) {
+            return Vec::new();
+        }
+        accepted.into_iter().map(str::to_string).collect()
+    }
+}
+
+// 422 details are either plain strings or `{resource, field, code, message}`
+fn describe_detail(detail: &serde_json::Value) -> String {
+    if let Some(text) = detail.as_str() {
+        return text.to_string();
+    }
+    if let Some(message) = detail.get("message").and_then(|m| m.as_str()) {
+        return message.to_string();
+    }
+    let field = detail.get("field").and_then(|f| f.as_str()).unwrap_or("?");
+    let code = detail
+        .get("code")
+        .and_then(|c| c.as_str())
+        .unwrap_or("invalid");
+    format!("{} is {}", field, code.replace('_', " "))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    // 401: missing, expired or revoked token
+    Unauthorized(Box<ErrorResponse>),
+    // 403 that isn't a rate limit: SSO enforcement, missing permissions, ...
+    Forbidden(Box<ErrorResponse>),
+    // 403/429 still rate limited after waiting, `reset` as unix timestamp
+    RateLimited {
+        response: Box<ErrorResponse>,
+        reset: Option<u64>,
+    },
+    // 404: missing resource, or a private one the token can't see
+    NotFound(Box<ErrorResponse>),
+    // 422: e.g. an invalid search query
+    Validation(Box<ErrorResponse>),
+    // any other non-2xx status
+    Http(Box<ErrorResponse>),
+    // the request never got a response: DNS, TLS, connection, timeout
+    Network(String),
+    // a response that doesn't look like what we asked for
+    Decode(String),
+    // offline mode without a cached copy
+    NotCached(String),
+    // a GraphQL query GitHub rejected
+    GraphQL(String),
+    // a change attempted in offline mode
+    Offline(String),
+}
+
+impl ApiError {
+    pub fn from_response(status: StatusCode, headers: &HeaderMap, body: &str, path: &str) -> Self {
+        let response = Box::new(ErrorResponse::from_parts(status, headers, body, path));
+        match status {
+            StatusCode::UNAUTHORIZED => ApiError::Unauthorized(response),
+            StatusCode::NOT_FOUND => ApiError::NotFound(response),
+            StatusCode::UNPROCESSABLE_ENTITY => ApiError::Validation(response),
+            StatusCode::TOO_MANY_REQUESTS => ApiError::RateLimited {
+                response,
+                reset: reset_header(headers),
+            },
+            StatusCode::FORBIDDEN if is_rate_limit(headers, &response) => ApiError::RateLimited {
+                response,
+                reset: reset_header(headers),
+            },
+            StatusCode::FORBIDDEN => ApiError::Forbidden(response),
+            _ => ApiError::Http(response),
+        }
+    }
+
+    pub fn response(&self) -> Option<&ErrorResponse> {
+        match self {
+            ApiError::Unauthorized(response)
+            | ApiError::Forbidden(response)
+            | ApiError::RateLimited { response, .. }
+            | ApiError::NotFound(response)
+            | ApiError::Validation(response)
+            | ApiError::Http(response) => Some(response.as_ref()),
+            _ => None,
+        }
+    }
+
+    // short headline for error panels
+    pub fn title(&self) -> &'static str {
+        match self {
+            ApiError::Unauthorized(_) => "Authentication failed",
+            ApiError::Forbidden(_) => "Access denied",
+            ApiError::RateLimited { .. } => "Rate limit exceeded",
+            ApiError::NotFound(_) => "Not found",
+            ApiError::Validation(_) => "Invalid request",
+            ApiError::Http(_) => "GitHub error",
+            ApiError::Network(_) => "Network error",
+            ApiError::Decode(_) => "Unexpected response",
+            ApiError::NotCached(_) => "Not available offline",
+            ApiError::GraphQL(_) => "GraphQL query failed",
+            ApiError::Offline(_) => "Offline",
+        }
+    }
+
+    // what the user can do about it
+    pub fn hint(&self) -> Option<String> {
+        let missing_scopes = self
+            .response()
+            .map(ErrorResponse::missing_scopes)
+            .unwrap_or_default();
+        if !missing_scopes.is_empty() {
+            return Some(format!(
+                "Token lacks the `{}` scope; create a token with it and update GITHUB_TOKEN",
+                missing_scopes.join("` or `")
+            ));
+        }
+
+        match self {
+            ApiError::Unauthorized(_) => Some(String::from(
+                "Check that GITHUB_TOKEN is set to a valid, unexpired personal access token",
+            )),
+            ApiError::Forbidden(response) if response.message.contains("SAML") => Some(
+                String::from("Authorize the token for this organization's SAML single sign-on"),
+            ),
+            ApiError::Forbidden(_) => Some(String::from(
+                "The token doesn't have permission for this resource",
+            )),
+            ApiError::RateLimited { reset, .. } => Some(match reset {
+                Some(reset) => format!(
+                    "Quota refills in {}; authenticated requests get a higher limit",
+                    format_duration(Duration::from_secs(reset.saturating_sub(now_secs())))
+                ),
+                None => String::from("Wait a minute before trying again"),
+            }),
+            ApiError::NotFound(response) => Some(not_found_hint(response)),
+            ApiError::Validation(_) => Some(String::from(
+                "Check the search syntax, e.g. `tui language:rust stars:>100`",
+            )),
+            ApiError::Network(_) => Some(String::from(
+                "Check your connection, or run with --offline to browse cached data",
+            )),
+            ApiError::NotCached(_) => Some(String::from(
+                "Open it once while online to make it available offline",
+            )),
+            ApiError::GraphQL(_) => Some(String::from(
+                "The GraphQL endpoint may be unavailable; check GITHUB_GRAPHQL_URL",
+            )),
+            ApiError::Offline(_) => Some(String::from("Restart without --offline to make changes")),
+            ApiError::Http(_) | ApiError::Decode(_) => None,
+        }
+    }
+}
+
+// A 404 means something different depending on what was asked for: a user
+// that doesn't exist, an item that is gone, or a repo the token can't see
+fn not_found_hint(response: &ErrorResponse) -> String {
+    let path = response.resource.as_deref().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    // GitHub Enterprise serves the API under a prefix such as /api/v3
+    let start = segments
+        .iter()
+        .position(|s| *s == "users" || *s == "repos")
+        .unwrap_or(segments.len());
+    let message = &response.message;
+
+    match &segments[start..] {
+        ["users", user, ..] => format!(
+            "There is no GitHub user `{}`; check the spelling of the username",
+            user
+        ),
+        ["repos", owner, repo, "issues" | "pulls", number, ..] => format!(
+            "#{} may have been deleted or moved out of {}/{}",
+            number, owner, repo
+        ),
+        ["repos", owner, repo, "milestones", number] => format!(
+            "Milestone {} may have been deleted from {}/{}",
+            number, owner, repo
+        ),
+        ["repos", _, _, "commits", ..] => {
+            String::from("The commit is no longer in the repository, e.g. after a force push")
+        }
+        // GraphQL names what it couldn't find in the message instead
+        _ if message.contains("Could not resolve to a User") => {
+            String::from("There is no GitHub user by that name; check the spelling of the username")
+        }
+        _ if message.contains("Could not resolve to a PullRequest")
+            || message.contains("Could not resolve to an Issue")
+            || message.starts_with("pull request #") =>
+        {
+            String::from("It may have been deleted or moved to another repository")
+        }
+        _ => String::from(
+            "The repository may be private: the token needs the `repo` scope to see it",
+        ),
+    }
+}
+
+fn reset_header(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get("x-ratelimit-reset")?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+fn is_rate_limit(headers: &HeaderMap, response: &ErrorResponse) -> bool {
+    let exhausted = headers
+        .get("x-ratelimit-remaining")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim() == "0");
+    exhausted
+        || headers.contains_key(RETRY_AFTER)
+        || response.message.to_lowercase().contains("rate limit")
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(response) = self.response() {
+            write!(
+                f,
+                "{} ({}): {}",
+                self.title(),
+                response.status,
+                response.message
+            )?;
+            if !response.details.is_empty() {
+                write!(f, " - {}", response.details.join("; "))?;
+            }
+            return Ok(());
+        }
+
+        match self {
+            ApiError::Network(message)
+            | ApiError::Decode(message)
+            | ApiError::NotCached(message)
+            | ApiError::GraphQL(message)
+            | ApiError::Offline(message) => write!(f, "{}: {}", self.title(), message),
+            _ => write!(f, "{}", self.title()),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            ApiError::Decode(e.to_string())
+        } else {
+            ApiError::Network(e.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(e: serde_json::Error) -> Self {
+        ApiError::Decode(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn not_found(path: &str) -> ApiError {
+        let body = r#"{"message": "Not Found"}"#;
+        ApiError::from_response(StatusCode::NOT_FOUND, &HeaderMap::new(), body, path)
+    }
+
+    #[test]
+    fn a_missing_user_is_blamed_on_the_username() {
+        let hint = not_found("/users/octocta/repos").hint().unwrap();
+        assert!(hint.contains("no GitHub user `octocta`"), "{}", hint);
+        // the prefix of a GitHub Enterprise API is skipped
+        let hint = not_found("/api/v3/users/octocta/repos").hint().unwrap();
+        assert!(hint.contains("`octocta`"), "{}", hint);
+    }
+
+    #[test]
+    fn a_missing_item_is_told_apart_from_a_hidden_repo() {
+        let hint = not_found("/repos/octocat/hello/issues/7").hint().unwrap();
+        assert!(hint.starts_with("#7 may have been deleted"), "{}", hint);
+        let hint = not_found("/repos/octocat/hello/milestones/2")
+            .hint()
+            .unwrap();
+        assert!(hint.starts_with("Milestone 2"), "{}", hint);
+        let hint = not_found("/repos/octocat/hello/issues").hint().unwrap();
+        assert!(hint.contains("may be private"), "{}", hint);
+    }
+
+    #[test]
+    fn graphql_not_found_is_read_from_the_message() {
+        let error = ApiError::NotFound(Box::new(ErrorResponse::new(
+            404,
+            "Could not resolve to a User with the login of 'octocta'.",
+        )));
+        assert!(error.hint().unwrap().contains("no GitHub user"));
+        let error = ApiError::NotFound(Box::new(ErrorResponse::new(
+            404,
+            "Could not resolve to a Repository with the name 'octocat/secret'.",
+        )));
+        assert!(error.hint().unwrap().contains("may be private"));
+    }
+}
@@ -1,15 +1,18 @@
-use crate::app::{App, AppMode};
-use crossterm::event::{KeyCode, KeyEvent};
+use crate::app::{App, AppMode, IssueField, RepoDetailState};
+use crate::editor::TextEditor;
+use crate::pull_action::{PullActionField, PullActionKind};
+use crate::triage::TriageAction;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-pub async fn handle_key_event(app: &mut App, key: KeyEvent) -> bool {
-    if key.code == KeyCode::Char('q') {
+pub fn handle_key_event(app: &mut App, key: KeyEvent) -> bool {
+    if key.code == KeyCode::Char('q') && !app.is_typing() {
         return true;
     }
 
     match app.mode {
         AppMode::RepoList => {
             if key.code == KeyCode::Enter {
-                app.select_current_repo().await;
+                app.select_current_repo();
             } else {
                 handle_repo_list_keys(app, key);
             }
@@ -17,21 +20,120 @@ pub async fn handle_key_event(app: &mut App, key: KeyEvent) -> bool {
         AppMode::RepoDetail => handle_repo_detail_keys(app, key),
         AppMode::Search => {
             if key.code == KeyCode::Enter {
-                app.search_repositories().await;
+                app.search_repositories();
             } else {
                 handle_search_keys(app, key);
             }
         }
-        AppMode::IssuePopUp => handle_issue_popup_keys(app, key),
+        AppMode::IssuePopUp | AppMode::PRPopUp => handle_popup_keys(app, key),
+        AppMode::FilterPopUp => handle_filter_keys(app, key),
+        AppMode::DiffView => handle_diff_keys(app, key),
+        AppMode::CommentEditor => handle_comment_keys(app, key),
+        AppMode::IssueForm => handle_issue_form_keys(app, key),
+        AppMode::TriagePopUp => handle_triage_keys(app, key),
+        AppMode::PullActionPopUp => handle_pull_action_keys(app, key),
+        AppMode::RefPicker => handle_ref_picker_keys(app, key),
     }
 
     false
 }
 
-pub fn handle_issue_popup_keys(app: &mut App, key: KeyEvent) {
+pub fn handle_popup_keys(app: &mut App, key: KeyEvent) {
     match key.code {
         KeyCode::Esc => app.back_to_details(),
-        _ => {}
+        KeyCode::Char('d') if app.mode == AppMode::PRPopUp => app.open_diff(),
+        KeyCode::Char('c') => app.open_comment_editor(),
+        KeyCode::Down | KeyCode::Char('j') => app.scroll_popup_down(),
+        KeyCode::Up | KeyCode::Char('k') => app.scroll_popup_up(),
+        _ if app.mode == AppMode::PRPopUp => handle_pull_popup_keys(app, key),
+        _ => handle_triage_action_keys(app, key),
+    }
+}
+
+// review, merge and branch keys of the PR popup
+fn handle_pull_popup_keys(app: &mut App, key: KeyEvent) {
+    let kind = match key.code {
+        KeyCode::Char('a') => PullActionKind::Approve,
+        KeyCode::Char('r') => PullActionKind::RequestChanges,
+        KeyCode::Char('v') => PullActionKind::Comment,
+        KeyCode::Char('M') => PullActionKind::Merge,
+        KeyCode::Char('A') => PullActionKind::AutoMerge,
+        KeyCode::Char('D') => PullActionKind::DeleteBranch,
+        KeyCode::Char('w') => return app.toggle_pull_draft(),
+        KeyCode::Char('C') => return app.open_pull_commits(),
+        _ => return,
+    };
+    app.open_pull_action(kind);
+}
+
+pub fn handle_pull_action_keys(app: &mut App, key: KeyEvent) {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let field = app.pull_action_field();
+    match key.code {
+        KeyCode::Esc => app.close_pull_action(),
+        KeyCode::Char('s') if ctrl => app.confirm_pull_action(),
+        KeyCode::Char('e') if ctrl => app.request_external_edit(),
+        KeyCode::Enter
+            if !matches!(
+                field,
+                Some(PullActionField::Title | PullActionField::Message)
+            ) =>
+        {
+            app.confirm_pull_action()
+        }
+        _ => {
+            let Some(action) = app.pull_action_mut() else {
+                return;
+            };
+            match (key.code, field) {
+                (KeyCode::Tab, _) => action.step_field(true),
+                (KeyCode::BackTab, _) => action.step_field(false),
+                (KeyCode::Right | KeyCode::Char(' '), Some(PullActionField::Method)) => {
+                    action.cycle_method(true)
+                }
+                (KeyCode::Left, Some(PullActionField::Method)) => action.cycle_method(false),
+                (KeyCode::Char(' '), Some(PullActionField::DeleteBranch)) => {
+                    action.delete_branch = !action.delete_branch
+                }
+                (KeyCode::Enter, Some(PullActionField::Title)) => action.step_field(true),
+                (KeyCode::Backspace, Some(PullActionField::Title)) => {
+                    action.title.pop();
+                }
+                (KeyCode::Char(c), Some(PullActionField::Title)) if !ctrl => action.title.push(c),
+                (_, Some(PullActionField::Message)) => edit_text(&mut action.editor, key),
+                _ => {}
+            }
+        }
+    }
+}
+
+// close, label, assign, milestone and lock keys of the issue table and popup
+fn handle_triage_action_keys(app: &mut App, key: KeyEvent) {
+    let action = match key.code {
+        KeyCode::Char('x') => TriageAction::Close,
+        KeyCode::Char('l') => TriageAction::Labels,
+        KeyCode::Char('a') => TriageAction::Assignees,
+        KeyCode::Char('m') => TriageAction::Milestone,
+        KeyCode::Char('L') => TriageAction::Lock,
+        _ => return,
+    };
+    app.open_triage(action);
+}
+
+pub fn handle_triage_keys(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.close_triage(),
+        KeyCode::Enter => app.apply_triage(),
+        _ => {
+            if let Some(picker) = app.triage_picker() {
+                match key.code {
+                    KeyCode::Down | KeyCode::Char('j') => picker.down(),
+                    KeyCode::Up | KeyCode::Char('k') => picker.up(),
+                    KeyCode::Char(' ') => picker.toggle(),
+                    _ => {}
+                }
+            }
+        }
     }
 }
 
@@ -45,13 +147,19 @@ pub fn handle_repo_list_keys(app: &mut App, key: KeyEvent) {
 }
 
 fn handle_issue_list_keys(app: &mut App, key: KeyEvent) {
-    match key.code {
-        KeyCode::Char('/') => app.enter_search_mode(),
-        _ => {}
+    if key.code == KeyCode::Char('/') {
+        app.enter_search_mode();
+    } else {
+        handle_triage_action_keys(app, key);
     }
 }
 
 pub fn handle_repo_detail_keys(app: &mut App, key: KeyEvent) {
+    match app.detail_mode {
+        RepoDetailState::Code => return handle_code_keys(app, key),
+        RepoDetailState::Commits => return handle_commits_tab_keys(app, key),
+        _ => {}
+    }
     match key.code {
         KeyCode::Down | KeyCode::Char('j') => {
             app.next();
@@ -61,11 +169,176 @@ pub fn handle_repo_detail_keys(app: &mut App, key: KeyEvent) {
         }
         KeyCode::Esc => app.back_to_list(),
         KeyCode::Tab => app.toggle_detail_mode(),
-        KeyCode::Enter => app.open_issue_popup(),
+        KeyCode::Char('p') => app.toggle_issue_prs(),
+        KeyCode::Char('f') => app.open_filter_form(),
+        KeyCode::Enter => app.open_selected(),
+        KeyCode::Char('d') => app.open_diff(),
+        KeyCode::Char('C') => app.open_pull_commits(),
+        KeyCode::Char('n') => app.open_issue_form(),
         _ => handle_issue_list_keys(app, key),
     }
 }
 
+fn handle_commits_tab_keys(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Down | KeyCode::Char('j') => app.next(),
+        KeyCode::Up | KeyCode::Char('k') => app.previous(),
+        KeyCode::Enter => app.open_selected(),
+        KeyCode::Char('f') => app.open_filter_form(),
+        KeyCode::Char('b') => app.open_ref_picker(),
+        KeyCode::Tab => app.toggle_detail_mode(),
+        KeyCode::Esc => app.close_commits(),
+        _ => {}
+    }
+}
+
+// keys of the code tab, for the tree or the file open beside it
+fn handle_code_keys(app: &mut App, key: KeyEvent) {
+    let viewing = app.code.as_ref().is_some_and(|code| code.viewing);
+    match key.code {
+        KeyCode::Down | KeyCode::Char('j') => app.next(),
+        KeyCode::Up | KeyCode::Char('k') => app.previous(),
+        KeyCode::PageDown => app.move_in_code(20),
+        KeyCode::PageUp => app.move_in_code(-20),
+        KeyCode::Char('g') => app.code_jump(false),
+        KeyCode::Char('G') => app.code_jump(true),
+        KeyCode::Enter => app.open_code_entry(true),
+        KeyCode::Right | KeyCode::Char('l') => app.open_code_entry(false),
+        KeyCode::Left | KeyCode::Char('h') => app.collapse_code(),
+        KeyCode::Esc if viewing => app.collapse_code(),
+        KeyCode::Esc => app.back_to_list(),
+        KeyCode::Tab => app.toggle_detail_mode(),
+        KeyCode::Char('b') => app.open_ref_picker(),
+        KeyCode::Char('y') => app.copy_permalink(),
+        _ => {}
+    }
+}
+
+pub fn handle_ref_picker_keys(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.close_ref_picker(),
+        KeyCode::Enter => app.choose_ref(),
+        _ => {
+            let Some(picker) = app.ref_picker_mut() else {
+                return;
+            };
+            match key.code {
+                KeyCode::Down => picker.down(),
+                KeyCode::Up => picker.up(),
+                KeyCode::Backspace => picker.backspace(),
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    picker.input(c)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+pub fn handle_diff_keys(app: &mut App, key: KeyEvent) {
+    // a single commit's diff can't be reviewed
+    let review = app.diff.as_ref().is_some_and(|diff| diff.sha.is_none());
+    match key.code {
+        KeyCode::Esc => app.close_diff(),
+        KeyCode::Down | KeyCode::Char('j') => app.scroll_diff(1),
+        KeyCode::Up | KeyCode::Char('k') => app.scroll_diff(-1),
+        KeyCode::PageDown => app.scroll_diff(20),
+        KeyCode::PageUp => app.scroll_diff(-20),
+        KeyCode::Tab => app.next_diff_file(),
+        KeyCode::BackTab => app.previous_diff_file(),
+        KeyCode::Char(']') => app.next_hunk(),
+        KeyCode::Char('[') => app.previous_hunk(),
+        KeyCode::Char('s') => app.cycle_diff_layout(),
+        KeyCode::Left | KeyCode::Char('h') => app.set_diff_side(true),
+        KeyCode::Right | KeyCode::Char('l') => app.set_diff_side(false),
+        KeyCode::Char('v') if review => app.toggle_diff_range(),
+        KeyCode::Char('c') if review => app.open_line_comment(),
+        KeyCode::Char('x') if review => app.discard_line_comment(),
+        KeyCode::Char('r') if review => app.toggle_thread_resolved(),
+        KeyCode::Char('S') if review => app.submit_pending_review(),
+        _ => {}
+    }
+}
+
+pub fn handle_comment_keys(app: &mut App, key: KeyEvent) {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Esc => app.close_comment_editor(),
+        KeyCode::Char('s') if ctrl => app.submit_comment(),
+        KeyCode::Char('p') if ctrl => app.toggle_comment_preview(),
+        KeyCode::Char('e') if ctrl => app.request_external_edit(),
+        _ => {
+            if let Some(editor) = app.comment_editor() {
+                edit_text(editor, key);
+            }
+        }
+    }
+}
+
+pub fn handle_issue_form_keys(app: &mut App, key: KeyEvent) {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Esc => app.close_issue_form(),
+        KeyCode::Char('s') if ctrl => app.submit_issue(),
+        KeyCode::Char('e') if ctrl => app.request_external_edit(),
+        KeyCode::Tab => app.next_issue_field(),
+        KeyCode::BackTab => app.previous_issue_field(),
+        _ => handle_issue_field_keys(app, key),
+    }
+}
+
+// keys for whichever field of the issue form has focus
+fn handle_issue_field_keys(app: &mut App, key: KeyEvent) {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match app.issue_field() {
+        Some(IssueField::Template) => match key.code {
+            KeyCode::Right | KeyCode::Char(' ') => app.cycle_issue_template(true),
+            KeyCode::Left => app.cycle_issue_template(false),
+            _ => {}
+        },
+        Some(IssueField::Title) => match key.code {
+            KeyCode::Enter => app.next_issue_field(),
+            KeyCode::Backspace => app.issue_title_backspace(),
+            KeyCode::Char(c) if !ctrl => app.issue_title_input(c),
+            _ => {}
+        },
+        Some(IssueField::Body) => {
+            if let Some(editor) = app.issue_body_editor() {
+                edit_text(editor, key);
+            }
+        }
+        Some(_) => {
+            let Some(picker) = app.issue_picker() else {
+                return;
+            };
+            match key.code {
+                KeyCode::Down | KeyCode::Char('j') => picker.down(),
+                KeyCode::Up | KeyCode::Char('k') => picker.up(),
+                KeyCode::Char(' ') | KeyCode::Enter => picker.toggle(),
+                _ => {}
+            }
+        }
+        None => {}
+    }
+}
+
+// keys every multi-line text field understands
+fn edit_text(editor: &mut TextEditor, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => editor.insert(c),
+        KeyCode::Enter => editor.newline(),
+        KeyCode::Backspace => editor.backspace(),
+        KeyCode::Delete => editor.delete(),
+        KeyCode::Left => editor.left(),
+        KeyCode::Right => editor.right(),
+        KeyCode::Up => editor.up(),
+        KeyCode::Down => editor.down(),
+        KeyCode::Home => editor.home(),
+        KeyCode::End => editor.end(),
+        _ => {}
+    }
+}
+
 pub fn handle_search_keys(app: &mut App, key: KeyEvent) {
     match key.code {
         KeyCode::Char(c) => app.search_input.push(c),
@@ -76,3 +349,17 @@ pub fn handle_search_keys(app: &mut App, key: KeyEvent) {
         _ => {}
     }
 }
+
+pub fn handle_filter_keys(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.close_filter_form(),
+        KeyCode::Enter => app.apply_filter(),
+        KeyCode::Down | KeyCode::Tab => app.next_filter_field(),
+        KeyCode::Up | KeyCode::BackTab => app.previous_filter_field(),
+        KeyCode::Left | KeyCode::Right => app.cycle_filter_value(),
+        KeyCode::Backspace => app.filter_backspace(),
+        KeyCode::Char(' ') if !app.is_typing() => app.cycle_filter_value(),
+        KeyCode::Char(c) => app.filter_input(c),
+        _ => {}
+    }
+}
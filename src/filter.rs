@@ -0,0 +1,389 @@
+// Filters for the issue and PR tables, translated into REST query parameters,
+// GraphQL arguments or search qualifiers by whichever backend serves them
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StateFilter {
+    #[default]
+    Open,
+    Closed,
+    All,
+}
+
+impl StateFilter {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StateFilter::Open => "open",
+            StateFilter::Closed => "closed",
+            StateFilter::All => "all",
+        }
+    }
+
+    pub fn cycle(&self) -> Self {
+        match self {
+            StateFilter::Open => StateFilter::Closed,
+            StateFilter::Closed => StateFilter::All,
+            StateFilter::All => StateFilter::Open,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortField {
+    #[default]
+    Created,
+    Updated,
+    Comments,
+}
+
+impl SortField {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortField::Created => "created",
+            SortField::Updated => "updated",
+            SortField::Comments => "comments",
+        }
+    }
+
+    // PR listings can't be sorted by comments in GraphQL, so PRs skip it
+    pub fn cycle(&self, prs: bool) -> Self {
+        match self {
+            SortField::Created => SortField::Updated,
+            SortField::Updated if prs => SortField::Created,
+            SortField::Updated => SortField::Comments,
+            SortField::Comments => SortField::Created,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortDirection::Asc => "asc",
+            SortDirection::Desc => "desc",
+        }
+    }
+
+    pub fn cycle(&self) -> Self {
+        match self {
+            SortDirection::Asc => SortDirection::Desc,
+            SortDirection::Desc => SortDirection::Asc,
+        }
+    }
+}
+
+// What an issue or PR table lists. The PR listings can't filter on labels,
+// assignee, author or milestone, so PRs narrowed by them are searched for.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct IssueFilter {
+    pub state: StateFilter,
+    pub labels: Vec<String>,
+    pub assignee: Option<String>,
+    pub author: Option<String>,
+    // milestone number, `*` for any milestone or `none`
+    pub milestone: Option<String>,
+    // title of the `milestone` number, which search matches milestones by;
+    // looked up when a search first needs it
+    pub milestone_title: Option<String>,
+    pub sort: SortField,
+    pub direction: SortDirection,
+}
+
+impl IssueFilter {
+    // the milestone filtered on, unless it is `*` or `none`
+    pub fn milestone_number(&self) -> Option<u32> {
+        self.milestone.as_deref()?.parse().ok()
+    }
+
+    // whether labels, assignee, author or milestone narrow the listing
+    pub fn narrows(&self) -> bool {
+        !self.labels.is_empty()
+            || self.assignee.is_some()
+            || self.author.is_some()
+            || self.milestone.is_some()
+    }
+
+    // `/repos/{repo}/issues` query parameters
+    pub fn issue_params(&self) -> String {
+        let mut params = format!("state={}", self.state.as_str());
+        if !self.labels.is_empty() {
+            params.push_str(&format!(
+                "&labels={}",
+                urlencoding::encode(&self.labels.join(","))
+            ));
+        }
+        if let Some(assignee) = &self.assignee {
+            params.push_str(&format!("&assignee={}", urlencoding::encode(assignee)));
+        }
+        if let Some(author) = &self.author {
+            params.push_str(&format!("&creator={}", urlencoding::encode(author)));
+        }
+        if let Some(milestone) = &self.milestone {
+            params.push_str(&format!("&milestone={}", urlencoding::encode(milestone)));
+        }
+        params.push_str(&format!(
+            "&sort={}&direction={}",
+            self.sort.as_str(),
+            self.direction.as_str()
+        ));
+        params
+    }
+
+    // `/repos/{repo}/pulls` query parameters; comment count is "popularity" there
+    pub fn pr_params(&self) -> String {
+        let sort = match self.sort {
+            SortField::Comments => "popularity",
+            sort => sort.as_str(),
+        };
+        format!(
+            "state={}&sort={}&direction={}",
+            self.state.as_str(),
+            sort,
+            self.direction.as_str()
+        )
+    }
+
+    // search qualifiers; search matches milestones by title rather than number,
+    // so the number only stands in until the title has been looked up
+    pub fn search_qualifiers(&self) -> String {
+        let mut qualifiers = Vec::new();
+        match self.state {
+            StateFilter::Open => qualifiers.push(String::from("is:open")),
+            StateFilter::Closed => qualifiers.push(String::from("is:closed")),
+            StateFilter::All => {}
+        }
+        for label in &self.labels {
+            qualifiers.push(format!("label:\"{}\"", label));
+        }
+        if let Some(assignee) = &self.assignee {
+            qualifiers.push(match assignee.as_str() {
+                "none" => String::from("no:assignee"),
+                assignee => format!("assignee:{}", assignee),
+            });
+        }
+        if let Some(author) = &self.author {
+            qualifiers.push(format!("author:{}", author));
+        }
+        match self.milestone.as_deref() {
+            None | Some("*") => {}
+            Some("none") => qualifiers.push(String::from("no:milestone")),
+            Some(milestone) => qualifiers.push(format!(
+                "milestone:\"{}\"",
+                self.milestone_title.as_deref().unwrap_or(milestone)
+            )),
+        }
+        qualifiers.push(format!(
+            "sort:{}-{}",
+            self.sort.as_str(),
+            self.direction.as_str()
+        ));
+        qualifiers.join(" ")
+    }
+
+    // compact description for table titles, e.g. `open, label:bug, sort:updated-asc`
+    pub fn summary(&self) -> String {
+        let mut parts = vec![self.state.as_str().to_string()];
+        if !self.labels.is_empty() {
+            parts.push(format!("label:{}", self.labels.join(",")));
+        }
+        if let Some(assignee) = &self.assignee {
+            parts.push(format!("assignee:{}", assignee));
+        }
+        if let Some(author) = &self.author {
+            parts.push(format!("author:{}", author));
+        }
+        if let Some(milestone) = &self.milestone {
+            parts.push(format!("milestone:{}", milestone));
+        }
+        if self.sort != SortField::default() || self.direction != SortDirection::default() {
+            parts.push(format!(
+                "sort:{}-{}",
+                self.sort.as_str(),
+                self.direction.as_str()
+            ));
+        }
+        parts.join(", ")
+    }
+}
+
+// What the commits tab lists: the history of a branch, tag or commit (the
+// default branch when None), narrowed to a path or an author
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CommitFilter {
+    pub git_ref: Option<String>,
+    // file or directory the commits touch
+    pub path: Option<String>,
+    // login or email
+    pub author: Option<String>,
+}
+
+impl CommitFilter {
+    // `/repos/{repo}/commits` query parameters
+    pub fn params(&self) -> String {
+        let mut params = Vec::new();
+        if let Some(git_ref) = &self.git_ref {
+            params.push(format!("sha={}", urlencoding::encode(git_ref)));
+        }
+        if let Some(path) = &self.path {
+            params.push(format!("path={}", urlencoding::encode(path)));
+        }
+        if let Some(author) = &self.author {
+            params.push(format!("author={}", urlencoding::encode(author)));
+        }
+        params.join("&")
+    }
+
+    // compact description for the table title, e.g. `path:src, author:octocat`
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(path) = &self.path {
+            parts.push(format!("path:{}", path));
+        }
+        if let Some(author) = &self.author {
+            parts.push(format!("author:{}", author));
+        }
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn narrowed() -> IssueFilter {
+        IssueFilter {
+            state: StateFilter::Closed,
+            labels: vec![String::from("bug"), String::from("good first issue")],
+            assignee: Some(String::from("octocat")),
+            author: Some(String::from("monalisa")),
+            milestone: Some(String::from("3")),
+            milestone_title: Some(String::from("v1.0")),
+            sort: SortField::Updated,
+            direction: SortDirection::Asc,
+        }
+    }
+
+    #[test]
+    fn issue_params_default_to_open_newest_first() {
+        assert_eq!(
+            IssueFilter::default().issue_params(),
+            "state=open&sort=created&direction=desc"
+        );
+    }
+
+    #[test]
+    fn issue_params_encode_every_field() {
+        assert_eq!(
+            narrowed().issue_params(),
+            "state=closed&labels=bug%2Cgood%20first%20issue&assignee=octocat&creator=monalisa\
+             &milestone=3&sort=updated&direction=asc"
+        );
+    }
+
+    #[test]
+    fn search_qualifiers_default_to_open_newest_first() {
+        assert_eq!(
+            IssueFilter::default().search_qualifiers(),
+            "is:open sort:created-desc"
+        );
+    }
+
+    #[test]
+    fn search_qualifiers_quote_labels() {
+        let filter = IssueFilter {
+            milestone: None,
+            ..narrowed()
+        };
+        assert_eq!(
+            filter.search_qualifiers(),
+            "is:closed label:\"bug\" label:\"good first issue\" assignee:octocat \
+             author:monalisa sort:updated-asc"
+        );
+    }
+
+    #[test]
+    fn search_qualifiers_match_milestones_by_title() {
+        assert_eq!(
+            narrowed().search_qualifiers(),
+            "is:closed label:\"bug\" label:\"good first issue\" assignee:octocat \
+             author:monalisa milestone:\"v1.0\" sort:updated-asc"
+        );
+        assert_eq!(narrowed().milestone_number(), Some(3));
+    }
+
+    #[test]
+    fn search_qualifiers_turn_none_into_no() {
+        let filter = IssueFilter {
+            state: StateFilter::All,
+            assignee: Some(String::from("none")),
+            milestone: Some(String::from("none")),
+            ..IssueFilter::default()
+        };
+        assert_eq!(
+            filter.search_qualifiers(),
+            "no:assignee no:milestone sort:created-desc"
+        );
+    }
+
+    #[test]
+    fn only_the_search_fields_narrow() {
+        assert!(!IssueFilter::default().narrows());
+        let sorted = IssueFilter {
+            state: StateFilter::All,
+            sort: SortField::Updated,
+            ..IssueFilter::default()
+        };
+        assert!(!sorted.narrows());
+        assert!(narrowed().narrows());
+    }
+
+    #[test]
+    fn summaries_list_every_narrowing_field() {
+        assert_eq!(IssueFilter::default().summary(), "open");
+        assert_eq!(
+            narrowed().summary(),
+            "closed, label:bug,good first issue, assignee:octocat, author:monalisa, \
+             milestone:3, sort:updated-asc"
+        );
+    }
+
+    #[test]
+    fn commit_filters_become_encoded_params() {
+        assert_eq!(CommitFilter::default().params(), "");
+        let filter = CommitFilter {
+            git_ref: Some(String::from("feature/x")),
+            path: Some(String::from("docs/read me.md")),
+            author: Some(String::from("mona@example.com")),
+        };
+        assert_eq!(
+            filter.params(),
+            "sha=feature%2Fx&path=docs%2Fread%20me.md&author=mona%40example.com"
+        );
+        let by_author = CommitFilter {
+            author: Some(String::from("octocat")),
+            ..CommitFilter::default()
+        };
+        assert_eq!(by_author.params(), "author=octocat");
+    }
+
+    #[test]
+    fn the_commit_summary_leaves_the_ref_to_the_title() {
+        let filter = CommitFilter {
+            git_ref: Some(String::from("main")),
+            path: Some(String::from("src")),
+            author: Some(String::from("octocat")),
+        };
+        assert_eq!(filter.summary(), "path:src, author:octocat");
+        let on_a_branch = CommitFilter {
+            git_ref: Some(String::from("main")),
+            ..CommitFilter::default()
+        };
+        assert_eq!(on_a_branch.summary(), "");
+    }
+}
@@ -0,0 +1,1004 @@
+use crate::backend::GitHubBackend;
+use crate::error::{ApiError, ErrorResponse};
+use crate::filter::{CommitFilter, IssueFilter, StateFilter};
+use crate::models::{
+    BranchRef, Check, CheckState, Comment, Commit, CommitAuthor, CommitInfo, CommitStats,
+    FileContent, GitRef, Issue, IssueTemplate, IssueUpdate, Label, MergeMethod, MergeRequest,
+    MergeResult, Milestone, NewIssue, NewReview, PR, Page, PullDetail, PullFile, PullRequestRef,
+    Reactions, Repo, RepoTree, Review, ReviewComment, ReviewThread, TreeEntry, User,
+};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::collections::HashMap;
+use std::time::Duration;
+
+// the one commit every fixture ref points at
+const FIXTURE_COMMIT: &str = "c0ffee00c0ffee00c0ffee00c0ffee00c0ffee00";
+// refs of every fixture repo besides HEAD and the commit itself
+const FIXTURE_BRANCHES: [&str; 2] = ["main", "develop"];
+const FIXTURE_TAGS: [&str; 1] = ["v1.0.0"];
+
+// Deterministic in-memory backend, used to drive `App` without network access.
+// Repos are matched to users by the owner part of `full_name`. Writes are
+// answered as GitHub would, but not stored.
+#[derive(Debug, Clone, Default)]
+pub struct FixtureBackend {
+    repos: Vec<Repo>,
+    issues: HashMap<String, Vec<Issue>>,
+    prs: HashMap<String, Vec<PR>>,
+    comments: HashMap<(String, u32), Vec<Comment>>,
+    files: HashMap<(String, u32), Vec<PullFile>>,
+    threads: HashMap<(String, u32), Vec<ReviewThread>>,
+    // files of each repo's code, by path
+    code: HashMap<String, Vec<(String, Vec<u8>)>>,
+    // history of each repo, newest first, the same from every ref
+    commits: HashMap<String, Vec<Commit>>,
+    pull_commits: HashMap<(String, u32), Vec<Commit>>,
+    latency: Option<Duration>,
+    page_size: Option<usize>,
+}
+
+impl FixtureBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_repo(mut self, repo: Repo) -> Self {
+        self.repos.push(repo);
+        self
+    }
+
+    pub fn with_issues(mut self, repo: &str, issues: Vec<Issue>) -> Self {
+        self.issues.insert(repo.to_string(), issues);
+        self
+    }
+
+    pub fn with_prs(mut self, repo: &str, prs: Vec<PR>) -> Self {
+        self.prs.insert(repo.to_string(), prs);
+        self
+    }
+
+    pub fn with_comments(mut self, repo: &str, number: u32, comments: Vec<Comment>) -> Self {
+        self.comments.insert((repo.to_string(), number), comments);
+        self
+    }
+
+    pub fn with_files(mut self, repo: &str, number: u32, files: Vec<PullFile>) -> Self {
+        self.files.insert((repo.to_string(), number), files);
+        self
+    }
+
+    pub fn with_threads(mut self, repo: &str, number: u32, threads: Vec<ReviewThread>) -> Self {
+        self.threads.insert((repo.to_string(), number), threads);
+        self
+    }
+
+    pub fn with_code(mut self, repo: &str, files: Vec<(&str, Vec<u8>)>) -> Self {
+        let files = files
+            .into_iter()
+            .map(|(path, content)| (path.to_string(), content))
+            .collect();
+        self.code.insert(repo.to_string(), files);
+        self
+    }
+
+    pub fn with_commits(mut self, repo: &str, commits: Vec<Commit>) -> Self {
+        self.commits.insert(repo.to_string(), commits);
+        self
+    }
+
+    pub fn with_pull_commits(mut self, repo: &str, number: u32, commits: Vec<Commit>) -> Self {
+        self.pull_commits
+            .insert((repo.to_string(), number), commits);
+        self
+    }
+
+    // delay every call, so loading states can be observed
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    // split issue and PR listings into pages of `page_size` items
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size.max(1));
+        self
+    }
+
+    // small canned data set: two repos for `octocat`, one with issues and PRs;
+    // like the REST API, its issue listing includes the PR
+    pub fn sample() -> Self {
+        Self::new()
+            .with_repo(repo("octocat/hello-world", "My first repository"))
+            .with_repo(repo("octocat/spoon-knife", "Fork me"))
+            .with_issues(
+                "octocat/hello-world",
+                vec![
+                    pr_issue(3, "Fix startup crash"),
+                    issue(2, "Crash on startup"),
+                    issue(1, "Add a README"),
+                ],
+            )
+            .with_prs("octocat/hello-world", vec![pr(3, "Fix startup crash")])
+            .with_comments(
+                "octocat/hello-world",
+                2,
+                vec![
+                    comment(1, "monalisa", "Same here on 1.2.0"),
+                    comment(2, "octocat", "Fixed in #3"),
+                ],
+            )
+            .with_files(
+                "octocat/hello-world",
+                3,
+                vec![
+                    pull_file(
+                        "src/main.rs",
+                        "modified",
+                        "@@ -1,4 +1,6 @@\n fn main() {\n-    let config = load().unwrap();\n+    let Ok(config) = load() else {\n+        return;\n+    };\n     run(config);\n }",
+                    ),
+                    PullFile {
+                        patch: None,
+                        ..pull_file("assets/logo.png", "added", "")
+                    },
+                ],
+            )
+            .with_threads(
+                "octocat/hello-world",
+                3,
+                vec![review_thread(
+                    "src/main.rs",
+                    3,
+                    vec![
+                        comment(3, "monalisa", "Should this log why loading failed?"),
+                        comment(4, "octocat", "It does, inside load()"),
+                    ],
+                )],
+            )
+            .with_code(
+                "octocat/hello-world",
+                vec![
+                    ("README.md", b"# Hello World\n\nMy first repository.\n".to_vec()),
+                    (
+                        "src/main.rs",
+                        b"fn main() {\n    let Ok(config) = load() else {\n        return;\n    };\n    run(config);\n}\n".to_vec(),
+                    ),
+                    ("src/config.rs", b"pub struct Config {\n    pub name: String,\n}\n".to_vec()),
+                    ("assets/logo.png", vec![0x89, b'P', b'N', b'G', 0, 0, 0, 13]),
+                ],
+            )
+            .with_commits(
+                "octocat/hello-world",
+                vec![
+                    fix_commit(),
+                    commit(
+                        2,
+                        "octocat",
+                        "Add a config file",
+                        vec![pull_file(
+                            "src/config.rs",
+                            "added",
+                            "@@ -0,0 +1,3 @@\n+pub struct Config {\n+    pub name: String,\n+}",
+                        )],
+                    ),
+                    commit(
+                        1,
+                        "octocat",
+                        "Initial commit",
+                        vec![pull_file(
+                            "README.md",
+                            "added",
+                            "@@ -0,0 +1,3 @@\n+# Hello World\n+\n+My first repository.",
+                        )],
+                    ),
+                ],
+            )
+            .with_pull_commits("octocat/hello-world", 3, vec![fix_commit()])
+    }
+
+    // cursors are plain item offsets
+    fn paginate<T: Clone>(&self, items: &[T], cursor: Option<&str>) -> Result<Page<T>, ApiError> {
+        let start = match cursor {
+            Some(cursor) => cursor
+                .parse::<usize>()
+                .map_err(|_| ApiError::Decode(format!("invalid cursor {}", cursor)))?,
+            None => 0,
+        };
+        let end = match self.page_size {
+            Some(size) => (start + size).min(items.len()),
+            None => items.len(),
+        };
+
+        Ok(Page {
+            items: items.get(start..end).unwrap_or_default().to_vec(),
+            next: (end < items.len()).then(|| end.to_string()),
+            stale_since: None,
+        })
+    }
+
+    async fn simulate_latency(&self) {
+        if let Some(latency) = self.latency {
+            tokio::time::sleep(latency).await;
+        }
+    }
+
+    fn find_repo(&self, full_name: &str) -> Result<(), ApiError> {
+        if self.repos.iter().any(|r| r.full_name == full_name) {
+            Ok(())
+        } else {
+            Err(ApiError::NotFound(Box::new(ErrorResponse::new(
+                404,
+                format!("repository {} not found", full_name),
+            ))))
+        }
+    }
+
+    fn find_code(&self, repo: &str) -> Result<&[(String, Vec<u8>)], ApiError> {
+        self.find_repo(repo)?;
+        self.code.get(repo).map(Vec::as_slice).ok_or_else(|| {
+            ApiError::Http(Box::new(ErrorResponse::new(
+                409,
+                "Git Repository is empty.",
+            )))
+        })
+    }
+
+    // every commit of a repo, its history and those of its PRs
+    fn all_commits(&self, repo: &str) -> impl Iterator<Item = &Commit> {
+        let pull_commits = self
+            .pull_commits
+            .iter()
+            .filter(move |((pr_repo, _), _)| pr_repo == repo)
+            .flat_map(|(_, commits)| commits);
+        self.commits
+            .get(repo)
+            .into_iter()
+            .flatten()
+            .chain(pull_commits)
+    }
+
+    fn find_pr(&self, repo: &str, number: u32) -> Result<PR, ApiError> {
+        self.find_repo(repo)?;
+        self.prs
+            .get(repo)
+            .and_then(|prs| prs.iter().find(|pr| pr.number == number))
+            .cloned()
+            .ok_or_else(|| {
+                ApiError::NotFound(Box::new(ErrorResponse::new(
+                    404,
+                    format!("pull request #{} not found", number),
+                )))
+            })
+    }
+}
+
+#[async_trait]
+impl GitHubBackend for FixtureBackend {
+    async fn fetch_repos(&self, username: &str) -> Result<Page<Repo>, ApiError> {
+        self.simulate_latency().await;
+        let prefix = format!("{}/", username);
+        let repos: Vec<Repo> = self
+            .repos
+            .iter()
+            .filter(|r| r.full_name.starts_with(&prefix))
+            .cloned()
+            .collect();
+        Ok(Page {
+            items: repos,
+            ..Page::default()
+        })
+    }
+
+    async fn search_repos(&self, query: &str) -> Result<Page<Repo>, ApiError> {
+        self.simulate_latency().await;
+        let query = query.to_lowercase();
+        let repos: Vec<Repo> = self
+            .repos
+            .iter()
+            .filter(|r| {
+                r.full_name.to_lowercase().contains(&query)
+                    || r.description
+                        .as_deref()
+                        .is_some_and(|d| d.to_lowercase().contains(&query))
+            })
+            .cloned()
+            .collect();
+        Ok(Page {
+            items: repos,
+            ..Page::default()
+        })
+    }
+
+    // only state, labels and author are applied: fixtures have no assignees or milestones
+    async fn get_repo_issues(
+        &self,
+        repo: &str,
+        filter: &IssueFilter,
+        cursor: Option<&str>,
+    ) -> Result<Page<Issue>, ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)?;
+        let issues: Vec<Issue> = self
+            .issues
+            .get(repo)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|issue| {
+                state_matches(filter, &issue.state)
+                    && filter
+                        .labels
+                        .iter()
+                        .all(|name| issue.labels.iter().any(|l| &l.name == name))
+                    && filter.author.as_ref().is_none_or(|author| {
+                        issue.user.as_ref().is_some_and(|u| &u.login == author)
+                    })
+            })
+            .cloned()
+            .collect();
+        self.paginate(&issues, cursor)
+    }
+
+    // only state and author: fixture PRs carry no labels
+    async fn get_repo_prs(
+        &self,
+        repo: &str,
+        filter: &IssueFilter,
+        cursor: Option<&str>,
+    ) -> Result<Page<PR>, ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)?;
+        let prs: Vec<PR> =
+            self.prs
+                .get(repo)
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+                .iter()
+                .filter(|pr| {
+                    state_matches(filter, &pr.state)
+                        && filter.author.as_ref().is_none_or(|author| {
+                            pr.user.as_ref().is_some_and(|u| &u.login == author)
+                        })
+                })
+                .cloned()
+                .collect();
+        self.paginate(&prs, cursor)
+    }
+
+    async fn get_pull_detail(&self, repo: &str, number: u32) -> Result<PullDetail, ApiError> {
+        self.simulate_latency().await;
+        let pr = self.find_pr(repo, number)?;
+        Ok(PullDetail {
+            pr,
+            reviews: vec![Review {
+                id: 1,
+                user: Some(user("monalisa")),
+                state: String::from("APPROVED"),
+                submitted_at: Some(String::from("2024-01-03T00:00:00Z")),
+            }],
+            checks: vec![Check {
+                name: String::from("ci / test"),
+                state: CheckState::Success,
+            }],
+        })
+    }
+
+    async fn get_pull_files(&self, repo: &str, number: u32) -> Result<Page<PullFile>, ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)?;
+        let files = self
+            .files
+            .get(&(repo.to_string(), number))
+            .cloned()
+            .unwrap_or_default();
+        Ok(Page {
+            items: files,
+            ..Page::default()
+        })
+    }
+
+    async fn get_labels(&self, repo: &str) -> Result<Page<Label>, ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)?;
+        Ok(Page {
+            items: vec![
+                label("bug", "d73a4a"),
+                label("enhancement", "a2eeef"),
+                label("good first issue", "7057ff"),
+            ],
+            ..Page::default()
+        })
+    }
+
+    async fn get_assignees(&self, repo: &str) -> Result<Page<User>, ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)?;
+        Ok(Page {
+            items: vec![user("octocat"), user("monalisa")],
+            ..Page::default()
+        })
+    }
+
+    async fn get_milestones(&self, repo: &str) -> Result<Page<Milestone>, ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)?;
+        Ok(Page {
+            items: vec![Milestone {
+                number: 1,
+                title: String::from("v1.0"),
+            }],
+            ..Page::default()
+        })
+    }
+
+    async fn get_milestone(&self, repo: &str, number: u32) -> Result<Milestone, ApiError> {
+        self.simulate_latency().await;
+        self.get_milestones(repo)
+            .await?
+            .items
+            .into_iter()
+            .find(|milestone| milestone.number == number)
+            .ok_or_else(|| {
+                ApiError::NotFound(Box::new(ErrorResponse::new(
+                    404,
+                    format!("milestone {} not found", number),
+                )))
+            })
+    }
+
+    async fn get_issue_templates(&self, repo: &str) -> Result<Vec<IssueTemplate>, ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)?;
+        Ok(vec![IssueTemplate::parse(
+            "bug_report.md",
+            "---\nname: Bug report\nabout: Something isn't working\ntitle: '[Bug] '\nlabels: bug\n---\n\n**Steps to reproduce**\n\n**Expected behavior**\n",
+        )])
+    }
+
+    async fn create_issue(&self, repo: &str, new: &NewIssue) -> Result<Issue, ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)?;
+        let number = self
+            .issues
+            .get(repo)
+            .and_then(|issues| issues.iter().map(|issue| issue.number).max())
+            .unwrap_or_default()
+            + 1;
+        Ok(Issue {
+            body: Some(new.body.clone()),
+            labels: new
+                .labels
+                .iter()
+                .map(|name| label(name, "ededed"))
+                .collect(),
+            ..issue(number, &new.title)
+        })
+    }
+
+    async fn update_issue(
+        &self,
+        repo: &str,
+        number: u32,
+        update: &IssueUpdate,
+    ) -> Result<Issue, ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)?;
+        let mut issue = self
+            .issues
+            .get(repo)
+            .and_then(|issues| issues.iter().find(|issue| issue.number == number))
+            .cloned()
+            .ok_or_else(|| {
+                ApiError::NotFound(Box::new(ErrorResponse::new(
+                    404,
+                    format!("issue #{} not found", number),
+                )))
+            })?;
+        if let Some(state) = &update.state {
+            issue.state.clone_from(state);
+            issue.state_reason.clone_from(&update.state_reason);
+        }
+        if let Some(labels) = &update.labels {
+            issue.labels = labels.iter().map(|name| label(name, "ededed")).collect();
+        }
+        if let Some(assignees) = &update.assignees {
+            issue.assignees = assignees.iter().map(|login| user(login)).collect();
+        }
+        if let Some(milestone) = update.milestone {
+            issue.milestone = milestone.map(|number| Milestone {
+                number,
+                title: String::from("v1.0"),
+            });
+        }
+        Ok(issue)
+    }
+
+    async fn lock_issue(
+        &self,
+        repo: &str,
+        _number: u32,
+        _reason: Option<&str>,
+    ) -> Result<(), ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)
+    }
+
+    async fn unlock_issue(&self, repo: &str, _number: u32) -> Result<(), ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)
+    }
+
+    async fn post_comment(
+        &self,
+        repo: &str,
+        _number: u32,
+        body: &str,
+    ) -> Result<Comment, ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)?;
+        Ok(Comment {
+            reactions: None,
+            ..comment(100, "octocat", body)
+        })
+    }
+
+    async fn get_review_threads(
+        &self,
+        repo: &str,
+        number: u32,
+    ) -> Result<Vec<ReviewThread>, ApiError> {
+        self.simulate_latency().await;
+        self.find_pr(repo, number)?;
+        Ok(self
+            .threads
+            .get(&(repo.to_string(), number))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn set_thread_resolved(&self, _thread_id: &str, _resolved: bool) -> Result<(), ApiError> {
+        self.simulate_latency().await;
+        Ok(())
+    }
+
+    async fn submit_review(
+        &self,
+        repo: &str,
+        number: u32,
+        review: &NewReview,
+    ) -> Result<Review, ApiError> {
+        self.simulate_latency().await;
+        self.find_pr(repo, number)?;
+        let state = match review.event.as_str() {
+            "APPROVE" => "APPROVED",
+            "REQUEST_CHANGES" => "CHANGES_REQUESTED",
+            _ => "COMMENTED",
+        };
+        Ok(Review {
+            id: 2,
+            user: Some(user("octocat")),
+            state: state.to_string(),
+            submitted_at: Some(String::from("2024-01-04T00:00:00Z")),
+        })
+    }
+
+    async fn merge_pull(
+        &self,
+        repo: &str,
+        number: u32,
+        _merge: &MergeRequest,
+    ) -> Result<MergeResult, ApiError> {
+        self.simulate_latency().await;
+        let pr = self.find_pr(repo, number)?;
+        if pr.state != "open" || pr.mergeable == Some(false) {
+            return Err(ApiError::Http(Box::new(ErrorResponse::new(
+                405,
+                "Pull Request is not mergeable",
+            ))));
+        }
+        Ok(MergeResult {
+            sha: Some(format!("{:040x}", number + 1000)),
+            merged: true,
+            message: String::from("Pull Request successfully merged"),
+        })
+    }
+
+    async fn enable_auto_merge(
+        &self,
+        repo: &str,
+        number: u32,
+        _method: MergeMethod,
+    ) -> Result<(), ApiError> {
+        self.simulate_latency().await;
+        self.find_pr(repo, number).map(|_| ())
+    }
+
+    async fn set_pull_draft(&self, repo: &str, number: u32, _draft: bool) -> Result<(), ApiError> {
+        self.simulate_latency().await;
+        self.find_pr(repo, number).map(|_| ())
+    }
+
+    async fn delete_branch(&self, repo: &str, _branch: &str) -> Result<(), ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)
+    }
+
+    async fn get_readme(&self, repo: &str) -> Result<Option<String>, ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)?;
+        let readme = self.code.get(repo).and_then(|files| {
+            files
+                .iter()
+                .find(|(path, _)| path.eq_ignore_ascii_case("README.md"))
+                .map(|(_, content)| String::from_utf8_lossy(content).into_owned())
+        });
+        Ok(readme)
+    }
+
+    async fn get_branches(&self, repo: &str) -> Result<Page<GitRef>, ApiError> {
+        self.simulate_latency().await;
+        self.find_code(repo)?;
+        Ok(git_refs(&FIXTURE_BRANCHES))
+    }
+
+    async fn get_tags(&self, repo: &str) -> Result<Page<GitRef>, ApiError> {
+        self.simulate_latency().await;
+        self.find_code(repo)?;
+        Ok(git_refs(&FIXTURE_TAGS))
+    }
+
+    async fn get_tree(&self, repo: &str, git_ref: &str) -> Result<RepoTree, ApiError> {
+        self.simulate_latency().await;
+        let files = self.find_code(repo)?;
+        known_ref(git_ref)?;
+
+        // directories are listed along with the files, as in a recursive tree
+        let mut entries: Vec<TreeEntry> = Vec::new();
+        for (path, content) in files {
+            let mut dir = path.as_str();
+            while let Some((parent, _)) = dir.rsplit_once('/') {
+                if !entries.iter().any(|entry| entry.path == parent) {
+                    entries.push(TreeEntry {
+                        path: parent.to_string(),
+                        kind: String::from("tree"),
+                        size: None,
+                    });
+                }
+                dir = parent;
+            }
+            entries.push(TreeEntry {
+                path: path.clone(),
+                kind: String::from("blob"),
+                size: Some(content.len() as u64),
+            });
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(RepoTree {
+            commit: FIXTURE_COMMIT.to_string(),
+            entries,
+            truncated: false,
+        })
+    }
+
+    async fn get_file(
+        &self,
+        repo: &str,
+        path: &str,
+        _commit: &str,
+    ) -> Result<FileContent, ApiError> {
+        self.simulate_latency().await;
+        let (path, content) = self
+            .find_code(repo)?
+            .iter()
+            .find(|(file, _)| file == path)
+            .ok_or_else(|| {
+                ApiError::NotFound(Box::new(ErrorResponse::new(
+                    404,
+                    format!("{} not found", path),
+                )))
+            })?;
+        Ok(FileContent {
+            path: path.clone(),
+            size: content.len() as u64,
+            encoding: Some(String::from("base64")),
+            content: STANDARD.encode(content),
+        })
+    }
+
+    // listed without their stats and files, like GitHub does
+    async fn get_commits(
+        &self,
+        repo: &str,
+        filter: &CommitFilter,
+        cursor: Option<&str>,
+    ) -> Result<Page<Commit>, ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)?;
+        if let Some(git_ref) = &filter.git_ref {
+            known_ref(git_ref)?;
+        }
+        let commits: Vec<Commit> = self
+            .commits
+            .get(repo)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|commit| {
+                filter.path.as_ref().is_none_or(|path| {
+                    commit.files.iter().any(|file| {
+                        file.filename == *path || file.filename.starts_with(&format!("{}/", path))
+                    })
+                }) && filter
+                    .author
+                    .as_ref()
+                    .is_none_or(|author| commit.author_name().eq_ignore_ascii_case(author))
+            })
+            .map(listed)
+            .collect();
+        self.paginate(&commits, cursor)
+    }
+
+    async fn get_pull_commits(&self, repo: &str, number: u32) -> Result<Page<Commit>, ApiError> {
+        self.simulate_latency().await;
+        self.find_pr(repo, number)?;
+        let commits = self
+            .pull_commits
+            .get(&(repo.to_string(), number))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(listed)
+            .collect();
+        Ok(Page {
+            items: commits,
+            ..Page::default()
+        })
+    }
+
+    // found by its full or abbreviated SHA
+    async fn get_commit(&self, repo: &str, sha: &str) -> Result<Commit, ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)?;
+        let commit = self
+            .all_commits(repo)
+            .find(|commit| !sha.is_empty() && commit.sha.starts_with(sha))
+            .cloned()
+            .ok_or_else(|| no_commit(sha))?;
+        let count = |count: fn(&PullFile) -> u32| commit.files.iter().map(count).sum();
+        Ok(Commit {
+            stats: Some(CommitStats {
+                additions: count(|file| file.additions),
+                deletions: count(|file| file.deletions),
+            }),
+            ..commit
+        })
+    }
+
+    async fn get_issue_comments(
+        &self,
+        repo: &str,
+        number: u32,
+        cursor: Option<&str>,
+    ) -> Result<Page<Comment>, ApiError> {
+        self.simulate_latency().await;
+        self.find_repo(repo)?;
+        let comments = self
+            .comments
+            .get(&(repo.to_string(), number))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        self.paginate(comments, cursor)
+    }
+}
+
+fn state_matches(filter: &IssueFilter, state: &str) -> bool {
+    filter.state == StateFilter::All || filter.state.as_str() == state
+}
+
+// HEAD, the fixture commit, or one of the fixture branches and tags
+fn known_ref(git_ref: &str) -> Result<(), ApiError> {
+    let known = git_ref == "HEAD"
+        || git_ref == FIXTURE_COMMIT
+        || FIXTURE_BRANCHES.contains(&git_ref)
+        || FIXTURE_TAGS.contains(&git_ref);
+    if known {
+        Ok(())
+    } else {
+        Err(no_commit(git_ref))
+    }
+}
+
+fn no_commit(sha: &str) -> ApiError {
+    ApiError::Http(Box::new(ErrorResponse::new(
+        422,
+        format!("No commit found for SHA: {}", sha),
+    )))
+}
+
+// a commit as listings have it
+fn listed(commit: &Commit) -> Commit {
+    Commit {
+        stats: None,
+        files: Vec::new(),
+        ..commit.clone()
+    }
+}
+
+// model builders with fixed, predictable field values
+
+pub fn repo(full_name: &str, description: &str) -> Repo {
+    Repo {
+        full_name: full_name.to_string(),
+        description: Some(description.to_string()),
+        stargazers_count: 42,
+        forks_count: 7,
+        language: Some(String::from("Rust")),
+        open_issues_count: 0,
+        updated_at: String::from("2024-01-02T00:00:00Z"),
+        created_at: String::from("2024-01-01T00:00:00Z"),
+        html_url: format!("https://github.com/{}", full_name),
+        default_branch: Some(String::from("main")),
+    }
+}
+
+fn git_refs(names: &[&str]) -> Page<GitRef> {
+    Page {
+        items: names
+            .iter()
+            .map(|name| GitRef {
+                name: name.to_string(),
+            })
+            .collect(),
+        ..Page::default()
+    }
+}
+
+pub fn issue(number: u32, title: &str) -> Issue {
+    Issue {
+        title: title.to_string(),
+        state: String::from("open"),
+        body: Some(format!("Body of issue #{}", number)),
+        number,
+        html_url: format!("https://github.com/fixture/issues/{}", number),
+        labels: vec![label("bug", "d73a4a")],
+        user: Some(user("octocat")),
+        comments: 0,
+        pull_request: None,
+        assignees: Vec::new(),
+        milestone: None,
+        locked: false,
+        state_reason: None,
+    }
+}
+
+// a PR as the issues endpoint lists it
+pub fn pr_issue(number: u32, title: &str) -> Issue {
+    Issue {
+        html_url: format!("https://github.com/fixture/pull/{}", number),
+        pull_request: Some(PullRequestRef {
+            html_url: Some(format!("https://github.com/fixture/pull/{}", number)),
+        }),
+        ..issue(number, title)
+    }
+}
+
+pub fn pr(number: u32, title: &str) -> PR {
+    PR {
+        number,
+        state: String::from("open"),
+        body: Some(format!("Body of PR #{}", number)),
+        title: title.to_string(),
+        html_url: format!("https://github.com/fixture/pull/{}", number),
+        user: Some(user("octocat")),
+        head: Some(BranchRef {
+            name: format!("fix-{}", number),
+            sha: format!("{:040x}", number),
+            repo: None,
+        }),
+        base: Some(BranchRef {
+            name: String::from("main"),
+            sha: format!("{:040x}", 0),
+            repo: None,
+        }),
+        mergeable: Some(true),
+        mergeable_state: Some(String::from("clean")),
+        additions: Some(10),
+        deletions: Some(2),
+        changed_files: Some(1),
+        commits: Some(1),
+        ..PR::default()
+    }
+}
+
+pub fn comment(id: u64, login: &str, body: &str) -> Comment {
+    Comment {
+        id,
+        user: Some(user(login)),
+        body: Some(body.to_string()),
+        created_at: String::from("2024-01-03T00:00:00Z"),
+        reactions: Some(Reactions {
+            total_count: 1,
+            plus_one: 1,
+            ..Reactions::default()
+        }),
+    }
+}
+
+// additions and deletions are counted from the patch
+pub fn pull_file(filename: &str, status: &str, patch: &str) -> PullFile {
+    let count = |prefix| patch.lines().filter(|l| l.starts_with(prefix)).count() as u32;
+    PullFile {
+        filename: filename.to_string(),
+        status: status.to_string(),
+        additions: count('+'),
+        deletions: count('-'),
+        patch: Some(patch.to_string()),
+        previous_filename: None,
+    }
+}
+
+// commit `number`, whose SHA is the number in hex, like the head of PR `number`
+pub fn commit(number: u32, login: &str, message: &str, files: Vec<PullFile>) -> Commit {
+    Commit {
+        sha: format!("{:040x}", number),
+        commit: CommitInfo {
+            message: message.to_string(),
+            author: Some(CommitAuthor {
+                name: login.to_string(),
+                date: format!("2024-01-{:02}T00:00:00Z", number),
+            }),
+        },
+        author: Some(user(login)),
+        stats: None,
+        files,
+    }
+}
+
+// the head commit of the sample PR #3, also on the default branch
+fn fix_commit() -> Commit {
+    commit(
+        3,
+        "monalisa",
+        "Fix startup crash\n\nA config that fails to load no longer panics, the app\njust exits.\n\nFixes #2",
+        vec![pull_file(
+            "src/main.rs",
+            "modified",
+            "@@ -1,4 +1,6 @@\n fn main() {\n-    let config = load().unwrap();\n+    let Ok(config) = load() else {\n+        return;\n+    };\n     run(config);\n }",
+        )],
+    )
+}
+
+// unresolved thread on the new side of `path`, made of plain comments
+pub fn review_thread(path: &str, line: u32, comments: Vec<Comment>) -> ReviewThread {
+    ReviewThread {
+        id: Some(format!("thread-{}-{}", path, line)),
+        path: path.to_string(),
+        line: Some(line),
+        side: String::from("RIGHT"),
+        resolved: false,
+        comments: comments
+            .into_iter()
+            .map(|c| ReviewComment {
+                user: c.user,
+                body: c.body.unwrap_or_default(),
+                created_at: c.created_at,
+            })
+            .collect(),
+    }
+}
+
+pub fn user(login: &str) -> User {
+    User {
+        login: login.to_string(),
+    }
+}
+
+pub fn label(name: &str, color: &str) -> Label {
+    Label {
+        url: format!("https://api.github.com/fixture/labels/{}", name),
+        description: None,
+        color: Some(color.to_string()),
+        name: name.to_string(),
+    }
+}
@@ -1,6 +1,33 @@
-use crate::models::{Issue, PR, Repo};
-use reqwest::Client;
+use crate::api_status::{ApiStatus, StatusSnapshot, rate_limit_wait};
+use crate::cache::{CachedResponse, HttpCache, now_secs};
+use crate::config::{Config, NetworkSettings};
+use crate::error::{ApiError, ErrorResponse};
+use crate::filter::{CommitFilter, IssueFilter};
+use crate::models::{
+    Check, CheckState, Comment, Commit, FileContent, GitRef, Issue, IssueTemplate, IssueUpdate,
+    Label, MergeRequest, MergeResult, Milestone, NewIssue, NewReview, PR, Page, PullDetail,
+    PullFile, Repo, RepoTree, Review, ReviewComment, ReviewThread, TreeEntry, User,
+};
+use reqwest::header::{
+    ACCEPT, AUTHORIZATION, ETAG, HeaderMap, HeaderName, HeaderValue, IF_MODIFIED_SINCE,
+    IF_NONE_MATCH, LAST_MODIFIED, LINK,
+};
+use reqwest::{Client, RequestBuilder, StatusCode};
 use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+// REST API version every request is pinned to
+const API_VERSION: &str = "2022-11-28";
+
+// Page size used for the lazily loaded issue and PR tables
+pub const TABLE_PAGE_SIZE: u32 = 50;
+
+// How many times a rate limited request is retried before giving up
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
 
 // Response structure for search API
 #[derive(Deserialize)]
@@ -8,108 +35,1645 @@ struct SearchResponse {
     items: Vec<Repo>,
 }
 
-// Reusable client builder
-fn build_client() -> Result<Client, reqwest::Error> {
-    Client::builder().user_agent("gte/0.1").build()
+#[derive(Deserialize)]
+struct PullSearchResponse {
+    items: Vec<PullHit>,
+}
+
+// A PR as issue search returns it, merge time nested under `pull_request`
+#[derive(Deserialize)]
+struct PullHit {
+    #[serde(flatten)]
+    pr: PR,
+    #[serde(default)]
+    pull_request: Option<MergedAt>,
 }
 
-// Get public repos for a user (with pagination support)
-pub async fn fetch_repos(username: &str) -> Result<Vec<Repo>, reqwest::Error> {
-    let client = build_client()?;
-    let mut all_repos = Vec::new();
-    let mut page = 1;
+#[derive(Deserialize)]
+struct MergedAt {
+    #[serde(default)]
+    merged_at: Option<String>,
+}
 
-    loop {
-        let url = format!(
-            "https://api.github.com/users/{}/repos?per_page=100&page={}",
-            username, page
+impl PullHit {
+    fn into_pr(self) -> PR {
+        PR {
+            merged_at: self.pull_request.and_then(|p| p.merged_at),
+            ..self.pr
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CombinedStatus {
+    statuses: Vec<CommitStatus>,
+}
+
+#[derive(Deserialize)]
+struct CommitStatus {
+    context: String,
+    // error, failure, pending or success
+    state: String,
+}
+
+#[derive(Deserialize)]
+struct CheckRuns {
+    check_runs: Vec<CheckRun>,
+}
+
+#[derive(Deserialize)]
+struct CheckRun {
+    name: String,
+    // queued, in_progress or completed
+    status: String,
+    conclusion: Option<String>,
+}
+
+// Line comment of a PR as the REST API lists them; replies point at the
+// first comment of their thread
+#[derive(Deserialize)]
+struct PullReviewComment {
+    id: u64,
+    #[serde(default)]
+    in_reply_to_id: Option<u64>,
+    path: String,
+    #[serde(default)]
+    line: Option<u32>,
+    #[serde(default)]
+    side: Option<String>,
+    #[serde(default)]
+    user: Option<User>,
+    body: String,
+    created_at: String,
+}
+
+// Entry of a contents API directory listing
+#[derive(Deserialize)]
+struct ContentEntry {
+    name: String,
+    path: String,
+    // file, dir, symlink or submodule
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+// The parts of `/commits/{ref}` needed to list its files
+#[derive(Deserialize)]
+struct CommitTree {
+    sha: String,
+    commit: GitCommit,
+}
+
+#[derive(Deserialize)]
+struct GitCommit {
+    tree: GitObject,
+}
+
+#[derive(Deserialize)]
+struct GitObject {
+    sha: String,
+}
+
+#[derive(Deserialize)]
+struct GitTree {
+    tree: Vec<TreeEntry>,
+    truncated: bool,
+}
+
+#[derive(Deserialize)]
+struct Viewer {
+    login: String,
+}
+
+// A decoded response body, with how old it is when it came from the offline cache
+pub(crate) struct Fetched<T> {
+    pub(crate) body: T,
+    pub(crate) next: Option<String>,
+    pub(crate) stale_since: Option<u64>,
+}
+
+// GraphQL answers 200 even for failed queries, with the reasons in `errors`
+#[derive(Deserialize)]
+struct GraphQLResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQLError>,
+}
+
+#[derive(Deserialize)]
+struct GraphQLError {
+    message: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+// Long-lived GitHub REST session. The underlying `reqwest::Client` is built
+// once, so connections (and HTTP/2 streams) are pooled across requests, and
+// auth, accept and API version headers are applied uniformly to all of them.
+pub struct GitHubClient {
+    client: Client,
+    // REST base URL without trailing slash
+    api_url: String,
+    graphql_url: String,
+    authenticated: bool,
+    status: ApiStatus,
+    cache: HttpCache,
+    // serve everything from `cache` without touching the network
+    offline: bool,
+    settings: NetworkSettings,
+    // login of the token owner, looked up on first use
+    viewer: OnceCell<String>,
+}
+
+impl GitHubClient {
+    pub fn new(config: &Config, cache: HttpCache) -> Result<Self, reqwest::Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("application/vnd.github+json"),
+        );
+        headers.insert(
+            HeaderName::from_static("x-github-api-version"),
+            HeaderValue::from_static(API_VERSION),
         );
+        let authenticated = !config.token.is_empty();
+        if authenticated
+            && let Ok(mut value) = HeaderValue::from_str(&format!("Bearer {}", config.token))
+        {
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+
+        let client = Client::builder()
+            .user_agent("gte/0.1")
+            .default_headers(headers)
+            .connect_timeout(config.network.connect_timeout)
+            .read_timeout(config.network.read_timeout)
+            .build()?;
+
+        let status = ApiStatus::default();
+        status.set_offline(config.offline);
+
+        Ok(Self {
+            client,
+            api_url: config.api_url.clone(),
+            graphql_url: config.graphql_url.clone(),
+            authenticated,
+            status,
+            cache,
+            offline: config.offline,
+            settings: config.network.clone(),
+            viewer: OnceCell::new(),
+        })
+    }
+
+    pub fn status(&self) -> StatusSnapshot {
+        self.status.snapshot()
+    }
+
+    pub fn is_authenticated(&self) -> bool {
+        self.authenticated
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    async fn wait_before_retry(&self, attempt: u32, what: &str) {
+        let delay = retry_delay(&self.settings, attempt);
+        let _backoff = self.status.begin_backoff(
+            delay,
+            format!("{} (retry {}/{})", what, attempt, self.settings.max_retries),
+        );
+        tokio::time::sleep(delay).await;
+    }
+
+    // GET `url`, revalidating cached copies
+    async fn send(&self, url: &str) -> Result<CachedResponse, ApiError> {
+        self.send_cached(url, || self.client.get(url)).await
+    }
+
+    // Send the request `build` makes, cached under `key`, sleeping through rate
+    // limits and retrying transient failures; only for reads, which are safe to repeat
+    async fn send_cached<F>(&self, key: &str, build: F) -> Result<CachedResponse, ApiError>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let cached = self.cache.get(key).await;
+        if self.offline {
+            return cached.ok_or_else(|| ApiError::NotCached(format!("{} was never fetched", key)));
+        }
+        let mut attempt = 0;
+        let mut retry = 0;
+
+        loop {
+            let mut request = build();
+            if let Some(cached) = &cached {
+                // entries without a validator are only kept for offline use
+                if let Some(etag) = &cached.etag {
+                    request = request.header(IF_NONE_MATCH, etag);
+                }
+                if let Some(last_modified) = &cached.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, last_modified);
+                }
+            }
+
+            let response = match request.send().await {
+                Ok(response) => response,
+                Err(e) if is_transient(&e) && retry < self.settings.max_retries => {
+                    retry += 1;
+                    self.wait_before_retry(retry, describe_transient(&e)).await;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            self.status.record_headers(response.headers());
+
+            if response.status() == StatusCode::NOT_MODIFIED
+                && let Some(cached) = self.cache.touch(key).await
+            {
+                return Ok(cached);
+            }
 
-        let page_repos = client
-            .get(&url)
-            .header("Accept", "application/vnd.github+json")
-            .send()
-            .await?
-            .error_for_status()?
-            .json::<Vec<Repo>>()
+            match rate_limit_wait(response.status(), response.headers()) {
+                Some(wait) if attempt < MAX_RATE_LIMIT_RETRIES => {
+                    attempt += 1;
+                    let _backoff = self.status.begin_backoff(
+                        wait,
+                        format!(
+                            "Rate limited (retry {}/{})",
+                            attempt, MAX_RATE_LIMIT_RETRIES
+                        ),
+                    );
+                    tokio::time::sleep(wait).await;
+                    continue;
+                }
+                _ => {}
+            }
+
+            let headers = response.headers().clone();
+            let status = response.status();
+            let path = response.url().path().to_string();
+            if status.is_server_error() && retry < self.settings.max_retries {
+                retry += 1;
+                self.wait_before_retry(retry, &format!("GitHub returned {}", status))
+                    .await;
+                continue;
+            }
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                return Err(ApiError::from_response(status, &headers, &body, &path));
+            }
+
+            let body = match response.text().await {
+                Ok(body) => body,
+                Err(e) if is_transient(&e) && retry < self.settings.max_retries => {
+                    retry += 1;
+                    self.wait_before_retry(retry, describe_transient(&e)).await;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let fresh = CachedResponse {
+                etag: header_string(&headers, ETAG),
+                last_modified: header_string(&headers, LAST_MODIFIED),
+                next: next_link(&headers),
+                body,
+                fetched_at: now_secs(),
+            };
+            self.cache.insert(key, fresh.clone()).await;
+
+            return Ok(fresh);
+        }
+    }
+
+    // Send a change once. Writes are neither retried, since a repeated POST
+    // could apply twice, nor cached; the response body is returned as is.
+    async fn send_write(&self, request: RequestBuilder) -> Result<String, ApiError> {
+        if self.offline {
+            return Err(ApiError::Offline(String::from(
+                "changes can't be sent while offline",
+            )));
+        }
+
+        let response = request.send().await?;
+        self.status.record_headers(response.headers());
+        let status = response.status();
+        let headers = response.headers().clone();
+        let path = response.url().path().to_string();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(ApiError::from_response(status, &headers, &body, &path));
+        }
+        Ok(body)
+    }
+
+    // Send a change and decode what GitHub made of it
+    async fn write<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ApiError> {
+        let body = self.send_write(request).await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    // Fetch a single page, returning the body and the URL of the next page, if any
+    async fn get_page<T: DeserializeOwned>(&self, url: &str) -> Result<Fetched<T>, ApiError> {
+        let response = self.send(url).await?;
+        let body = serde_json::from_str::<T>(&response.body)?;
+
+        Ok(Fetched {
+            body,
+            next: response.next,
+            stale_since: self.offline.then_some(response.fetched_at),
+        })
+    }
+
+    // Fetch a file's raw content, cached apart from the JSON form of the same URL
+    async fn get_raw(&self, url: &str) -> Result<String, ApiError> {
+        let key = format!("{} raw", url);
+        let response = self
+            .send_cached(&key, || {
+                self.client
+                    .get(url)
+                    .header(ACCEPT, "application/vnd.github.raw+json")
+            })
+            .await?;
+        Ok(response.body)
+    }
+
+    // Run a read-only GraphQL query. Responses are cached under the query text
+    // and variables, so they are available offline like REST pages.
+    pub(crate) async fn graphql<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<Fetched<T>, ApiError> {
+        let key = self.graphql_key(query, &variables);
+        let body = serde_json::json!({ "query": query, "variables": variables });
+        let response = self
+            .send_cached(&key, || self.client.post(&self.graphql_url).json(&body))
+            .await?;
+
+        Ok(Fetched {
+            body: graphql_data(&response.body)?,
+            next: None,
+            stale_since: self.offline.then_some(response.fetched_at),
+        })
+    }
+
+    // GraphQL answers are cached under the endpoint, query and variables
+    pub(crate) fn graphql_key(&self, query: &str, variables: &serde_json::Value) -> String {
+        let body = serde_json::json!({ "query": query, "variables": variables });
+        format!("{} {}", self.graphql_url, body)
+    }
+
+    // Run a GraphQL mutation: sent once and never cached, like REST writes
+    pub(crate) async fn graphql_mutation<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<T, ApiError> {
+        let body = serde_json::json!({ "query": query, "variables": variables });
+        let response = self
+            .send_write(self.client.post(&self.graphql_url).json(&body))
             .await?;
+        graphql_data(&response)
+    }
+
+    // Follow `Link: rel="next"` from `url` until the last page
+    async fn get_all<T: DeserializeOwned>(&self, url: String) -> Result<Page<T>, ApiError> {
+        let mut all = Page::default();
+        let mut next = Some(url);
 
-        if page_repos.is_empty() {
-            break;
+        while let Some(url) = next {
+            let page = self.get_page::<Vec<T>>(&url).await?;
+            all.items.extend(page.body);
+            all.mark_stale(page.stale_since);
+            next = page.next;
         }
 
-        all_repos.extend(page_repos);
-        page += 1;
+        Ok(all)
     }
 
-    Ok(all_repos)
-}
+    // login of the user the token belongs to
+    pub async fn viewer_login(&self) -> Result<&str, ApiError> {
+        let login = self
+            .viewer
+            .get_or_try_init(|| async {
+                let url = format!("{}/user", self.api_url);
+                let viewer = self.get_page::<Viewer>(&url).await?;
+                Ok::<_, ApiError>(viewer.body.login)
+            })
+            .await?;
+        Ok(login.as_str())
+    }
 
-// Get private repos for current user
-pub async fn fetch_private_repos(token: &str) -> Result<Vec<Repo>, reqwest::Error> {
-    let client = build_client()?;
-    let url = "https://api.github.com/user/repos?per_page=100&affiliation=owner,collaborator";
+    // Whether `username` owns the token. Offline, with or without a token, the
+    // login an earlier session looked up decides, so the owner's repos are read
+    // back from the entry they were cached under.
+    async fn is_viewer(&self, username: &str) -> Result<bool, ApiError> {
+        if self.offline {
+            return Ok(self
+                .viewer_login()
+                .await
+                .is_ok_and(|login| login.eq_ignore_ascii_case(username)));
+        }
+        Ok(self.authenticated && self.viewer_login().await?.eq_ignore_ascii_case(username))
+    }
+
+    // Get a user's repos; for the token owner this includes private ones
+    pub async fn fetch_repos(&self, username: &str) -> Result<Page<Repo>, ApiError> {
+        if self.is_viewer(username).await? {
+            match self.fetch_private_repos().await {
+                // offline, a session that never listed them may have the public ones
+                Err(ApiError::NotCached(_)) => {}
+                result => return result,
+            }
+        }
+
+        let url = format!("{}/users/{}/repos?per_page=100", self.api_url, username);
+        self.get_all(url).await
+    }
+
+    // Get the token owner's repos and those they collaborate on, private ones included
+    pub async fn fetch_private_repos(&self) -> Result<Page<Repo>, ApiError> {
+        let url = format!(
+            "{}/user/repos?per_page=100&affiliation=owner,collaborator",
+            self.api_url
+        );
+        self.get_all(url).await
+    }
 
-    client
-        .get(url)
-        .bearer_auth(token)
-        .header("Accept", "application/vnd.github+json")
-        .send()
-        .await?
-        .error_for_status()?
-        .json::<Vec<Repo>>()
+    // Search repositories (GitHub caps search results at 1000)
+    pub async fn search_repos(&self, query: &str) -> Result<Page<Repo>, ApiError> {
+        let encoded_query = urlencoding::encode(query);
+        let mut all_repos = Page::default();
+        let mut next = Some(format!(
+            "{}/search/repositories?q={}&per_page=100",
+            self.api_url, encoded_query
+        ));
+
+        while let Some(url) = next {
+            let page = self.get_page::<SearchResponse>(&url).await?;
+            all_repos.items.extend(page.body.items);
+            all_repos.mark_stale(page.stale_since);
+            next = page.next;
+        }
+
+        Ok(all_repos)
+    }
+
+    // Get one page of repo issues, starting from the first one when `page_url` is None
+    pub async fn get_repo_issues(
+        &self,
+        repo: &str,
+        filter: &IssueFilter,
+        page_url: Option<&str>,
+    ) -> Result<Page<Issue>, ApiError> {
+        let url = match page_url {
+            Some(url) => url.to_string(),
+            None => format!(
+                "{}/repos/{}/issues?{}&per_page={}",
+                self.api_url,
+                repo,
+                filter.issue_params(),
+                TABLE_PAGE_SIZE
+            ),
+        };
+        let page = self.get_page(&url).await?;
+
+        Ok(Page {
+            items: page.body,
+            next: page.next,
+            stale_since: page.stale_since,
+        })
+    }
+
+    // Get one page of repo pull requests, starting from the first one when `page_url` is None
+    pub async fn get_repo_prs(
+        &self,
+        repo: &str,
+        filter: &IssueFilter,
+        page_url: Option<&str>,
+    ) -> Result<Page<PR>, ApiError> {
+        if filter.narrows() {
+            return self.search_prs(repo, filter, page_url).await;
+        }
+        let url = match page_url {
+            Some(url) => url.to_string(),
+            None => format!(
+                "{}/repos/{}/pulls?{}&per_page={}",
+                self.api_url,
+                repo,
+                filter.pr_params(),
+                TABLE_PAGE_SIZE
+            ),
+        };
+        let page = self.get_page(&url).await?;
+
+        Ok(Page {
+            items: page.body,
+            next: page.next,
+            stale_since: page.stale_since,
+        })
+    }
+
+    // PRs narrowed by what only search filters on. Hits are issue-shaped, so
+    // branches are left for the PR's own endpoint to fill in.
+    async fn search_prs(
+        &self,
+        repo: &str,
+        filter: &IssueFilter,
+        page_url: Option<&str>,
+    ) -> Result<Page<PR>, ApiError> {
+        let url = match page_url {
+            Some(url) => url.to_string(),
+            None => format!(
+                "{}/search/issues?q={}&per_page={}",
+                self.api_url,
+                urlencoding::encode(&format!(
+                    "repo:{} is:pr {}",
+                    repo,
+                    filter.search_qualifiers()
+                )),
+                TABLE_PAGE_SIZE
+            ),
+        };
+        let page = self.get_page::<PullSearchResponse>(&url).await?;
+
+        Ok(Page {
+            items: page.body.items.into_iter().map(PullHit::into_pr).collect(),
+            next: page.next,
+            stale_since: page.stale_since,
+        })
+    }
+
+    // Get a PR with everything its popup shows: reviews and the CI checks of its head commit
+    pub async fn get_pull_detail(&self, repo: &str, number: u32) -> Result<PullDetail, ApiError> {
+        let url = format!("{}/repos/{}/pulls/{}", self.api_url, repo, number);
+        let pr = self.get_page::<PR>(&url).await?.body;
+
+        let reviews = self.get_all::<Review>(format!(
+            "{}/repos/{}/pulls/{}/reviews?per_page=100",
+            self.api_url, repo, number
+        ));
+        let checks = async {
+            match &pr.head {
+                Some(head) => self.get_checks(repo, &head.sha).await,
+                None => Vec::new(),
+            }
+        };
+        let (reviews, checks) = tokio::join!(reviews, checks);
+
+        Ok(PullDetail {
+            pr,
+            reviews: reviews?.items,
+            checks,
+        })
+    }
+
+    // Files changed by a PR with their patches. GitHub lists at most 3000 files
+    // and leaves the patch out for binary files and very large diffs.
+    pub async fn get_pull_files(
+        &self,
+        repo: &str,
+        number: u32,
+    ) -> Result<Page<PullFile>, ApiError> {
+        let url = format!(
+            "{}/repos/{}/pulls/{}/files?per_page=100",
+            self.api_url, repo, number
+        );
+        self.get_all(url).await
+    }
+
+    // Review threads rebuilt from the PR's line comments. REST doesn't say
+    // whether a thread is resolved, nor give the id needed to resolve it.
+    pub async fn get_review_comments(
+        &self,
+        repo: &str,
+        number: u32,
+    ) -> Result<Vec<ReviewThread>, ApiError> {
+        let url = format!(
+            "{}/repos/{}/pulls/{}/comments?per_page=100",
+            self.api_url, repo, number
+        );
+        let comments: Page<PullReviewComment> = self.get_all(url).await?;
+
+        let mut threads: Vec<(u64, ReviewThread)> = Vec::new();
+        for comment in comments.items {
+            let reply = ReviewComment {
+                user: comment.user,
+                body: comment.body,
+                created_at: comment.created_at,
+            };
+            let root = comment.in_reply_to_id.unwrap_or(comment.id);
+            match threads.iter_mut().find(|(id, _)| *id == root) {
+                Some((_, thread)) => thread.comments.push(reply),
+                None => threads.push((
+                    comment.id,
+                    ReviewThread {
+                        id: None,
+                        path: comment.path,
+                        line: comment.line,
+                        side: comment.side.unwrap_or_else(|| String::from("RIGHT")),
+                        resolved: false,
+                        comments: vec![reply],
+                    },
+                )),
+            }
+        }
+        Ok(threads.into_iter().map(|(_, thread)| thread).collect())
+    }
+
+    // Commit statuses and check runs of `sha`. Best effort: tokens often lack
+    // the checks permission, and a PR is still worth showing without its CI.
+    async fn get_checks(&self, repo: &str, sha: &str) -> Vec<Check> {
+        let status_url = format!("{}/repos/{}/commits/{}/status", self.api_url, repo, sha);
+        let runs_url = format!(
+            "{}/repos/{}/commits/{}/check-runs?per_page=100",
+            self.api_url, repo, sha
+        );
+        let statuses = self.get_page::<CombinedStatus>(&status_url);
+        let runs = self.get_page::<CheckRuns>(&runs_url);
+        let (statuses, runs) = tokio::join!(statuses, runs);
+
+        let mut checks: Vec<Check> = statuses
+            .map(|s| s.body.statuses)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|status| Check {
+                name: status.context,
+                state: match status.state.as_str() {
+                    "success" => CheckState::Success,
+                    "pending" => CheckState::Pending,
+                    _ => CheckState::Failure,
+                },
+            })
+            .collect();
+        checks.extend(
+            runs.map(|r| r.body.check_runs)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|run| Check {
+                    state: match (run.status.as_str(), run.conclusion.as_deref()) {
+                        ("completed", Some("success")) => CheckState::Success,
+                        ("completed", Some("neutral" | "skipped" | "cancelled")) => {
+                            CheckState::Neutral
+                        }
+                        ("completed", _) => CheckState::Failure,
+                        _ => CheckState::Pending,
+                    },
+                    name: run.name,
+                }),
+        );
+        checks
+    }
+
+    pub async fn get_labels(&self, repo: &str) -> Result<Page<Label>, ApiError> {
+        let url = format!("{}/repos/{}/labels?per_page=100", self.api_url, repo);
+        self.get_all(url).await
+    }
+
+    // Users issues in `repo` can be assigned to
+    pub async fn get_assignees(&self, repo: &str) -> Result<Page<User>, ApiError> {
+        let url = format!("{}/repos/{}/assignees?per_page=100", self.api_url, repo);
+        self.get_all(url).await
+    }
+
+    pub async fn get_milestones(&self, repo: &str) -> Result<Page<Milestone>, ApiError> {
+        let url = format!(
+            "{}/repos/{}/milestones?state=open&per_page=100",
+            self.api_url, repo
+        );
+        self.get_all(url).await
+    }
+
+    // any milestone, closed ones included
+    pub async fn get_milestone(&self, repo: &str, number: u32) -> Result<Milestone, ApiError> {
+        let url = format!("{}/repos/{}/milestones/{}", self.api_url, repo, number);
+        Ok(self.get_page::<Milestone>(&url).await?.body)
+    }
+
+    // Markdown templates in `.github/ISSUE_TEMPLATE`; issue forms (`.yml`) aren't
+    // supported. A repo without the directory simply has no templates.
+    pub async fn get_issue_templates(&self, repo: &str) -> Result<Vec<IssueTemplate>, ApiError> {
+        let url = format!(
+            "{}/repos/{}/contents/.github/ISSUE_TEMPLATE",
+            self.api_url, repo
+        );
+        let entries = match self.get_page::<Vec<ContentEntry>>(&url).await {
+            Ok(page) => page.body,
+            Err(ApiError::NotFound(_)) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut templates = Vec::new();
+        for entry in entries {
+            if entry.kind != "file" || !entry.name.to_lowercase().ends_with(".md") {
+                continue;
+            }
+            let url = format!("{}/repos/{}/contents/{}", self.api_url, repo, entry.path);
+            let text = self.get_raw(&url).await?;
+            templates.push(IssueTemplate::parse(&entry.name, &text));
+        }
+        Ok(templates)
+    }
+
+    // The repo's README as raw Markdown, None when it has none
+    pub async fn get_readme(&self, repo: &str) -> Result<Option<String>, ApiError> {
+        let url = format!("{}/repos/{}/readme", self.api_url, repo);
+        match self.get_raw(&url).await {
+            Ok(text) => Ok(Some(text)),
+            Err(ApiError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // First page of branches and tags, to pick from in the code browser; any
+    // other ref can still be typed in
+    pub async fn get_branches(&self, repo: &str) -> Result<Page<GitRef>, ApiError> {
+        let url = format!("{}/repos/{}/branches?per_page=100", self.api_url, repo);
+        self.get_first_page(&url).await
+    }
+
+    pub async fn get_tags(&self, repo: &str) -> Result<Page<GitRef>, ApiError> {
+        let url = format!("{}/repos/{}/tags?per_page=100", self.api_url, repo);
+        self.get_first_page(&url).await
+    }
+
+    async fn get_first_page<T: DeserializeOwned>(&self, url: &str) -> Result<Page<T>, ApiError> {
+        let page = self.get_page::<Vec<T>>(url).await?;
+        Ok(Page {
+            items: page.body,
+            next: None,
+            stale_since: page.stale_since,
+        })
+    }
+
+    // Every path of a branch, tag or commit. The ref is resolved to its commit
+    // first, so files and permalinks keep pointing at what was listed.
+    pub async fn get_tree(&self, repo: &str, git_ref: &str) -> Result<RepoTree, ApiError> {
+        let url = format!(
+            "{}/repos/{}/commits/{}",
+            self.api_url,
+            repo,
+            encode_path(git_ref)
+        );
+        let commit = self.get_page::<CommitTree>(&url).await?.body;
+        let url = format!(
+            "{}/repos/{}/git/trees/{}?recursive=1",
+            self.api_url, repo, commit.commit.tree.sha
+        );
+        let tree = self.get_page::<GitTree>(&url).await?.body;
+        Ok(RepoTree {
+            commit: commit.sha,
+            entries: tree.tree,
+            truncated: tree.truncated,
+        })
+    }
+
+    // A file as of `commit`, base64 encoded
+    pub async fn get_file(
+        &self,
+        repo: &str,
+        path: &str,
+        commit: &str,
+    ) -> Result<FileContent, ApiError> {
+        let url = format!(
+            "{}/repos/{}/contents/{}?ref={}",
+            self.api_url,
+            repo,
+            encode_path(path),
+            commit
+        );
+        Ok(self.get_page::<FileContent>(&url).await?.body)
+    }
+
+    // Get one page of a history, newest first, starting from the first one
+    // when `page_url` is None
+    pub async fn get_commits(
+        &self,
+        repo: &str,
+        filter: &CommitFilter,
+        page_url: Option<&str>,
+    ) -> Result<Page<Commit>, ApiError> {
+        let url = match page_url {
+            Some(url) => url.to_string(),
+            None => {
+                let mut params = filter.params();
+                if !params.is_empty() {
+                    params.push('&');
+                }
+                format!(
+                    "{}/repos/{}/commits?{}per_page={}",
+                    self.api_url, repo, params, TABLE_PAGE_SIZE
+                )
+            }
+        };
+        let page = self.get_page(&url).await?;
+
+        Ok(Page {
+            items: page.body,
+            next: page.next,
+            stale_since: page.stale_since,
+        })
+    }
+
+    // Commits of a PR, oldest first. GitHub lists at most 250.
+    pub async fn get_pull_commits(
+        &self,
+        repo: &str,
+        number: u32,
+    ) -> Result<Page<Commit>, ApiError> {
+        let url = format!(
+            "{}/repos/{}/pulls/{}/commits?per_page=100",
+            self.api_url, repo, number
+        );
+        self.get_all(url).await
+    }
+
+    // One commit with its stats and the patch of each file it changed, of
+    // which GitHub sends the first 300
+    pub async fn get_commit(&self, repo: &str, sha: &str) -> Result<Commit, ApiError> {
+        let url = format!("{}/repos/{}/commits/{}", self.api_url, repo, sha);
+        Ok(self.get_page::<Commit>(&url).await?.body)
+    }
+
+    pub async fn create_issue(&self, repo: &str, issue: &NewIssue) -> Result<Issue, ApiError> {
+        let url = format!("{}/repos/{}/issues", self.api_url, repo);
+        self.write(self.client.post(url).json(issue)).await
+    }
+
+    // Change an issue's state, labels, assignees or milestone
+    pub async fn update_issue(
+        &self,
+        repo: &str,
+        number: u32,
+        update: &IssueUpdate,
+    ) -> Result<Issue, ApiError> {
+        let url = format!("{}/repos/{}/issues/{}", self.api_url, repo, number);
+        self.write(self.client.patch(url).json(update)).await
+    }
+
+    // Lock an issue's conversation, optionally saying why: off-topic,
+    // too heated, resolved or spam
+    pub async fn lock_issue(
+        &self,
+        repo: &str,
+        number: u32,
+        reason: Option<&str>,
+    ) -> Result<(), ApiError> {
+        let url = format!("{}/repos/{}/issues/{}/lock", self.api_url, repo, number);
+        let body = match reason {
+            Some(reason) => serde_json::json!({ "lock_reason": reason }),
+            None => serde_json::json!({}),
+        };
+        self.send_write(self.client.put(url).json(&body)).await?;
+        Ok(())
+    }
+
+    pub async fn unlock_issue(&self, repo: &str, number: u32) -> Result<(), ApiError> {
+        let url = format!("{}/repos/{}/issues/{}/lock", self.api_url, repo, number);
+        self.send_write(self.client.delete(url)).await?;
+        Ok(())
+    }
+
+    // Comment on an issue or PR
+    pub async fn post_comment(
+        &self,
+        repo: &str,
+        number: u32,
+        body: &str,
+    ) -> Result<Comment, ApiError> {
+        let url = format!("{}/repos/{}/issues/{}/comments", self.api_url, repo, number);
+        self.write(
+            self.client
+                .post(url)
+                .json(&serde_json::json!({ "body": body })),
+        )
         .await
+    }
+
+    // Approve, request changes on or comment on a PR
+    pub async fn submit_review(
+        &self,
+        repo: &str,
+        number: u32,
+        review: &NewReview,
+    ) -> Result<Review, ApiError> {
+        let url = format!("{}/repos/{}/pulls/{}/reviews", self.api_url, repo, number);
+        self.write(self.client.post(url).json(review)).await
+    }
+
+    pub async fn merge_pull(
+        &self,
+        repo: &str,
+        number: u32,
+        merge: &MergeRequest,
+    ) -> Result<MergeResult, ApiError> {
+        let url = format!("{}/repos/{}/pulls/{}/merge", self.api_url, repo, number);
+        self.write(self.client.put(url).json(merge)).await
+    }
+
+    pub async fn delete_branch(&self, repo: &str, branch: &str) -> Result<(), ApiError> {
+        // branch names may hold slashes, which are part of the ref path
+        let url = format!(
+            "{}/repos/{}/git/refs/heads/{}",
+            self.api_url,
+            repo,
+            encode_path(branch)
+        );
+        self.send_write(self.client.delete(url)).await?;
+        Ok(())
+    }
+
+    // Get one page of an issue's (or PR's) comments, starting from the first one
+    // when `page_url` is None
+    pub async fn get_issue_comments(
+        &self,
+        repo: &str,
+        number: u32,
+        page_url: Option<&str>,
+    ) -> Result<Page<Comment>, ApiError> {
+        let url = match page_url {
+            Some(url) => url.to_string(),
+            None => format!(
+                "{}/repos/{}/issues/{}/comments?per_page=100",
+                self.api_url, repo, number
+            ),
+        };
+        let page = self.get_page(&url).await?;
+
+        Ok(Page {
+            items: page.body,
+            next: page.next,
+            stale_since: page.stale_since,
+        })
+    }
 }
 
-// Search repositories
-pub async fn search_repos(query: &str) -> Result<Vec<Repo>, reqwest::Error> {
-    let client = build_client()?;
-    let encoded_query = urlencoding::encode(query);
-    let url = format!(
-        "https://api.github.com/search/repositories?q={}&per_page=100",
-        encoded_query
-    );
-
-    let search_response = client
-        .get(&url)
-        .header("Accept", "application/vnd.github+json")
-        .send()
-        .await?
-        .error_for_status()?
-        .json::<SearchResponse>()
-        .await?;
-
-    Ok(search_response.items)
-}
-
-// Get repo issues
-pub async fn get_repo_issues(repo: &str, token: &str) -> Result<Vec<Issue>, reqwest::Error> {
-    let client = build_client()?;
-
-    let url = format!("https://api.github.com/repos/{}/issues?state=all", repo);
-    client
-        .get(&url)
-        .bearer_auth(token)
-        .header("Accept", "application/vnd.github+json")
-        .send()
-        .await?
-        .error_for_status()?
-        .json::<Vec<Issue>>()
-        .await
+// `data` of a GraphQL response, or its errors mapped onto the REST ones
+fn graphql_data<T: DeserializeOwned>(body: &str) -> Result<T, ApiError> {
+    let decoded: GraphQLResponse<T> = serde_json::from_str(body)?;
+
+    if let Some(error) = decoded.errors.first() {
+        let messages: Vec<&str> = decoded.errors.iter().map(|e| e.message.as_str()).collect();
+        let response = |status| Box::new(ErrorResponse::new(status, messages.join("; ")));
+        return Err(match error.kind.as_deref() {
+            Some("NOT_FOUND") => ApiError::NotFound(response(404)),
+            Some("FORBIDDEN") => ApiError::Forbidden(response(403)),
+            Some("RATE_LIMITED") => ApiError::RateLimited {
+                response: response(403),
+                reset: None,
+            },
+            _ => ApiError::GraphQL(messages.join("; ")),
+        });
+    }
+    decoded
+        .data
+        .ok_or_else(|| ApiError::Decode(String::from("GraphQL response without data")))
 }
 
-// Get repo pull requests
-pub async fn get_repo_prs(repo: &str, token: &str) -> Result<Vec<PR>, reqwest::Error> {
-    let client = build_client()?;
+// encode each segment of a slash separated path, keeping the slashes
+fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(urlencoding::encode)
+        .collect::<Vec<_>>()
+        .join("/")
+}
 
-    let url = format!("https://api.github.com/repos/{}/pulls?state=all", repo);
-    client
-        .get(&url)
-        .bearer_auth(token)
-        .header("Accept", "application/vnd.github+json")
-        .send()
-        .await?
-        .error_for_status()?
-        .json::<Vec<PR>>()
-        .await
+fn header_string(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers.get(name)?.to_str().ok().map(str::to_string)
+}
+
+// failures worth retrying: the request may not even have reached GitHub
+fn is_transient(e: &reqwest::Error) -> bool {
+    e.is_connect() || e.is_timeout() || e.is_request() || e.is_body()
+}
+
+fn describe_transient(e: &reqwest::Error) -> &'static str {
+    if e.is_timeout() {
+        "Request timed out"
+    } else if e.is_connect() {
+        "Connection failed"
+    } else {
+        "Connection dropped"
+    }
+}
+
+// exponential backoff with jitter: a random delay in [d/2, d] where d doubles
+// from the base delay on every attempt, so parallel requests don't retry in lockstep
+fn retry_delay(settings: &NetworkSettings, attempt: u32) -> Duration {
+    let doubled = settings
+        .retry_base_delay
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16));
+    let capped = doubled.min(settings.retry_max_delay);
+    let half = capped / 2;
+    let random = RandomState::new().build_hasher().finish();
+    let jitter = random % (half.as_millis() as u64 + 1);
+    half + Duration::from_millis(jitter)
+}
+
+// Extract the `rel="next"` target from a `Link` response header
+pub fn next_link(headers: &HeaderMap) -> Option<String> {
+    let link = headers.get(LINK)?.to_str().ok()?;
+    let mut rest = link;
+
+    // entries look like `<url>; rel="next", <url>; rel="last"`
+    while let Some(start) = rest.find('<') {
+        let end = start + rest[start..].find('>')?;
+        let url = &rest[start + 1..end];
+        let params_end = rest[end..].find('<').map_or(rest.len(), |i| end + i);
+        let params = &rest[end + 1..params_end];
+
+        if params
+            .split(';')
+            .any(|param| param.trim().trim_end_matches(',').trim() == r#"rel="next""#)
+        {
+            return Some(url.to_string());
+        }
+        rest = &rest[params_end..];
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{DetailQuery, GitHubBackend};
+    use crate::filter::StateFilter;
+    use crate::graphql;
+    use crate::mock_server::{MockServer, response};
+    use crate::models::{MergeMethod, NewReviewComment};
+    use reqwest::header::HeaderValue;
+
+    fn link(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(LINK, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn next_link_picks_the_next_relation() {
+        let headers = link(
+            r#"<https://api.github.com/repositories/1/issues?page=2>; rel="next", <https://api.github.com/repositories/1/issues?page=5>; rel="last""#,
+        );
+        assert_eq!(
+            next_link(&headers).as_deref(),
+            Some("https://api.github.com/repositories/1/issues?page=2")
+        );
+    }
+
+    #[test]
+    fn next_link_is_found_after_other_relations() {
+        let headers = link(
+            r#"<https://api.github.com/x?page=1>; rel="prev", <https://api.github.com/x?page=3>; rel="next""#,
+        );
+        assert_eq!(
+            next_link(&headers).as_deref(),
+            Some("https://api.github.com/x?page=3")
+        );
+    }
+
+    #[test]
+    fn next_link_is_none_on_the_last_page() {
+        let headers = link(
+            r#"<https://api.github.com/x?page=1>; rel="first", <https://api.github.com/x?page=2>; rel="prev""#,
+        );
+        assert_eq!(next_link(&headers), None);
+        assert_eq!(next_link(&HeaderMap::new()), None);
+    }
+
+    fn offline_client(token: &str, cache: HttpCache) -> GitHubClient {
+        let config = Config {
+            username: String::from("octocat"),
+            token: String::from(token),
+            api_url: String::from("https://api.github.com"),
+            graphql_url: String::from("https://api.github.com/graphql"),
+            cache_dir: None,
+            offline: true,
+            network: NetworkSettings::default(),
+        };
+        GitHubClient::new(&config, cache).unwrap()
+    }
+
+    async fn cache_body(cache: &HttpCache, url: &str, body: serde_json::Value) {
+        let response = CachedResponse {
+            etag: None,
+            last_modified: None,
+            body: body.to_string(),
+            next: None,
+            fetched_at: 0,
+        };
+        cache.insert(url, response).await;
+    }
+
+    fn repo_json(full_name: &str) -> serde_json::Value {
+        serde_json::json!({
+            "full_name": full_name,
+            "description": null,
+            "stargazers_count": 0,
+            "forks_count": 0,
+            "language": null,
+            "open_issues_count": 0,
+            "updated_at": "2024-01-02T00:00:00Z",
+            "created_at": "2024-01-01T00:00:00Z",
+            "html_url": format!("https://github.com/{}", full_name),
+        })
+    }
+
+    #[tokio::test]
+    async fn offline_the_owners_repos_come_from_where_they_were_cached() {
+        let cache = HttpCache::default();
+        cache_body(
+            &cache,
+            "https://api.github.com/user",
+            serde_json::json!({ "login": "octocat" }),
+        )
+        .await;
+        cache_body(
+            &cache,
+            "https://api.github.com/user/repos?per_page=100&affiliation=owner,collaborator",
+            serde_json::json!([repo_json("octocat/secret"), repo_json("friend/shared")]),
+        )
+        .await;
+
+        for token in ["", "ghp_token"] {
+            let client = offline_client(token, cache.clone());
+            let repos = client.fetch_repos("Octocat").await.unwrap();
+            let names: Vec<&str> = repos.items.iter().map(|r| r.full_name.as_str()).collect();
+            assert_eq!(names, ["octocat/secret", "friend/shared"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn offline_other_users_repos_use_their_own_entry() {
+        let cache = HttpCache::default();
+        cache_body(
+            &cache,
+            "https://api.github.com/user",
+            serde_json::json!({ "login": "octocat" }),
+        )
+        .await;
+        cache_body(
+            &cache,
+            "https://api.github.com/users/hubot/repos?per_page=100",
+            serde_json::json!([repo_json("hubot/public")]),
+        )
+        .await;
+
+        let client = offline_client("", cache);
+        let repos = client.fetch_repos("hubot").await.unwrap();
+        assert_eq!(repos.items[0].full_name, "hubot/public");
+        assert!(matches!(
+            client.fetch_repos("someone").await,
+            Err(ApiError::NotCached(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn offline_without_a_token_reads_what_graphql_cached() {
+        let cache = HttpCache::default();
+        let client = offline_client("", cache.clone());
+        let query = DetailQuery::issues(None);
+        let (text, variables) = graphql::detail_request("octocat/hello-world", &query).unwrap();
+        let issue = serde_json::json!({
+            "__typename": "Issue",
+            "number": 5,
+            "title": "Cached with a token",
+            "state": "OPEN",
+            "body": null,
+            "url": "https://github.com/octocat/hello-world/issues/5",
+            "author": { "login": "octocat" },
+            "comments": { "totalCount": 0 },
+            "assignees": { "nodes": [] },
+            "milestone": null,
+            "labels": { "nodes": [] },
+        });
+        let page_info = serde_json::json!({ "hasNextPage": false, "endCursor": null });
+        cache_body(
+            &cache,
+            &client.graphql_key(&text, &variables),
+            serde_json::json!({ "data": { "repository": {
+                "issues": { "pageInfo": page_info, "nodes": [issue] },
+            } } }),
+        )
+        .await;
+
+        let variables = serde_json::json!({
+            "owner": "octocat", "name": "hello-world", "number": 3, "after": null,
+        });
+        let thread = serde_json::json!({
+            "id": "T_1",
+            "isResolved": true,
+            "path": "src/main.rs",
+            "line": 2,
+            "diffSide": "RIGHT",
+            "comments": { "pageInfo": page_info, "nodes": [
+                { "author": { "login": "monalisa" }, "body": "nit", "createdAt": "2024-01-03T00:00:00Z" },
+            ] },
+        });
+        cache_body(
+            &cache,
+            &client.graphql_key(graphql::REVIEW_THREADS_QUERY, &variables),
+            serde_json::json!({ "data": { "repository": { "pullRequest": {
+                "reviewThreads": { "pageInfo": page_info, "nodes": [thread] },
+            } } } }),
+        )
+        .await;
+
+        let detail = GitHubBackend::get_repo_detail(&client, "octocat/hello-world", query)
+            .await
+            .unwrap();
+        assert_eq!(detail.issues.unwrap().unwrap().items[0].number, 5);
+        let threads = GitHubBackend::get_review_threads(&client, "octocat/hello-world", 3)
+            .await
+            .unwrap();
+        assert!(threads[0].resolved);
+        assert_eq!(threads[0].comments[0].body, "nit");
+    }
+
+    #[tokio::test]
+    async fn offline_falls_back_to_what_rest_cached() {
+        let cache = HttpCache::default();
+        cache_body(
+            &cache,
+            "https://api.github.com/repos/octocat/hello-world/pulls/3/comments?per_page=100",
+            serde_json::json!([]),
+        )
+        .await;
+
+        for token in ["", "ghp_token"] {
+            let client = offline_client(token, cache.clone());
+            let threads = GitHubBackend::get_review_threads(&client, "octocat/hello-world", 3)
+                .await
+                .unwrap();
+            assert!(threads.is_empty());
+            assert!(matches!(
+                GitHubBackend::get_review_threads(&client, "octocat/hello-world", 4).await,
+                Err(ApiError::NotCached(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried_until_one_succeeds() {
+        let server = MockServer::start(vec![
+            response(502, &[], ""),
+            response(503, &[], ""),
+            response(200, &[], r#"{"login": "octocat"}"#),
+        ])
+        .await;
+        let client = server.client(2);
+        assert_eq!(client.viewer_login().await.unwrap(), "octocat");
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn retries_give_up_after_the_configured_number() {
+        let server = MockServer::start(vec![
+            response(500, &[], ""),
+            response(500, &[], ""),
+            response(200, &[], r#"{"login": "octocat"}"#),
+        ])
+        .await;
+        let client = server.client(1);
+        assert!(matches!(
+            client.viewer_login().await,
+            Err(ApiError::Http(response)) if response.status == 500
+        ));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let server = MockServer::start(vec![
+            response(404, &[], r#"{"message": "Not Found"}"#),
+            response(200, &[], "{}"),
+        ])
+        .await;
+        let client = server.client(3);
+        assert!(matches!(
+            client.get_milestone("octocat/hello-world", 9).await,
+            Err(ApiError::NotFound(_))
+        ));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn writes_are_never_retried() {
+        let server =
+            MockServer::start(vec![response(502, &[], ""), response(201, &[], "{}")]).await;
+        let client = server.client(3);
+        let result = client
+            .post_comment("octocat/hello-world", 1, "Thanks!")
+            .await;
+        assert!(matches!(result, Err(ApiError::Http(response)) if response.status == 502));
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+    }
+
+    #[test]
+    fn retry_delays_double_with_jitter_up_to_the_cap() {
+        let settings = NetworkSettings {
+            retry_base_delay: Duration::from_millis(100),
+            retry_max_delay: Duration::from_secs(1),
+            ..NetworkSettings::default()
+        };
+        let between = |attempt, low, high| {
+            for _ in 0..20 {
+                let delay = retry_delay(&settings, attempt).as_millis();
+                assert!(
+                    (low..=high).contains(&delay),
+                    "attempt {}: {}ms",
+                    attempt,
+                    delay
+                );
+            }
+        };
+        between(1, 50, 100);
+        between(2, 100, 200);
+        between(3, 200, 400);
+        // capped from here on, however many attempts
+        between(5, 500, 1000);
+        between(40, 500, 1000);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_reads_wait_and_try_again() {
+        let limited = [
+            ("retry-after", "0"),
+            ("x-ratelimit-limit", "60"),
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "0"),
+        ];
+        let quota = [
+            ("x-ratelimit-limit", "60"),
+            ("x-ratelimit-remaining", "59"),
+            ("x-ratelimit-reset", "1700000000"),
+        ];
+        let server = MockServer::start(vec![
+            response(429, &limited, r#"{"message": "API rate limit exceeded"}"#),
+            response(200, &quota, r#"{"login": "octocat"}"#),
+        ])
+        .await;
+        // rate limits are waited out, not counted as retries
+        let client = server.client(0);
+        assert_eq!(client.viewer_login().await.unwrap(), "octocat");
+        assert_eq!(server.requests().len(), 2);
+        let status = client.status();
+        assert_eq!(status.rate_limits["core"].remaining, 59);
+        assert!(status.backoff.is_none());
+    }
+
+    #[tokio::test]
+    async fn a_refusal_that_isnt_a_rate_limit_fails_at_once() {
+        let server = MockServer::start(vec![
+            response(403, &[], r#"{"message": "Resource not accessible"}"#),
+            response(200, &[], r#"{"login": "octocat"}"#),
+        ])
+        .await;
+        let client = server.client(3);
+        assert!(matches!(
+            client.viewer_login().await,
+            Err(ApiError::Forbidden(_))
+        ));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_not_modified_answer_reuses_and_refreshes_the_cached_body() {
+        let server = MockServer::start(vec![response(304, &[], "")]).await;
+        let url = format!("{}/user", server.url);
+        let cache = HttpCache::default();
+        let stored = CachedResponse {
+            etag: Some(String::from("\"v1\"")),
+            last_modified: Some(String::from("Mon, 01 Jan 2024 00:00:00 GMT")),
+            body: String::from(r#"{"login": "octocat"}"#),
+            next: None,
+            fetched_at: 0,
+        };
+        cache.insert(&url, stored).await;
+
+        let client = server.client_with(cache.clone(), 0);
+        assert_eq!(client.viewer_login().await.unwrap(), "octocat");
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].header("if-none-match"), Some("\"v1\""));
+        assert_eq!(
+            requests[0].header("if-modified-since"),
+            Some("Mon, 01 Jan 2024 00:00:00 GMT")
+        );
+        // the server vouched for the body just now
+        let refreshed = cache.get(&url).await.unwrap();
+        assert!(refreshed.fetched_at > 0);
+        assert_eq!(refreshed.etag.as_deref(), Some("\"v1\""));
+    }
+
+    #[tokio::test]
+    async fn a_fresh_answer_is_stored_with_its_validators() {
+        let server = MockServer::start(vec![response(
+            200,
+            &[
+                ("etag", "\"v2\""),
+                ("last-modified", "Tue, 02 Jan 2024 00:00:00 GMT"),
+            ],
+            r#"{"login": "octocat"}"#,
+        )])
+        .await;
+        let cache = HttpCache::default();
+        let client = server.client_with(cache.clone(), 0);
+        assert_eq!(client.viewer_login().await.unwrap(), "octocat");
+        assert_eq!(server.requests()[0].header("if-none-match"), None);
+
+        let stored = cache.get(&format!("{}/user", server.url)).await.unwrap();
+        assert_eq!(stored.etag.as_deref(), Some("\"v2\""));
+        assert_eq!(
+            stored.last_modified.as_deref(),
+            Some("Tue, 02 Jan 2024 00:00:00 GMT")
+        );
+        assert_eq!(stored.body, r#"{"login": "octocat"}"#);
+    }
+
+    #[tokio::test]
+    async fn a_merge_is_put_with_its_method_message_and_head() {
+        let server = MockServer::start(vec![response(
+            200,
+            &[],
+            r#"{"sha": "6dcb09b", "merged": true, "message": "Pull Request successfully merged"}"#,
+        )])
+        .await;
+        let merge = MergeRequest {
+            merge_method: MergeMethod::Squash,
+            commit_title: Some(String::from("Fix the README (#3)")),
+            commit_message: None,
+            sha: Some(String::from("abc")),
+        };
+        let result = server
+            .client(0)
+            .merge_pull("octocat/hello-world", 3, &merge)
+            .await
+            .unwrap();
+        assert!(result.merged);
+
+        let request = &server.requests()[0];
+        assert_eq!(request.method, "PUT");
+        assert_eq!(request.path, "/repos/octocat/hello-world/pulls/3/merge");
+        assert_eq!(
+            request.json(),
+            serde_json::json!({
+                "merge_method": "squash",
+                "commit_title": "Fix the README (#3)",
+                "sha": "abc",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn a_refused_merge_is_reported_not_retried() {
+        let server = MockServer::start(vec![
+            response(405, &[], r#"{"message": "Pull Request is not mergeable"}"#),
+            response(200, &[], r#"{"merged": true, "message": ""}"#),
+        ])
+        .await;
+        let result = server
+            .client(3)
+            .merge_pull("octocat/hello-world", 3, &MergeRequest::default())
+            .await;
+        assert!(result.is_err());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_review_is_posted_with_its_line_comments() {
+        let server = MockServer::start(vec![response(
+            200,
+            &[],
+            r#"{"id": 80, "user": {"login": "octocat"}, "state": "CHANGES_REQUESTED"}"#,
+        )])
+        .await;
+        let review = NewReview {
+            event: String::from("REQUEST_CHANGES"),
+            body: String::new(),
+            comments: vec![NewReviewComment {
+                path: String::from("src/main.rs"),
+                body: String::from("off by one"),
+                line: 12,
+                side: String::from("RIGHT"),
+                start_line: Some(10),
+                start_side: Some(String::from("RIGHT")),
+            }],
+        };
+        let submitted = server
+            .client(0)
+            .submit_review("octocat/hello-world", 3, &review)
+            .await
+            .unwrap();
+        assert_eq!(submitted.state, "CHANGES_REQUESTED");
+
+        let request = &server.requests()[0];
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/repos/octocat/hello-world/pulls/3/reviews");
+        // an empty body is left out rather than sent blank
+        assert_eq!(
+            request.json(),
+            serde_json::json!({
+                "event": "REQUEST_CHANGES",
+                "comments": [{
+                    "path": "src/main.rs",
+                    "body": "off by one",
+                    "line": 12,
+                    "side": "RIGHT",
+                    "start_line": 10,
+                    "start_side": "RIGHT",
+                }],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn deleting_a_branch_keeps_the_slashes_of_its_name() {
+        let server = MockServer::start(vec![response(204, &[], "")]).await;
+        server
+            .client(0)
+            .delete_branch("octocat/hello-world", "fix/read me")
+            .await
+            .unwrap();
+        let request = &server.requests()[0];
+        assert_eq!(request.method, "DELETE");
+        assert_eq!(
+            request.path,
+            "/repos/octocat/hello-world/git/refs/heads/fix/read%20me"
+        );
+    }
+
+    #[tokio::test]
+    async fn the_commit_filter_narrows_the_listing_request() {
+        let server =
+            MockServer::start(vec![response(200, &[], "[]"), response(200, &[], "[]")]).await;
+        let client = server.client(0);
+        let filter = CommitFilter {
+            git_ref: Some(String::from("release/1.0")),
+            path: Some(String::from("src/app.rs")),
+            author: Some(String::from("octocat")),
+        };
+        client
+            .get_commits("octocat/hello-world", &filter, None)
+            .await
+            .unwrap();
+        client
+            .get_commits("octocat/hello-world", &CommitFilter::default(), None)
+            .await
+            .unwrap();
+
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/repos/octocat/hello-world/commits?sha=release%2F1.0&path=src%2Fapp.rs&author=octocat&per_page=50",
+                "/repos/octocat/hello-world/commits?per_page=50",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn narrowed_prs_come_from_issue_search() {
+        let hits = serde_json::json!({
+            "total_count": 1,
+            "items": [{
+                "number": 3,
+                "title": "Fix startup crash",
+                "state": "closed",
+                "body": null,
+                "html_url": "https://github.com/octocat/hello-world/pull/3",
+                "user": { "login": "octocat" },
+                "draft": false,
+                "pull_request": {
+                    "html_url": "https://github.com/octocat/hello-world/pull/3",
+                    "merged_at": "2024-01-03T00:00:00Z",
+                },
+            }],
+        });
+        let server = MockServer::start(vec![response(200, &[], &hits.to_string())]).await;
+        let filter = IssueFilter {
+            state: StateFilter::Closed,
+            author: Some(String::from("octocat")),
+            ..IssueFilter::default()
+        };
+        let page = server
+            .client(0)
+            .get_repo_prs("octocat/hello-world", &filter, None)
+            .await
+            .unwrap();
+        assert_eq!(page.items[0].number, 3);
+        assert_eq!(
+            page.items[0].merged_at.as_deref(),
+            Some("2024-01-03T00:00:00Z")
+        );
+        assert_eq!(
+            server.requests()[0].path,
+            "/search/issues?q=repo%3Aoctocat%2Fhello-world%20is%3Apr%20is%3Aclosed%20author%3Aoctocat\
+             %20sort%3Acreated-desc&per_page=50"
+        );
+    }
 }
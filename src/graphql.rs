@@ -0,0 +1,1198 @@
+use crate::backend::DetailQuery;
+use crate::error::{ApiError, ErrorResponse};
+use crate::filter::{IssueFilter, SortDirection, SortField, StateFilter};
+use crate::github::{GitHubClient, TABLE_PAGE_SIZE};
+use crate::models::{
+    BranchRef, Issue, Label, MergeMethod, Milestone, PR, Page, PullRequestRef, Repo, RepoCounts,
+    RepoDetail, RepoRef, ReviewComment, ReviewThread, User,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+// Selections for each part of the detail view; only the requested ones are
+// put into the query
+
+const METADATA_FIELDS: &str = "
+    nameWithOwner
+    description
+    stargazerCount
+    forkCount
+    primaryLanguage { name }
+    createdAt
+    updatedAt
+    url
+    defaultBranchRef { name }
+    openIssues: issues(states: OPEN) { totalCount }
+    openPullRequests: pullRequests(states: OPEN) { totalCount }";
+
+const ISSUE_NODE: &str = "
+        __typename
+        number
+        title
+        state
+        body
+        url
+        author { login }
+        comments { totalCount }
+        locked
+        assignees(first: 10) { nodes { login } }
+        milestone { number title }
+        labels(first: 20) { nodes { name color description url } }";
+
+const PR_NODE: &str = "
+        number
+        title
+        state
+        body
+        url
+        author { login }
+        isDraft
+        mergedAt
+        headRefName
+        headRefOid
+        headRepository { nameWithOwner }
+        baseRefName
+        baseRefOid
+        baseRepository { nameWithOwner }";
+
+// Response shapes, mirroring the selections above
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Data {
+    repository: Option<RepositoryNode>,
+    issue_search: Option<Connection<IssueNode>>,
+    pr_search: Option<Connection<PRNode>>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RepositoryNode {
+    name_with_owner: Option<String>,
+    description: Option<String>,
+    stargazer_count: Option<u32>,
+    fork_count: Option<u32>,
+    primary_language: Option<Language>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    url: Option<String>,
+    default_branch_ref: Option<BranchName>,
+    open_issues: Option<TotalCount>,
+    open_pull_requests: Option<TotalCount>,
+    issues: Option<Connection<IssueNode>>,
+    pull_requests: Option<Connection<PRNode>>,
+}
+
+#[derive(Deserialize)]
+struct Language {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct BranchName {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TotalCount {
+    total_count: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Connection<T> {
+    page_info: PageInfo,
+    nodes: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct Nodes<T> {
+    nodes: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct IssueNode {
+    #[serde(rename = "__typename")]
+    typename: String,
+    number: u32,
+    title: String,
+    state: String,
+    body: Option<String>,
+    url: String,
+    author: Option<User>,
+    comments: TotalCount,
+    #[serde(default)]
+    locked: bool,
+    assignees: Option<Nodes<User>>,
+    milestone: Option<Milestone>,
+    labels: Option<Nodes<LabelNode>>,
+}
+
+#[derive(Deserialize)]
+struct LabelNode {
+    name: String,
+    color: Option<String>,
+    description: Option<String>,
+    url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PRNode {
+    number: u32,
+    title: String,
+    state: String,
+    body: Option<String>,
+    url: String,
+    author: Option<User>,
+    is_draft: bool,
+    merged_at: Option<String>,
+    head_ref_name: String,
+    head_ref_oid: String,
+    // None once the fork the branch lived in is deleted
+    head_repository: Option<RepositoryName>,
+    base_ref_name: String,
+    base_ref_oid: String,
+    base_repository: Option<RepositoryName>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RepositoryName {
+    name_with_owner: String,
+}
+
+const PR_FIELDS: &str = "
+    pullRequests(first: $first, after: $prsAfter, states: $prStates, orderBy: $prOrder) {
+      pageInfo { hasNextPage endCursor }
+      nodes {{PR_NODE}
+      }
+    }";
+
+const ISSUE_FIELDS: &str = "
+    issues(first: $first, after: $issuesAfter, filterBy: $issueFilter, orderBy: $issueOrder) {
+      pageInfo { hasNextPage endCursor }
+      nodes {{ISSUE_NODE}
+      }
+    }";
+
+// Issues mixed with PRs come from the search index, the only listing holding
+// both; `search` is a root field, so it sits next to `repository`
+const ISSUE_SEARCH_FIELDS: &str = "
+  issueSearch: search(query: $issuesSearch, type: ISSUE, first: $first, after: $issuesAfter) {
+    pageInfo { hasNextPage endCursor }
+    nodes {
+      ... on Issue {{ISSUE_NODE}
+      }
+      ... on PullRequest {{ISSUE_NODE}
+      }
+    }
+  }";
+
+// PRs narrowed by labels, assignee, author or milestone, which only search
+// filters on; `is:pr` keeps the hits to PRs
+const PR_SEARCH_FIELDS: &str = "
+  prSearch: search(query: $prsSearch, type: ISSUE, first: $first, after: $prsAfter) {
+    pageInfo { hasNextPage endCursor }
+    nodes {
+      ... on PullRequest {{PR_NODE}
+      }
+    }
+  }";
+
+fn searches_prs(query: &DetailQuery) -> bool {
+    query.prs && query.pr_filter.narrows()
+}
+
+// whether any field under `repository` is asked for; an empty selection is invalid
+fn selects_repository(query: &DetailQuery) -> bool {
+    query.metadata
+        || (query.issues && !query.issues_include_prs)
+        || (query.prs && !searches_prs(query))
+}
+
+// Build the query for the parts `query` selects; GraphQL rejects unused
+// variables, so each listing's variables are only declared alongside it
+fn build_query(query: &DetailQuery) -> String {
+    let mut variables = Vec::new();
+    let mut fields = String::new();
+    let mut root_fields = String::new();
+
+    if selects_repository(query) {
+        variables.extend(["$owner: String!", "$name: String!"]);
+    }
+    if query.metadata {
+        fields.push_str(METADATA_FIELDS);
+    }
+    if query.issues || query.prs {
+        variables.push("$first: Int!");
+    }
+    if query.issues {
+        variables.push("$issuesAfter: String");
+        if query.issues_include_prs {
+            variables.push("$issuesSearch: String!");
+            root_fields.push_str(&ISSUE_SEARCH_FIELDS.replace("{ISSUE_NODE}", ISSUE_NODE));
+        } else {
+            variables.extend(["$issueFilter: IssueFilters", "$issueOrder: IssueOrder"]);
+            fields.push_str(&ISSUE_FIELDS.replace("{ISSUE_NODE}", ISSUE_NODE));
+        }
+    }
+    if query.prs {
+        variables.push("$prsAfter: String");
+        if searches_prs(query) {
+            variables.push("$prsSearch: String!");
+            root_fields.push_str(&PR_SEARCH_FIELDS.replace("{PR_NODE}", PR_NODE));
+        } else {
+            variables.extend(["$prStates: [PullRequestState!]", "$prOrder: IssueOrder"]);
+            fields.push_str(&PR_FIELDS.replace("{PR_NODE}", PR_NODE));
+        }
+    }
+
+    let repository = if selects_repository(query) {
+        format!(
+            "\n  repository(owner: $owner, name: $name) {{{}\n  }}",
+            fields
+        )
+    } else {
+        String::new()
+    };
+
+    format!(
+        "query({}) {{{}{}\n}}",
+        variables.join(", "),
+        repository,
+        root_fields
+    )
+}
+
+fn order(filter: &IssueFilter) -> serde_json::Value {
+    let field = match filter.sort {
+        SortField::Created => "CREATED_AT",
+        SortField::Updated => "UPDATED_AT",
+        SortField::Comments => "COMMENTS",
+    };
+    let direction = match filter.direction {
+        SortDirection::Asc => "ASC",
+        SortDirection::Desc => "DESC",
+    };
+    json!({ "field": field, "direction": direction })
+}
+
+// `IssueFilters` input. A field left out doesn't filter, while `null` asks
+// for no assignee or no milestone, which REST spells `none`
+fn issue_filter_by(filter: &IssueFilter) -> serde_json::Value {
+    let mut filter_by = serde_json::Map::new();
+    match filter.state {
+        StateFilter::Open => filter_by.insert(String::from("states"), json!(["OPEN"])),
+        StateFilter::Closed => filter_by.insert(String::from("states"), json!(["CLOSED"])),
+        StateFilter::All => None,
+    };
+    if !filter.labels.is_empty() {
+        filter_by.insert(String::from("labels"), json!(filter.labels));
+    }
+    if let Some(assignee) = &filter.assignee {
+        filter_by.insert(String::from("assignee"), none_as_null(assignee));
+    }
+    if let Some(author) = &filter.author {
+        filter_by.insert(String::from("createdBy"), json!(author));
+    }
+    if let Some(milestone) = &filter.milestone {
+        filter_by.insert(String::from("milestoneNumber"), none_as_null(milestone));
+    }
+    serde_json::Value::Object(filter_by)
+}
+
+fn none_as_null(value: &str) -> serde_json::Value {
+    match value {
+        "none" => serde_json::Value::Null,
+        value => json!(value),
+    }
+}
+
+fn pr_states(filter: &IssueFilter) -> serde_json::Value {
+    match filter.state {
+        StateFilter::Open => json!(["OPEN"]),
+        StateFilter::Closed => json!(["CLOSED", "MERGED"]),
+        StateFilter::All => serde_json::Value::Null,
+    }
+}
+
+// `OPEN` -> `open`, with merged PRs reported as closed like the REST API does
+fn rest_state(state: &str) -> String {
+    match state {
+        "MERGED" => String::from("closed"),
+        state => state.to_lowercase(),
+    }
+}
+
+fn page_of<N, T>(connection: Connection<N>, convert: fn(N) -> T) -> Page<T> {
+    Page {
+        items: connection.nodes.into_iter().map(convert).collect(),
+        next: connection
+            .page_info
+            .end_cursor
+            .filter(|_| connection.page_info.has_next_page),
+        stale_since: None,
+    }
+}
+
+fn to_issue(node: IssueNode) -> Issue {
+    let pull_request = (node.typename == "PullRequest").then(|| PullRequestRef {
+        html_url: Some(node.url.clone()),
+    });
+    Issue {
+        title: node.title,
+        state: rest_state(&node.state),
+        body: node.body,
+        number: node.number,
+        html_url: node.url,
+        labels: node
+            .labels
+            .map(|labels| labels.nodes.into_iter().map(to_label).collect())
+            .unwrap_or_default(),
+        user: node.author,
+        comments: node.comments.total_count,
+        pull_request,
+        assignees: node
+            .assignees
+            .map(|assignees| assignees.nodes)
+            .unwrap_or_default(),
+        milestone: node.milestone,
+        locked: node.locked,
+        // not selectable on the PR half of the search fragments
+        state_reason: None,
+    }
+}
+
+fn to_label(node: LabelNode) -> Label {
+    Label {
+        url: node.url,
+        description: node.description,
+        color: node.color,
+        name: node.name,
+    }
+}
+
+fn to_pr(node: PRNode) -> PR {
+    PR {
+        number: node.number,
+        state: rest_state(&node.state),
+        body: node.body,
+        title: node.title,
+        html_url: node.url,
+        user: node.author,
+        head: Some(BranchRef {
+            name: node.head_ref_name,
+            sha: node.head_ref_oid,
+            repo: node.head_repository.map(to_repo_ref),
+        }),
+        base: Some(BranchRef {
+            name: node.base_ref_name,
+            sha: node.base_ref_oid,
+            repo: node.base_repository.map(to_repo_ref),
+        }),
+        draft: node.is_draft,
+        merged_at: node.merged_at,
+        ..PR::default()
+    }
+}
+
+fn to_repo_ref(node: RepositoryName) -> RepoRef {
+    RepoRef {
+        full_name: node.name_with_owner,
+    }
+}
+
+// REST-shaped repo from the metadata fields, if they were selected
+fn to_repo(node: &mut RepositoryNode) -> Option<Repo> {
+    Some(Repo {
+        full_name: node.name_with_owner.take()?,
+        description: node.description.take(),
+        stargazers_count: node.stargazer_count.unwrap_or_default(),
+        forks_count: node.fork_count.unwrap_or_default(),
+        language: node.primary_language.take().map(|l| l.name),
+        open_issues_count: node.open_issues.as_ref().map_or(0, |c| c.total_count),
+        updated_at: node.updated_at.take().unwrap_or_default(),
+        created_at: node.created_at.take().unwrap_or_default(),
+        html_url: node.url.take().unwrap_or_default(),
+        default_branch: node.default_branch_ref.take().map(|b| b.name),
+    })
+}
+
+// Fetch every part of a repo's detail view `query` selects in a single request
+pub async fn fetch_repo_detail(
+    client: &GitHubClient,
+    repo: &str,
+    query: &DetailQuery,
+) -> Result<RepoDetail, ApiError> {
+    let (text, variables) = detail_request(repo, query)?;
+    let fetched = client.graphql::<Data>(&text, variables).await?;
+    let mut node = match fetched.body.repository {
+        Some(node) => node,
+        None if selects_repository(query) => {
+            return Err(ApiError::NotFound(Box::new(ErrorResponse::new(
+                404,
+                format!("repository {} not found", repo),
+            ))));
+        }
+        None => RepositoryNode::default(),
+    };
+
+    let counts = match (&node.open_issues, &node.open_pull_requests) {
+        (Some(issues), Some(prs)) => Some(RepoCounts {
+            open_issues: issues.total_count,
+            open_prs: prs.total_count,
+        }),
+        _ => None,
+    };
+    let mut issues = node
+        .issues
+        .take()
+        .or(fetched.body.issue_search)
+        .map(|c| page_of(c, to_issue));
+    let mut prs = node
+        .pull_requests
+        .take()
+        .or(fetched.body.pr_search)
+        .map(|c| page_of(c, to_pr));
+    if let Some(page) = &mut issues {
+        page.mark_stale(fetched.stale_since);
+    }
+    if let Some(page) = &mut prs {
+        page.mark_stale(fetched.stale_since);
+    }
+
+    Ok(RepoDetail {
+        repo: to_repo(&mut node),
+        counts,
+        issues: issues.map(Ok),
+        prs: prs.map(Ok),
+    })
+}
+
+// The query text and variables `fetch_repo_detail` sends for `query`
+pub(crate) fn detail_request(
+    repo: &str,
+    query: &DetailQuery,
+) -> Result<(String, serde_json::Value), ApiError> {
+    let (owner, name) = split_repo(repo)?;
+    // only pass the variables the query declares
+    let mut variables = json!({});
+    if selects_repository(query) {
+        variables["owner"] = json!(owner);
+        variables["name"] = json!(name);
+    }
+    if query.issues || query.prs {
+        variables["first"] = json!(TABLE_PAGE_SIZE);
+    }
+    if query.issues {
+        variables["issuesAfter"] = json!(query.issues_after);
+        if query.issues_include_prs {
+            variables["issuesSearch"] = json!(format!(
+                "repo:{} {}",
+                repo,
+                query.issue_filter.search_qualifiers()
+            ));
+        } else {
+            variables["issueFilter"] = issue_filter_by(&query.issue_filter);
+            variables["issueOrder"] = order(&query.issue_filter);
+        }
+    }
+    if query.prs {
+        variables["prsAfter"] = json!(query.prs_after);
+        if searches_prs(query) {
+            variables["prsSearch"] = json!(format!(
+                "repo:{} is:pr {}",
+                repo,
+                query.pr_filter.search_qualifiers()
+            ));
+        } else {
+            variables["prStates"] = pr_states(&query.pr_filter);
+            variables["prOrder"] = order(&query.pr_filter);
+        }
+    }
+    Ok((build_query(query), variables))
+}
+
+fn split_repo(repo: &str) -> Result<(&str, &str), ApiError> {
+    repo.split_once('/')
+        .ok_or_else(|| ApiError::Decode(format!("invalid repository name {}", repo)))
+}
+
+// PR changes the REST API has no endpoint for. Mutations take the PR's node
+// id, so it is looked up first.
+
+const PULL_ID_QUERY: &str = "
+query($owner: String!, $name: String!, $number: Int!) {
+  repository(owner: $owner, name: $name) {
+    pullRequest(number: $number) { id }
+  }
+}";
+
+const AUTO_MERGE_MUTATION: &str = "
+mutation($id: ID!, $method: PullRequestMergeMethod!) {
+  enablePullRequestAutoMerge(input: { pullRequestId: $id, mergeMethod: $method }) {
+    clientMutationId
+  }
+}";
+
+const READY_MUTATION: &str = "
+mutation($id: ID!) {
+  markPullRequestReadyForReview(input: { pullRequestId: $id }) { clientMutationId }
+}";
+
+const DRAFT_MUTATION: &str = "
+mutation($id: ID!) {
+  convertPullRequestToDraft(input: { pullRequestId: $id }) { clientMutationId }
+}";
+
+#[derive(Deserialize)]
+struct PullIdData {
+    repository: Option<PullIdRepository>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PullIdRepository {
+    pull_request: Option<NodeId>,
+}
+
+#[derive(Deserialize)]
+struct NodeId {
+    id: String,
+}
+
+async fn pull_request_id(
+    client: &GitHubClient,
+    repo: &str,
+    number: u32,
+) -> Result<String, ApiError> {
+    let (owner, name) = split_repo(repo)?;
+    let fetched = client
+        .graphql::<PullIdData>(
+            PULL_ID_QUERY,
+            json!({ "owner": owner, "name": name, "number": number }),
+        )
+        .await?;
+    fetched
+        .body
+        .repository
+        .and_then(|r| r.pull_request)
+        .map(|node| node.id)
+        .ok_or_else(|| {
+            ApiError::NotFound(Box::new(ErrorResponse::new(
+                404,
+                format!("pull request #{} not found in {}", number, repo),
+            )))
+        })
+}
+
+// Have GitHub merge the PR with `method` once its requirements are met
+pub async fn enable_auto_merge(
+    client: &GitHubClient,
+    repo: &str,
+    number: u32,
+    method: MergeMethod,
+) -> Result<(), ApiError> {
+    let id = pull_request_id(client, repo, number).await?;
+    client
+        .graphql_mutation::<serde_json::Value>(
+            AUTO_MERGE_MUTATION,
+            json!({ "id": id, "method": method.name().to_uppercase() }),
+        )
+        .await?;
+    Ok(())
+}
+
+// Convert a PR to a draft, or mark a draft ready for review
+pub async fn set_draft(
+    client: &GitHubClient,
+    repo: &str,
+    number: u32,
+    draft: bool,
+) -> Result<(), ApiError> {
+    let id = pull_request_id(client, repo, number).await?;
+    let mutation = if draft {
+        DRAFT_MUTATION
+    } else {
+        READY_MUTATION
+    };
+    client
+        .graphql_mutation::<serde_json::Value>(mutation, json!({ "id": id }))
+        .await?;
+    Ok(())
+}
+
+pub(crate) const REVIEW_THREADS_QUERY: &str = "
+query($owner: String!, $name: String!, $number: Int!, $after: String) {
+  repository(owner: $owner, name: $name) {
+    pullRequest(number: $number) {
+      reviewThreads(first: 100, after: $after) {
+        pageInfo { hasNextPage endCursor }
+        nodes {
+          id
+          isResolved
+          path
+          line
+          diffSide
+          comments(first: 100) {
+            pageInfo { hasNextPage endCursor }
+            nodes { author { login } body createdAt }
+          }
+        }
+      }
+    }
+  }
+}";
+
+// The rest of the comments of a thread too long for the first page
+const THREAD_COMMENTS_QUERY: &str = "
+query($id: ID!, $after: String) {
+  node(id: $id) {
+    ... on PullRequestReviewThread {
+      comments(first: 100, after: $after) {
+        pageInfo { hasNextPage endCursor }
+        nodes { author { login } body createdAt }
+      }
+    }
+  }
+}";
+
+const RESOLVE_MUTATION: &str = "
+mutation($id: ID!) {
+  resolveReviewThread(input: { threadId: $id }) { clientMutationId }
+}";
+
+const UNRESOLVE_MUTATION: &str = "
+mutation($id: ID!) {
+  unresolveReviewThread(input: { threadId: $id }) { clientMutationId }
+}";
+
+#[derive(Deserialize)]
+struct ThreadsData {
+    repository: Option<ThreadsRepository>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThreadsRepository {
+    pull_request: Option<ThreadsPull>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThreadsPull {
+    review_threads: Connection<ThreadNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThreadNode {
+    id: String,
+    is_resolved: bool,
+    path: String,
+    line: Option<u32>,
+    diff_side: String,
+    comments: Connection<ThreadCommentNode>,
+}
+
+#[derive(Deserialize)]
+struct ThreadCommentsData {
+    node: Option<ThreadComments>,
+}
+
+#[derive(Deserialize)]
+struct ThreadComments {
+    comments: Connection<ThreadCommentNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThreadCommentNode {
+    author: Option<User>,
+    body: String,
+    created_at: String,
+}
+
+// Review threads of a PR with whether they are resolved, paged through until
+// every thread and every comment on them is in
+pub async fn fetch_review_threads(
+    client: &GitHubClient,
+    repo: &str,
+    number: u32,
+) -> Result<Vec<ReviewThread>, ApiError> {
+    let (owner, name) = split_repo(repo)?;
+    let mut threads = Vec::new();
+    let mut after: Option<String> = None;
+    loop {
+        let fetched = client
+            .graphql::<ThreadsData>(
+                REVIEW_THREADS_QUERY,
+                json!({ "owner": owner, "name": name, "number": number, "after": after }),
+            )
+            .await?;
+        let pull = fetched
+            .body
+            .repository
+            .and_then(|r| r.pull_request)
+            .ok_or_else(|| {
+                ApiError::NotFound(Box::new(ErrorResponse::new(
+                    404,
+                    format!("pull request #{} not found in {}", number, repo),
+                )))
+            })?;
+
+        let page = pull.review_threads;
+        for node in page.nodes {
+            let mut comments = node.comments.nodes;
+            let mut comments_after = node
+                .comments
+                .page_info
+                .end_cursor
+                .filter(|_| node.comments.page_info.has_next_page);
+            while let Some(cursor) = comments_after {
+                let more = client
+                    .graphql::<ThreadCommentsData>(
+                        THREAD_COMMENTS_QUERY,
+                        json!({ "id": node.id, "after": cursor }),
+                    )
+                    .await?
+                    .body
+                    .node
+                    .map(|thread| thread.comments);
+                let Some(more) = more else {
+                    break;
+                };
+                comments.extend(more.nodes);
+                comments_after = more
+                    .page_info
+                    .end_cursor
+                    .filter(|_| more.page_info.has_next_page);
+            }
+            threads.push(ReviewThread {
+                id: Some(node.id),
+                path: node.path,
+                line: node.line,
+                side: node.diff_side,
+                resolved: node.is_resolved,
+                comments: comments
+                    .into_iter()
+                    .map(|c| ReviewComment {
+                        user: c.author,
+                        body: c.body,
+                        created_at: c.created_at,
+                    })
+                    .collect(),
+            });
+        }
+
+        after = page
+            .page_info
+            .end_cursor
+            .filter(|_| page.page_info.has_next_page);
+        if after.is_none() {
+            return Ok(threads);
+        }
+    }
+}
+
+pub async fn set_thread_resolved(
+    client: &GitHubClient,
+    thread_id: &str,
+    resolved: bool,
+) -> Result<(), ApiError> {
+    let mutation = if resolved {
+        RESOLVE_MUTATION
+    } else {
+        UNRESOLVE_MUTATION
+    };
+    client
+        .graphql_mutation::<serde_json::Value>(mutation, json!({ "id": thread_id }))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::{MockServer, response};
+
+    #[test]
+    fn unset_filters_are_left_out() {
+        assert_eq!(
+            issue_filter_by(&IssueFilter::default()),
+            json!({ "states": ["OPEN"] })
+        );
+        let all = IssueFilter {
+            state: StateFilter::All,
+            ..IssueFilter::default()
+        };
+        assert_eq!(issue_filter_by(&all), json!({}));
+    }
+
+    #[test]
+    fn none_becomes_null() {
+        let filter = IssueFilter {
+            state: StateFilter::Closed,
+            assignee: Some(String::from("none")),
+            milestone: Some(String::from("none")),
+            ..IssueFilter::default()
+        };
+        assert_eq!(
+            issue_filter_by(&filter),
+            json!({ "states": ["CLOSED"], "assignee": null, "milestoneNumber": null })
+        );
+    }
+
+    #[test]
+    fn set_filters_pass_through() {
+        let filter = IssueFilter {
+            labels: vec![String::from("bug")],
+            assignee: Some(String::from("*")),
+            author: Some(String::from("octocat")),
+            milestone: Some(String::from("3")),
+            ..IssueFilter::default()
+        };
+        assert_eq!(
+            issue_filter_by(&filter),
+            json!({
+                "states": ["OPEN"],
+                "labels": ["bug"],
+                "assignee": "*",
+                "createdBy": "octocat",
+                "milestoneNumber": "3",
+            })
+        );
+    }
+
+    // the variables a query declares, in order
+    fn declared(query: &str) -> Vec<&str> {
+        let start = query.find('(').unwrap() + 1;
+        let end = query.find(')').unwrap();
+        query[start..end]
+            .split(", ")
+            .map(|variable| variable.split(':').next().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn only_the_requested_parts_are_selected() {
+        let metadata = DetailQuery {
+            metadata: true,
+            ..DetailQuery::default()
+        };
+        let query = build_query(&metadata);
+        assert_eq!(declared(&query), vec!["$owner", "$name"]);
+        assert!(query.contains("stargazerCount"));
+        assert!(!query.contains("issues(first"));
+        assert!(!query.contains("pullRequests(first"));
+
+        let query = build_query(&DetailQuery::prs(None));
+        assert_eq!(
+            declared(&query),
+            vec![
+                "$owner",
+                "$name",
+                "$first",
+                "$prsAfter",
+                "$prStates",
+                "$prOrder"
+            ]
+        );
+        assert!(query.contains("pullRequests(first: $first, after: $prsAfter"));
+        assert!(!query.contains("stargazerCount"));
+        assert!(!query.contains("issues(first"));
+
+        let query = build_query(&DetailQuery::all());
+        assert!(query.contains("stargazerCount"));
+        assert!(query.contains("issues(first: $first, after: $issuesAfter"));
+        assert!(query.contains("pullRequests(first"));
+        assert!(!query.contains("issueSearch"));
+    }
+
+    #[test]
+    fn issues_mixed_with_prs_come_from_search_alone() {
+        let query = DetailQuery {
+            issues_include_prs: true,
+            ..DetailQuery::issues(None)
+        };
+        let text = build_query(&query);
+        // nothing under `repository` is asked for, so it is left out
+        assert!(!text.contains("repository("));
+        assert_eq!(
+            declared(&text),
+            vec!["$first", "$issuesAfter", "$issuesSearch"]
+        );
+        assert!(text.contains("issueSearch: search(query: $issuesSearch"));
+        assert!(text.contains("... on PullRequest"));
+
+        let (_, variables) = detail_request("octocat/hello-world", &query).unwrap();
+        assert_eq!(
+            variables,
+            json!({
+                "first": TABLE_PAGE_SIZE,
+                "issuesAfter": null,
+                "issuesSearch": "repo:octocat/hello-world is:open sort:created-desc",
+            })
+        );
+    }
+
+    #[test]
+    fn cursors_continue_each_listing_on_its_own() {
+        let (text, variables) = detail_request(
+            "octocat/hello-world",
+            &DetailQuery::issues(Some(String::from("Y2"))),
+        )
+        .unwrap();
+        assert_eq!(variables["issuesAfter"], json!("Y2"));
+        assert!(variables.get("prsAfter").is_none());
+        // every variable passed is declared, and the other way round
+        let mut passed: Vec<String> = variables
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|name| format!("${}", name))
+            .collect();
+        let mut wanted: Vec<String> = declared(&text).into_iter().map(String::from).collect();
+        passed.sort();
+        wanted.sort();
+        assert_eq!(passed, wanted);
+
+        let (_, variables) = detail_request(
+            "octocat/hello-world",
+            &DetailQuery::prs(Some(String::from("UDE"))),
+        )
+        .unwrap();
+        assert_eq!(variables["prsAfter"], json!("UDE"));
+        assert!(variables.get("issuesAfter").is_none());
+        assert!(matches!(
+            detail_request("hello-world", &DetailQuery::all()),
+            Err(ApiError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn the_end_cursor_is_only_kept_while_there_are_more_pages() {
+        let connection = |has_next_page| Connection {
+            page_info: PageInfo {
+                has_next_page,
+                end_cursor: Some(String::from("Y3")),
+            },
+            nodes: vec![1, 2],
+        };
+        let page = page_of(connection(true), |n: u32| n * 10);
+        assert_eq!(page.items, vec![10, 20]);
+        assert_eq!(page.next.as_deref(), Some("Y3"));
+        assert_eq!(page_of(connection(false), |n: u32| n).next, None);
+    }
+
+    #[tokio::test]
+    async fn a_detail_page_carries_the_cursor_of_the_next() {
+        let body = json!({
+            "data": {
+                "repository": {
+                    "pullRequests": {
+                        "pageInfo": { "hasNextPage": true, "endCursor": "UDI" },
+                        "nodes": [{
+                            "number": 3,
+                            "title": "Fix",
+                            "state": "MERGED",
+                            "body": null,
+                            "url": "https://github.com/octocat/hello-world/pull/3",
+                            "author": { "login": "octocat" },
+                            "isDraft": false,
+                            "mergedAt": "2024-01-03T00:00:00Z",
+                            "headRefName": "fix",
+                            "headRefOid": "abc",
+                            "headRepository": { "nameWithOwner": "monalisa/hello-world" },
+                            "baseRefName": "main",
+                            "baseRefOid": "def",
+                            "baseRepository": { "nameWithOwner": "octocat/hello-world" },
+                        }, {
+                            "number": 2,
+                            "title": "From a deleted fork",
+                            "state": "OPEN",
+                            "body": null,
+                            "url": "https://github.com/octocat/hello-world/pull/2",
+                            "author": null,
+                            "isDraft": true,
+                            "mergedAt": null,
+                            "headRefName": "patch-1",
+                            "headRefOid": "123",
+                            "headRepository": null,
+                            "baseRefName": "main",
+                            "baseRefOid": "def",
+                            "baseRepository": { "nameWithOwner": "octocat/hello-world" },
+                        }],
+                    },
+                },
+            },
+        });
+        let server = MockServer::start(vec![response(200, &[], &body.to_string())]).await;
+        let client = server.client(0);
+        let detail = fetch_repo_detail(
+            &client,
+            "octocat/hello-world",
+            &DetailQuery::prs(Some(String::from("UDE"))),
+        )
+        .await
+        .unwrap();
+
+        let request = &server.requests()[0];
+        assert_eq!(request.path, "/graphql");
+        assert_eq!(request.json()["variables"]["prsAfter"], json!("UDE"));
+        assert!(detail.repo.is_none());
+        assert!(detail.issues.is_none());
+        let prs = detail.prs.unwrap().unwrap();
+        assert_eq!(prs.next.as_deref(), Some("UDI"));
+        assert_eq!(prs.items[0].state, "closed");
+        // merged PRs are told apart from closed ones, and forks from the repo
+        assert_eq!(
+            prs.items[0].merged_at.as_deref(),
+            Some("2024-01-03T00:00:00Z")
+        );
+        let head_repo = |pr: &PR| pr.head.as_ref()?.repo.as_ref().map(|r| r.full_name.clone());
+        assert_eq!(
+            head_repo(&prs.items[0]).as_deref(),
+            Some("monalisa/hello-world")
+        );
+        assert_eq!(prs.items[1].merged_at, None);
+        assert_eq!(head_repo(&prs.items[1]), None);
+    }
+
+    fn pull_id(id: &str) -> String {
+        let body = json!({ "data": { "repository": { "pullRequest": { "id": id } } } });
+        response(200, &[], &body.to_string())
+    }
+
+    fn mutation_done(name: &str) -> String {
+        let body = json!({ "data": { name: { "clientMutationId": null } } });
+        response(200, &[], &body.to_string())
+    }
+
+    #[tokio::test]
+    async fn drafts_are_toggled_on_the_pull_requests_node() {
+        for (draft, mutation) in [
+            (true, "convertPullRequestToDraft"),
+            (false, "markPullRequestReadyForReview"),
+        ] {
+            let server = MockServer::start(vec![pull_id("PR_kw1"), mutation_done(mutation)]).await;
+            set_draft(&server.client(0), "octocat/hello-world", 3, draft)
+                .await
+                .unwrap();
+
+            let requests = server.requests();
+            assert_eq!(requests.len(), 2);
+            let lookup = requests[0].json();
+            assert_eq!(
+                lookup["variables"],
+                json!({ "owner": "octocat", "name": "hello-world", "number": 3 })
+            );
+            let change = requests[1].json();
+            assert!(change["query"].as_str().unwrap().contains(mutation));
+            assert_eq!(change["variables"], json!({ "id": "PR_kw1" }));
+        }
+    }
+
+    #[tokio::test]
+    async fn auto_merge_is_enabled_with_the_chosen_method() {
+        let server = MockServer::start(vec![
+            pull_id("PR_kw1"),
+            mutation_done("enablePullRequestAutoMerge"),
+        ])
+        .await;
+        enable_auto_merge(
+            &server.client(0),
+            "octocat/hello-world",
+            3,
+            MergeMethod::Rebase,
+        )
+        .await
+        .unwrap();
+        let change = server.requests()[1].json();
+        assert!(
+            change["query"]
+                .as_str()
+                .unwrap()
+                .contains("enablePullRequestAutoMerge")
+        );
+        assert_eq!(
+            change["variables"],
+            json!({ "id": "PR_kw1", "method": "REBASE" })
+        );
+    }
+
+    #[tokio::test]
+    async fn no_mutation_is_sent_for_a_missing_pull_request() {
+        let missing = json!({ "data": { "repository": { "pullRequest": null } } });
+        let server = MockServer::start(vec![
+            response(200, &[], &missing.to_string()),
+            mutation_done("convertPullRequestToDraft"),
+        ])
+        .await;
+        let result = set_draft(&server.client(0), "octocat/hello-world", 9, true).await;
+        assert!(matches!(result, Err(ApiError::NotFound(_))));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn mutation_errors_are_surfaced() {
+        let refused = json!({
+            "data": null,
+            "errors": [{ "message": "Pull request is in clean status" }],
+        });
+        let server = MockServer::start(vec![
+            pull_id("PR_kw1"),
+            response(200, &[], &refused.to_string()),
+        ])
+        .await;
+        let result = enable_auto_merge(
+            &server.client(0),
+            "octocat/hello-world",
+            3,
+            MergeMethod::Merge,
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn narrowed_prs_are_searched_for() {
+        let query = DetailQuery {
+            pr_filter: IssueFilter {
+                labels: vec![String::from("bug")],
+                ..IssueFilter::default()
+            },
+            ..DetailQuery::prs(Some(String::from("UDE")))
+        };
+        let (text, variables) = detail_request("octocat/hello-world", &query).unwrap();
+        assert!(!text.contains("repository("));
+        assert!(!text.contains("pullRequests("));
+        assert!(text.contains("prSearch: search(query: $prsSearch"));
+        assert_eq!(
+            variables,
+            json!({
+                "first": TABLE_PAGE_SIZE,
+                "prsAfter": "UDE",
+                "prsSearch": "repo:octocat/hello-world is:pr is:open label:\"bug\" sort:created-desc",
+            })
+        );
+
+        // the rest of the detail view still comes from the repository
+        let everything = DetailQuery {
+            pr_filter: query.pr_filter.clone(),
+            ..DetailQuery::all()
+        };
+        let text = build_query(&everything);
+        assert!(text.contains("repository(") && text.contains("issues(first"));
+        assert!(text.contains("prSearch") && !text.contains("pullRequests(first"));
+    }
+}
@@ -0,0 +1,80 @@
+use ratatui::style::{Color, Style};
+use ratatui::text::Span;
+use std::path::Path;
+use std::sync::OnceLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+
+// Syntax definitions and theme are parsed once, on first use, since loading
+// them takes a noticeable moment
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    THEME.get_or_init(|| {
+        let mut themes = ThemeSet::load_defaults();
+        themes
+            .themes
+            .remove("base16-eighties.dark")
+            .unwrap_or_default()
+    })
+}
+
+// pick the syntax by file extension, then by file name (`Makefile`, ...)
+fn syntax_for(path: &str) -> &'static SyntaxReference {
+    let syntaxes = syntaxes();
+    let file = Path::new(path);
+    file.extension()
+        .and_then(|ext| syntaxes.find_syntax_by_extension(&ext.to_string_lossy()))
+        .or_else(|| {
+            file.file_name()
+                .and_then(|name| syntaxes.find_syntax_by_extension(&name.to_string_lossy()))
+        })
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text())
+}
+
+// Colors a file line by line, keeping parser state across lines so multi-line
+// strings and comments come out right
+pub struct Highlighter {
+    lines: HighlightLines<'static>,
+}
+
+impl Highlighter {
+    pub fn for_path(path: &str) -> Self {
+        Self {
+            lines: HighlightLines::new(syntax_for(path), theme()),
+        }
+    }
+
+    // for fenced code blocks, named by language (`rust`, `rs`, `python`, ...);
+    // None when the language isn't known
+    pub fn for_language(language: &str) -> Option<Self> {
+        let syntax = syntaxes().find_syntax_by_token(language)?;
+        Some(Self {
+            lines: HighlightLines::new(syntax, theme()),
+        })
+    }
+
+    // foreground colors only, so callers can still set a background
+    pub fn highlight(&mut self, line: &str) -> Vec<Span<'static>> {
+        let with_newline = format!("{}\n", line);
+        match self.lines.highlight_line(&with_newline, syntaxes()) {
+            Ok(regions) => regions
+                .into_iter()
+                .map(|(style, text)| {
+                    let fg = style.foreground;
+                    Span::styled(
+                        text.trim_end_matches('\n').to_string(),
+                        Style::default().fg(Color::Rgb(fg.r, fg.g, fg.b)),
+                    )
+                })
+                .filter(|span| !span.content.is_empty())
+                .collect(),
+            Err(_) => vec![Span::from(line.to_string())],
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crossterm::{
+    clipboard::CopyToClipboard,
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
@@ -8,24 +9,45 @@ use crossterm::event::Event;
 
 use ratatui::{Terminal, backend::CrosstermBackend};
 use std::io;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio;
 
+pub mod api_status;
 pub mod app;
+pub mod backend;
+pub mod cache;
+pub mod code;
+pub mod config;
+pub mod diff;
+pub mod editor;
+pub mod error;
 pub mod events;
+pub mod filter;
+#[cfg(test)]
+pub mod fixture;
 pub mod github;
+pub mod graphql;
+pub mod highlight;
+pub mod markdown;
+#[cfg(test)]
+pub mod mock_server;
 pub mod models;
+pub mod picker;
+pub mod pull_action;
+pub mod triage;
 pub mod ui;
 pub mod utils;
 
 use app::App;
+use cache::HttpCache;
+use config::Config;
+use github::GitHubClient;
 
 use dotenvy::dotenv;
-use std::env;
 
 use crate::events::handle_key_event;
 
-async fn run_app(username: &str, token: &str) -> io::Result<()> {
+async fn run_app(mut app: App) -> io::Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -33,23 +55,40 @@ async fn run_app(username: &str, token: &str) -> io::Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Create app
-    let mut app = App::new(username.to_string(), token.to_string())
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-
-    // Load initial data
-    app.load_user_repos().await;
+    // Load initial data in the background
+    app.load_user_repos();
 
     // Main loop
     loop {
+        // apply whatever the background fetches have finished since the last frame
+        app.handle_messages();
+
         terminal.draw(|f| ui::render_ui(f, &mut app))?;
 
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                if handle_key_event(&mut app, key).await == true {
-                    break;
-                }
-            }
+        if event::poll(Duration::from_millis(100))?
+            && let Event::Key(key) = event::read()?
+            && handle_key_event(&mut app, key)
+        {
+            break;
+        }
+
+        // OSC 52 asks the terminal itself to copy, which also works over ssh
+        if let Some(text) = app.take_clipboard() {
+            execute!(
+                terminal.backend_mut(),
+                CopyToClipboard::to_clipboard_from(text)
+            )?;
+        }
+
+        // hand the terminal over to $EDITOR and take it back afterwards
+        if let Some(text) = app.take_external_edit() {
+            disable_raw_mode()?;
+            execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+            let edited = editor::edit_externally(&text);
+            enable_raw_mode()?;
+            execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+            terminal.clear()?;
+            app.finish_external_edit(edited);
         }
     }
 
@@ -65,9 +104,16 @@ async fn run_app(username: &str, token: &str) -> io::Result<()> {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv().ok(); // loads variables from .env
 
-    let username = env::var("GITHUB_USERNAME").expect("missing GITHUB_USERNAME");
-    let token = env::var("GITHUB_TOKEN").expect("missing GITHUB_TOKEN");
+    let config = Config::load()?;
+
+    // Create app, with the one client every request goes through
+    let cache = match config.cache_dir.clone() {
+        Some(dir) => HttpCache::with_dir(dir),
+        None => HttpCache::default(),
+    };
+    let client = Arc::new(GitHubClient::new(&config, cache)?);
+    let app = App::new(config.username, client);
 
-    run_app(username.as_str(), token.as_str()).await?;
+    run_app(app).await?;
     Ok(())
 }
@@ -0,0 +1,577 @@
+use crate::highlight::Highlighter;
+use pulldown_cmark::{
+    Alignment, BlockQuoteKind, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd,
+};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+// Markdown rendered for the terminal: styled lines instead of HTML. Meant to
+// be shown in a `Paragraph` that wraps without trimming, so code keeps its
+// indentation. GitHub's extensions are understood too: tables, task lists,
+// strikethrough, alerts, and `#123` references and `@mentions` stand out.
+pub fn render(markdown: &str) -> Text<'static> {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_GFM;
+    let mut renderer = Renderer::default();
+    for event in Parser::new_ext(markdown, options) {
+        renderer.event(event);
+    }
+    renderer.finish()
+}
+
+// how many rendered bodies are kept before starting over
+const CACHE_SIZE: usize = 1024;
+
+thread_local! {
+    static CACHE: RefCell<HashMap<String, Text<'static>>> = RefCell::new(HashMap::new());
+}
+
+// Same as `render`, remembered: bodies are drawn on every frame, and parsing
+// and highlighting them each time would make scrolling sluggish
+pub fn render_cached(markdown: &str) -> Text<'static> {
+    CACHE.with_borrow_mut(|cache| {
+        if let Some(text) = cache.get(markdown) {
+            return text.clone();
+        }
+        if cache.len() >= CACHE_SIZE {
+            cache.clear();
+        }
+        let text = render(markdown);
+        cache.insert(markdown.to_string(), text.clone());
+        text
+    })
+}
+
+// a body squeezed onto one line, for table cells
+pub fn summary(markdown: &str) -> Line<'static> {
+    let mut spans = Vec::new();
+    for line in render_cached(markdown).lines {
+        if line.width() == 0 {
+            continue;
+        }
+        if !spans.is_empty() {
+            spans.push(Span::raw(" "));
+        }
+        // list indentation means nothing on one line
+        spans.extend(
+            line.spans
+                .into_iter()
+                .skip_while(|span| span.content.trim().is_empty()),
+        );
+    }
+    Line::from(spans)
+}
+
+fn code_style() -> Style {
+    Style::default().fg(Color::Yellow)
+}
+
+fn faint() -> Style {
+    Style::default().fg(Color::DarkGray)
+}
+
+fn reference_style() -> Style {
+    Style::default()
+        .fg(Color::Blue)
+        .add_modifier(Modifier::UNDERLINED)
+}
+
+fn mention_style() -> Style {
+    Style::default()
+        .fg(Color::Magenta)
+        .add_modifier(Modifier::BOLD)
+}
+
+// Text cut where `#123` references and `@mentions` start and end, each piece
+// with the style it gets, if any. Like on GitHub they only count after a
+// space or punctuation, so `a@b.com` and `C#` stay plain.
+fn references(text: &str) -> Vec<(&str, Option<Style>)> {
+    let bytes = text.as_bytes();
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    let mut pieces = Vec::new();
+    let mut plain = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let sigil = bytes[i];
+        if (sigil == b'#' || sigil == b'@') && (i == 0 || !is_word(bytes[i - 1])) {
+            let (name, style): (fn(&u8) -> bool, Style) = if sigil == b'#' {
+                (u8::is_ascii_digit, reference_style())
+            } else {
+                (|b| b.is_ascii_alphanumeric() || *b == b'-', mention_style())
+            };
+            let end = i + 1 + bytes[i + 1..].iter().take_while(|b| name(b)).count();
+            if end > i + 1 && bytes.get(end).is_none_or(|b| !is_word(*b)) {
+                if plain < i {
+                    pieces.push((&text[plain..i], None));
+                }
+                pieces.push((&text[i..end], Some(style)));
+                plain = end;
+                i = end;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    if plain < text.len() {
+        pieces.push((&text[plain..], None));
+    }
+    pieces
+}
+
+// label and color of a `> [!NOTE]` style alert
+fn alert(kind: BlockQuoteKind) -> (&'static str, Color) {
+    match kind {
+        BlockQuoteKind::Note => ("Note", Color::Blue),
+        BlockQuoteKind::Tip => ("Tip", Color::Green),
+        BlockQuoteKind::Important => ("Important", Color::Magenta),
+        BlockQuoteKind::Warning => ("Warning", Color::Yellow),
+        BlockQuoteKind::Caution => ("Caution", Color::Red),
+    }
+}
+
+// a table as it is read, cell by cell
+#[derive(Default)]
+struct TableState {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<Vec<Span<'static>>>>,
+}
+
+#[derive(Default)]
+struct Renderer {
+    lines: Vec<Line<'static>>,
+    // the line being built
+    spans: Vec<Span<'static>>,
+    // inline styles, innermost last
+    styles: Vec<Style>,
+    // next number of each open list, None for bullets, and how far its items
+    // are indented
+    lists: Vec<(Option<u64>, usize)>,
+    // marker of a list item, put in front of its first line
+    marker: Option<String>,
+    quotes: usize,
+    // target and text so far of each open link
+    links: Vec<(String, String)>,
+    // text of the code block being read
+    code: Option<String>,
+    // language of a fenced code block, for highlighting
+    language: Option<String>,
+    table: Option<TableState>,
+}
+
+impl Renderer {
+    fn style(&self) -> Style {
+        self.styles.last().copied().unwrap_or_default()
+    }
+
+    fn push_style(&mut self, style: Style) {
+        self.styles.push(self.style().patch(style));
+    }
+
+    // quote bars and list indentation in front of every line of a block
+    fn start_line(&mut self) {
+        if !self.spans.is_empty() {
+            return;
+        }
+        if self.quotes > 0 {
+            self.spans
+                .push(Span::styled("│ ".repeat(self.quotes), faint()));
+        }
+        let indent: usize = self.lists.iter().map(|(_, width)| width).sum();
+        match self.marker.take() {
+            Some(marker) => {
+                let outer = indent.saturating_sub(marker.chars().count());
+                self.spans.push(Span::raw(" ".repeat(outer)));
+                self.spans
+                    .push(Span::styled(marker, Style::default().fg(Color::Cyan)));
+            }
+            None if indent > 0 => self.spans.push(Span::raw(" ".repeat(indent))),
+            None => {}
+        }
+    }
+
+    // inline text goes into the open table cell, or else the current line
+    fn push_span(&mut self, span: Span<'static>) {
+        if let Some((_, text)) = self.links.last_mut() {
+            text.push_str(&span.content);
+        }
+        if let Some(cell) = self
+            .table
+            .as_mut()
+            .and_then(|table| table.rows.last_mut())
+            .and_then(|row| row.last_mut())
+        {
+            cell.push(span);
+            return;
+        }
+        self.start_line();
+        self.spans.push(span);
+    }
+
+    fn text(&mut self, text: &str) {
+        self.push_span(Span::styled(text.to_string(), self.style()));
+    }
+
+    fn flush(&mut self) {
+        if !self.spans.is_empty() {
+            self.lines.push(Line::from(std::mem::take(&mut self.spans)));
+        }
+    }
+
+    // an empty line between blocks, never two in a row
+    fn blank(&mut self) {
+        self.flush();
+        if self.lines.last().is_some_and(|line| line.width() > 0) {
+            self.lines.push(Line::default());
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => match &mut self.code {
+                Some(code) => code.push_str(&text),
+                // a link's text is already shown as a link
+                None if !self.links.is_empty() => self.text(&text),
+                None => {
+                    let style = self.style();
+                    for (piece, extra) in references(&text) {
+                        let style = extra.map_or(style, |extra| style.patch(extra));
+                        self.push_span(Span::styled(piece.to_string(), style));
+                    }
+                }
+            },
+            Event::Code(code) => {
+                let style = self.style().patch(code_style());
+                self.push_span(Span::styled(code.to_string(), style));
+            }
+            Event::TaskListMarker(checked) => {
+                // the box takes the place of the bullet
+                if self.marker.as_deref() == Some("• ") {
+                    self.marker = None;
+                }
+                let (checkbox, style) = if checked {
+                    ("[x] ", Style::default().fg(Color::Green))
+                } else {
+                    ("[ ] ", faint())
+                };
+                self.push_span(Span::styled(checkbox, style));
+            }
+            Event::SoftBreak => self.text(" "),
+            Event::HardBreak => self.flush(),
+            Event::Rule => {
+                self.blank();
+                self.lines.push(Line::styled("─".repeat(40), faint()));
+                self.lines.push(Line::default());
+            }
+            Event::Html(html) => self.html(&html),
+            // `<br>` is the one inline tag that changes the layout
+            Event::InlineHtml(html)
+                if html
+                    .trim_start_matches('<')
+                    .to_lowercase()
+                    .starts_with("br") =>
+            {
+                self.flush()
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.flush(),
+            Tag::Heading { level, .. } => {
+                self.blank();
+                let style = match level {
+                    HeadingLevel::H1 => Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                    HeadingLevel::H2 => Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                    _ => Style::default().add_modifier(Modifier::BOLD),
+                };
+                self.start_line();
+                self.spans.push(Span::styled(
+                    format!("{} ", "#".repeat(level as usize)),
+                    faint(),
+                ));
+                self.push_style(style);
+            }
+            Tag::BlockQuote(kind) => {
+                self.flush();
+                self.quotes += 1;
+                if let Some((label, color)) = kind.map(alert) {
+                    self.start_line();
+                    self.spans.push(Span::styled(
+                        label,
+                        Style::default().fg(color).add_modifier(Modifier::BOLD),
+                    ));
+                    self.flush();
+                }
+                self.push_style(Style::default().add_modifier(Modifier::ITALIC));
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush();
+                self.code = Some(String::new());
+                // the info string may carry more than the language: `rust,ignore`
+                self.language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .next()
+                        .filter(|language| !language.is_empty())
+                        .map(str::to_string),
+                    CodeBlockKind::Indented => None,
+                };
+            }
+            Tag::List(start) => {
+                self.flush();
+                let width = if start.is_some() { 4 } else { 2 };
+                self.lists.push((start, width));
+            }
+            Tag::Item => {
+                self.flush();
+                if let Some((next, width)) = self.lists.last_mut() {
+                    self.marker = Some(match next {
+                        Some(number) => {
+                            *number += 1;
+                            format!("{:<width$}", format!("{}.", *number - 1))
+                        }
+                        None => String::from("• "),
+                    });
+                }
+            }
+            Tag::Table(alignments) => {
+                self.blank();
+                self.table = Some(TableState {
+                    alignments,
+                    rows: Vec::new(),
+                });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => {
+                if let Some(row) = self.table.as_mut().and_then(|table| table.rows.last_mut()) {
+                    row.push(Vec::new());
+                }
+            }
+            Tag::Emphasis => self.push_style(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.push_style(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => {
+                self.push_style(Style::default().add_modifier(Modifier::CROSSED_OUT))
+            }
+            Tag::Link { dest_url, .. } => {
+                self.links.push((dest_url.to_string(), String::new()));
+                self.push_style(
+                    Style::default()
+                        .fg(Color::Blue)
+                        .add_modifier(Modifier::UNDERLINED),
+                );
+            }
+            Tag::Image { .. } => {
+                self.push_style(faint());
+                self.text("[image: ");
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => {
+                if self.lists.is_empty() {
+                    self.blank();
+                } else {
+                    self.flush();
+                }
+            }
+            TagEnd::Heading(_) => {
+                self.styles.pop();
+                self.blank();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.styles.pop();
+                self.flush();
+                self.quotes -= 1;
+                if self.quotes == 0 {
+                    self.blank();
+                }
+            }
+            TagEnd::CodeBlock => {
+                let code = self.code.take().unwrap_or_default();
+                let mut highlighter = self
+                    .language
+                    .take()
+                    .and_then(|l| Highlighter::for_language(&l));
+                for line in code.lines() {
+                    let line = line.replace('\t', "    ");
+                    self.start_line();
+                    self.spans.push(Span::styled("▏ ", faint()));
+                    match &mut highlighter {
+                        Some(highlighter) => self.spans.extend(highlighter.highlight(&line)),
+                        None => self.spans.push(Span::styled(line, code_style())),
+                    }
+                    self.flush();
+                }
+                if self.lists.is_empty() {
+                    self.blank();
+                }
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank();
+                }
+            }
+            TagEnd::Item => self.flush(),
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.table(table);
+                }
+                self.blank();
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => {
+                self.styles.pop();
+            }
+            TagEnd::Link => {
+                self.styles.pop();
+                if let Some((url, text)) = self.links.pop()
+                    && url != text
+                    && !url.starts_with('#')
+                {
+                    self.push_span(Span::styled(format!(" ({})", url), faint()));
+                }
+            }
+            TagEnd::Image => {
+                self.text("]");
+                self.styles.pop();
+            }
+            _ => {}
+        }
+    }
+
+    // Columns padded to their widest cell, the header row underlined
+    fn table(&mut self, table: TableState) {
+        let width = |cell: &Vec<Span>| cell.iter().map(|span| span.width()).sum::<usize>();
+        let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                table
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.get(column))
+                    .map(width)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        for (i, row) in table.rows.into_iter().enumerate() {
+            self.start_line();
+            let mut cells = row.into_iter();
+            for (column, &column_width) in widths.iter().enumerate() {
+                if column > 0 {
+                    self.spans.push(Span::styled(" │ ", faint()));
+                }
+                let cell = cells.next().unwrap_or_default();
+                let padding = column_width - width(&cell);
+                let (before, after) = match table.alignments.get(column) {
+                    Some(Alignment::Right) => (padding, 0),
+                    Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+                    _ => (0, padding),
+                };
+                self.spans.push(Span::raw(" ".repeat(before)));
+                for span in cell {
+                    // the header row is bold
+                    if i == 0 {
+                        self.spans
+                            .push(span.patch_style(Style::default().add_modifier(Modifier::BOLD)));
+                    } else {
+                        self.spans.push(span);
+                    }
+                }
+                self.spans.push(Span::raw(" ".repeat(after)));
+            }
+            self.flush();
+            if i == 0 {
+                let rule: Vec<String> = widths.iter().map(|w| "─".repeat(*w)).collect();
+                self.start_line();
+                self.spans.push(Span::styled(rule.join("─┼─"), faint()));
+                self.flush();
+            }
+        }
+    }
+
+    // HTML blocks, common at the top of READMEs, show just their text
+    fn html(&mut self, html: &str) {
+        let mut text = String::new();
+        let mut in_tag = false;
+        for c in html.chars() {
+            match c {
+                '<' => in_tag = true,
+                '>' => in_tag = false,
+                c if !in_tag => text.push(c),
+                _ => {}
+            }
+        }
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            self.start_line();
+            self.spans.push(Span::raw(line.to_string()));
+            self.flush();
+        }
+    }
+
+    fn finish(mut self) -> Text<'static> {
+        self.flush();
+        while self.lines.last().is_some_and(|line| line.width() == 0) {
+            self.lines.pop();
+        }
+        Text::from(self.lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn styled(text: &str) -> Vec<&str> {
+        references(text)
+            .into_iter()
+            .filter(|(_, style)| style.is_some())
+            .map(|(piece, _)| piece)
+            .collect()
+    }
+
+    #[test]
+    fn references_split_issue_numbers_and_mentions() {
+        let pieces = references("Fixed in #3, thanks @mona-lisa!");
+        let texts: Vec<&str> = pieces.iter().map(|(piece, _)| *piece).collect();
+        assert_eq!(
+            texts,
+            vec!["Fixed in ", "#3", ", thanks ", "@mona-lisa", "!"]
+        );
+        assert_eq!(pieces[1].1, Some(reference_style()));
+        assert_eq!(pieces[3].1, Some(mention_style()));
+    }
+
+    #[test]
+    fn references_need_a_boundary_on_both_sides() {
+        assert!(styled("mail a@b.com about C# or #12a").is_empty());
+        assert_eq!(styled("#1 (#2) @a"), vec!["#1", "#2", "@a"]);
+    }
+
+    #[test]
+    fn references_leave_bare_sigils_plain() {
+        assert_eq!(references("# @ #"), vec![("# @ #", None)]);
+        assert!(references("").is_empty());
+    }
+}
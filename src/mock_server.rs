@@ -0,0 +1,153 @@
+use crate::cache::HttpCache;
+use crate::config::{Config, NetworkSettings};
+use crate::github::GitHubClient;
+use reqwest::StatusCode;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+// Local HTTP server answering each connection with the next canned response, to
+// drive `GitHubClient` through retries, rate limits and revalidation without
+// network access. Every response closes its connection, so one request is one
+// connection.
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+// What the client sent; header names are lowercase
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.body).unwrap()
+    }
+}
+
+// One canned response: status, extra headers and body
+pub fn response(status: u16, headers: &[(&str, &str)], body: &str) -> String {
+    let reason = StatusCode::from_u16(status)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or_default();
+    let mut text = format!(
+        "HTTP/1.1 {} {}\r\ncontent-length: {}\r\nconnection: close\r\n",
+        status,
+        reason,
+        body.len()
+    );
+    for (name, value) in headers {
+        text.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    text.push_str("\r\n");
+    text.push_str(body);
+    text
+}
+
+impl MockServer {
+    pub async fn start(responses: Vec<String>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                if let Some(request) = read_request(&mut socket).await {
+                    seen.lock().unwrap().push(request);
+                }
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        Self { url, requests }
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    // a client of this server that retries up to `max_retries` times, right away
+    pub fn client(&self, max_retries: u32) -> GitHubClient {
+        self.client_with(HttpCache::default(), max_retries)
+    }
+
+    pub fn client_with(&self, cache: HttpCache, max_retries: u32) -> GitHubClient {
+        let config = Config {
+            username: String::from("octocat"),
+            token: String::from("test-token"),
+            api_url: self.url.clone(),
+            graphql_url: format!("{}/graphql", self.url),
+            cache_dir: None,
+            offline: false,
+            network: NetworkSettings {
+                max_retries,
+                retry_base_delay: Duration::from_millis(1),
+                retry_max_delay: Duration::from_millis(2),
+                ..NetworkSettings::default()
+            },
+        };
+        GitHubClient::new(&config, cache).unwrap()
+    }
+}
+
+async fn read_request(socket: &mut TcpStream) -> Option<Request> {
+    let mut data = Vec::new();
+    let mut buffer = [0; 4096];
+    let head_end = loop {
+        let read = socket.read(&mut buffer).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        data.extend_from_slice(&buffer[..read]);
+        if let Some(end) = data.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+    let length: usize = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+
+    while data.len() < head_end + length {
+        let read = socket.read(&mut buffer).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        data.extend_from_slice(&buffer[..read]);
+    }
+    let end = data.len().min(head_end + length);
+    Some(Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&data[head_end..end]).into_owned(),
+    })
+}
@@ -1,5 +1,6 @@
 // github.rs
-use serde::Deserialize;
+use crate::error::ApiError;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Repo {
@@ -12,6 +13,8 @@ pub struct Repo {
     pub updated_at: String,
     pub created_at: String,
     pub html_url: String,
+    #[serde(default)]
+    pub default_branch: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -22,15 +25,374 @@ pub struct Issue {
     pub number: u32,
     pub html_url: String,
     pub labels: Vec<Label>,
+    // author, missing for deleted accounts
+    #[serde(default)]
+    pub user: Option<User>,
+    #[serde(default)]
+    pub comments: u32,
+    // set when the "issue" is really a pull request
+    #[serde(default)]
+    pub pull_request: Option<PullRequestRef>,
+    #[serde(default)]
+    pub assignees: Vec<User>,
+    #[serde(default)]
+    pub milestone: Option<Milestone>,
+    #[serde(default)]
+    pub locked: bool,
+    // why it was closed: completed, not_planned; reopened after a reopen
+    #[serde(default)]
+    pub state_reason: Option<String>,
 }
 
+impl Issue {
+    pub fn is_pull_request(&self) -> bool {
+        self.pull_request.is_some()
+    }
+}
+
+// Marker the issues endpoint puts on pull requests
 #[derive(Debug, Deserialize, Clone)]
+pub struct PullRequestRef {
+    pub html_url: Option<String>,
+}
+
+// Listings fill in the basics; mergeability, line and file counts only come
+// with the single PR endpoint
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct PR {
     pub number: u32,
     pub state: String,
     pub body: Option<String>,
     pub title: String,
     pub html_url: String,
+    #[serde(default)]
+    pub user: Option<User>,
+    #[serde(default)]
+    pub head: Option<BranchRef>,
+    #[serde(default)]
+    pub base: Option<BranchRef>,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub merged_at: Option<String>,
+    // None while GitHub is still computing it
+    #[serde(default)]
+    pub mergeable: Option<bool>,
+    // clean, dirty, blocked, behind, unstable, ...
+    #[serde(default)]
+    pub mergeable_state: Option<String>,
+    #[serde(default)]
+    pub additions: Option<u32>,
+    #[serde(default)]
+    pub deletions: Option<u32>,
+    #[serde(default)]
+    pub changed_files: Option<u32>,
+    #[serde(default)]
+    pub commits: Option<u32>,
+    #[serde(default)]
+    pub requested_reviewers: Vec<User>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct User {
+    pub login: String,
+}
+
+// One side of a PR: branch name and the commit it points at
+#[derive(Debug, Deserialize, Clone)]
+pub struct BranchRef {
+    #[serde(rename = "ref")]
+    pub name: String,
+    pub sha: String,
+    // repo the branch lives in, a fork for outside contributions; gone when
+    // the fork was deleted
+    #[serde(default)]
+    pub repo: Option<RepoRef>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RepoRef {
+    pub full_name: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Review {
+    pub id: u64,
+    #[serde(default)]
+    pub user: Option<User>,
+    // APPROVED, CHANGES_REQUESTED, COMMENTED, DISMISSED or PENDING
+    pub state: String,
+    #[serde(default)]
+    pub submitted_at: Option<String>,
+}
+
+// Body of `POST /repos/{repo}/pulls/{n}/reviews`
+#[derive(Debug, Serialize, Clone)]
+pub struct NewReview {
+    // APPROVE, REQUEST_CHANGES or COMMENT
+    pub event: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub body: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<NewReviewComment>,
+}
+
+// A comment on a line, or a range of lines, of a PR's diff. `side` is LEFT for
+// the old version of the file and RIGHT for the new one.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct NewReviewComment {
+    pub path: String,
+    pub body: String,
+    pub line: u32,
+    pub side: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_side: Option<String>,
+}
+
+// Conversation on the lines of a PR's diff
+#[derive(Debug, Clone)]
+pub struct ReviewThread {
+    // GraphQL node id, needed to resolve it; None when read over REST
+    pub id: Option<String>,
+    pub path: String,
+    // None once the lines it was left on are gone from the diff
+    pub line: Option<u32>,
+    pub side: String,
+    pub resolved: bool,
+    pub comments: Vec<ReviewComment>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReviewComment {
+    pub user: Option<User>,
+    pub body: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeMethod {
+    #[default]
+    Merge,
+    Squash,
+    Rebase,
+}
+
+impl MergeMethod {
+    pub const ALL: [MergeMethod; 3] =
+        [MergeMethod::Merge, MergeMethod::Squash, MergeMethod::Rebase];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MergeMethod::Merge => "merge",
+            MergeMethod::Squash => "squash",
+            MergeMethod::Rebase => "rebase",
+        }
+    }
+
+    // rebasing replays the commits as they are, without a commit of its own
+    pub fn has_message(&self) -> bool {
+        *self != MergeMethod::Rebase
+    }
+}
+
+// Body of `PUT /repos/{repo}/pulls/{n}/merge`; GitHub writes the commit
+// title and message itself when they are left out
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct MergeRequest {
+    pub merge_method: MergeMethod,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_message: Option<String>,
+    // refuse to merge if the head moved since it was reviewed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MergeResult {
+    #[serde(default)]
+    pub sha: Option<String>,
+    pub merged: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheckState {
+    Success,
+    Failure,
+    Pending,
+    // neutral, skipped or cancelled: neither passes nor blocks
+    Neutral,
+}
+
+// One commit status or check run on the head commit
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: String,
+    pub state: CheckState,
+}
+
+// Everything the PR popup shows
+#[derive(Debug, Clone, Default)]
+pub struct PullDetail {
+    pub pr: PR,
+    pub reviews: Vec<Review>,
+    pub checks: Vec<Check>,
+}
+
+impl PullDetail {
+    // each reviewer's latest verdict; comments only count when that's all there is
+    pub fn review_decisions(&self) -> Vec<(String, String)> {
+        let mut decisions: Vec<(String, String)> = Vec::new();
+        for review in &self.reviews {
+            let Some(user) = &review.user else {
+                continue;
+            };
+            if review.state == "PENDING" {
+                continue;
+            }
+            match decisions.iter_mut().find(|(login, _)| login == &user.login) {
+                Some((_, state)) if review.state != "COMMENTED" => state.clone_from(&review.state),
+                Some(_) => {}
+                None => decisions.push((user.login.clone(), review.state.clone())),
+            }
+        }
+        decisions
+    }
+
+    // combined CI state of the head commit, None when nothing ran
+    pub fn ci_state(&self) -> Option<CheckState> {
+        let has = |state| self.checks.iter().any(|c| c.state == state);
+        if has(CheckState::Failure) {
+            Some(CheckState::Failure)
+        } else if has(CheckState::Pending) {
+            Some(CheckState::Pending)
+        } else if self.checks.is_empty() {
+            None
+        } else {
+            Some(CheckState::Success)
+        }
+    }
+}
+
+// A file changed by a PR or a commit, as listed by `/pulls/{n}/files` and
+// `/commits/{sha}`
+#[derive(Debug, Deserialize, Clone)]
+pub struct PullFile {
+    pub filename: String,
+    // added, removed, modified, renamed, copied, changed or unchanged
+    pub status: String,
+    pub additions: u32,
+    pub deletions: u32,
+    // unified diff hunks; missing for binary files and very large diffs
+    #[serde(default)]
+    pub patch: Option<String>,
+    #[serde(default)]
+    pub previous_filename: Option<String>,
+}
+
+// A commit as the commit listings give it; stats and files only come with a
+// single commit
+#[derive(Debug, Deserialize, Clone)]
+pub struct Commit {
+    pub sha: String,
+    pub commit: CommitInfo,
+    // GitHub account of the author, when their email is linked to one
+    #[serde(default)]
+    pub author: Option<User>,
+    #[serde(default)]
+    pub stats: Option<CommitStats>,
+    #[serde(default)]
+    pub files: Vec<PullFile>,
+}
+
+// what git itself records about a commit
+#[derive(Debug, Deserialize, Clone)]
+pub struct CommitInfo {
+    pub message: String,
+    #[serde(default)]
+    pub author: Option<CommitAuthor>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CommitAuthor {
+    pub name: String,
+    pub date: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CommitStats {
+    pub additions: u32,
+    pub deletions: u32,
+}
+
+impl Commit {
+    pub fn short_sha(&self) -> &str {
+        self.sha.get(..7).unwrap_or(&self.sha)
+    }
+
+    // first line of the message
+    pub fn subject(&self) -> &str {
+        self.commit.message.lines().next().unwrap_or_default()
+    }
+
+    // the login when GitHub knows it, else the name git has
+    pub fn author_name(&self) -> &str {
+        match (&self.author, &self.commit.author) {
+            (Some(user), _) => &user.login,
+            (None, Some(author)) => &author.name,
+            (None, None) => "unknown",
+        }
+    }
+
+    pub fn date(&self) -> &str {
+        self.commit
+            .author
+            .as_ref()
+            .map_or("", |author| author.date.as_str())
+    }
+}
+
+// Branch or tag, as the branches and tags listings give them
+#[derive(Debug, Deserialize, Clone)]
+pub struct GitRef {
+    pub name: String,
+}
+
+// Everything in a commit, from the recursive git tree
+#[derive(Debug, Clone)]
+pub struct RepoTree {
+    // the commit the requested ref pointed at
+    pub commit: String,
+    pub entries: Vec<TreeEntry>,
+    // GitHub stops listing very large trees
+    pub truncated: bool,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TreeEntry {
+    pub path: String,
+    // blob, tree, or commit for submodules
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+// A file from the contents API
+#[derive(Debug, Deserialize, Clone)]
+pub struct FileContent {
+    pub path: String,
+    pub size: u64,
+    // base64, or none for files over 1 MB, which come without content
+    #[serde(default)]
+    pub encoding: Option<String>,
+    #[serde(default)]
+    pub content: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -40,3 +402,207 @@ pub struct Label {
     pub color: Option<String>,
     pub name: String,
 }
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct Milestone {
+    pub number: u32,
+    pub title: String,
+}
+
+// Body of `POST /repos/{repo}/issues`
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct NewIssue {
+    pub title: String,
+    pub body: String,
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub milestone: Option<u32>,
+}
+
+// Body of `PATCH /repos/{repo}/issues/{n}`: only the fields that are set change
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct IssueUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assignees: Option<Vec<String>>,
+    // `Some(None)` removes the milestone
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub milestone: Option<Option<u32>>,
+}
+
+// A Markdown issue template from `.github/ISSUE_TEMPLATE`
+#[derive(Debug, Clone, Default)]
+pub struct IssueTemplate {
+    pub name: String,
+    pub about: String,
+    pub title: String,
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+    pub body: String,
+}
+
+impl IssueTemplate {
+    // Read the front matter GitHub templates start with:
+    //
+    //   ---
+    //   name: Bug report
+    //   labels: bug, triage      (or `["bug", "triage"]`, or a `- bug` list)
+    //   ---
+    //
+    // Files without one are used whole as the body, named after the file.
+    pub fn parse(file_name: &str, text: &str) -> Self {
+        let mut template = Self {
+            name: file_name.trim_end_matches(".md").replace(['-', '_'], " "),
+            ..Self::default()
+        };
+        let Some(rest) = text.trim_start().strip_prefix("---") else {
+            template.body = text.to_string();
+            return template;
+        };
+        let Some((front, body)) = rest.split_once("\n---") else {
+            template.body = text.to_string();
+            return template;
+        };
+        template.body = body.trim_start_matches(['-', '\r', '\n']).to_string();
+
+        let mut key = "";
+        for line in front.lines() {
+            let unquote = |value: &str| value.trim().trim_matches(['"', '\'']).to_string();
+            // continuation of a `key:` followed by a `- item` list
+            if let Some(item) = line.trim().strip_prefix("- ") {
+                match key {
+                    "labels" => template.labels.push(unquote(item)),
+                    "assignees" => template.assignees.push(unquote(item)),
+                    _ => {}
+                }
+                continue;
+            }
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            key = name.trim();
+            let list = || -> Vec<String> {
+                value
+                    .trim()
+                    .trim_matches(['[', ']'])
+                    .split(',')
+                    .map(unquote)
+                    .filter(|item| !item.is_empty())
+                    .collect()
+            };
+            match key {
+                "name" => template.name = unquote(value),
+                "about" => template.about = unquote(value),
+                "title" => template.title = unquote(value),
+                "labels" => template.labels = list(),
+                "assignees" => template.assignees = list(),
+                _ => {}
+            }
+        }
+        template
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Comment {
+    pub id: u64,
+    #[serde(default)]
+    pub user: Option<User>,
+    pub body: Option<String>,
+    pub created_at: String,
+    #[serde(default)]
+    pub reactions: Option<Reactions>,
+}
+
+// Reaction rollup GitHub attaches to issues and comments
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Reactions {
+    pub total_count: u32,
+    #[serde(rename = "+1")]
+    pub plus_one: u32,
+    #[serde(rename = "-1")]
+    pub minus_one: u32,
+    pub laugh: u32,
+    pub hooray: u32,
+    pub confused: u32,
+    pub heart: u32,
+    pub rocket: u32,
+    pub eyes: u32,
+}
+
+impl Reactions {
+    // non-zero reactions as (emoji, count)
+    pub fn counts(&self) -> Vec<(&'static str, u32)> {
+        [
+            ("👍", self.plus_one),
+            ("👎", self.minus_one),
+            ("😄", self.laugh),
+            ("🎉", self.hooray),
+            ("😕", self.confused),
+            ("❤️", self.heart),
+            ("🚀", self.rocket),
+            ("👀", self.eyes),
+        ]
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .collect()
+    }
+}
+
+// Open issue and PR totals of a repo
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoCounts {
+    pub open_issues: u32,
+    pub open_prs: u32,
+}
+
+// Whatever parts of a repo's detail view were asked for in one go. Each
+// listing that was asked for has an outcome of its own, since they may come
+// from separate requests.
+#[derive(Debug, Clone, Default)]
+pub struct RepoDetail {
+    pub repo: Option<Repo>,
+    pub counts: Option<RepoCounts>,
+    pub issues: Option<Result<Page<Issue>, ApiError>>,
+    pub prs: Option<Result<Page<PR>, ApiError>>,
+}
+
+// One page of a paginated listing; `next` is an opaque cursor for the following page
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next: Option<String>,
+    // unix timestamp of the oldest cached data when served offline
+    pub stale_since: Option<u64>,
+}
+
+impl<T> Default for Page<T> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            next: None,
+            stale_since: None,
+        }
+    }
+}
+
+impl<T> Page<T> {
+    // keep track of the oldest cached part of a listing
+    pub fn mark_stale(&mut self, stale_since: Option<u64>) {
+        self.stale_since = oldest(self.stale_since, stale_since);
+    }
+}
+
+// earliest of two optional timestamps
+pub fn oldest(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
@@ -0,0 +1,85 @@
+// Choice list behind the label, assignee and milestone fields
+#[derive(Debug, Clone, Default)]
+pub struct Picker {
+    items: Vec<PickerItem>,
+    cursor: usize,
+    // several items can be chosen at once
+    multi: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct PickerItem {
+    pub name: String,
+    // hex color, for labels
+    pub color: Option<String>,
+    pub chosen: bool,
+}
+
+impl Picker {
+    pub fn new(items: Vec<(String, Option<String>)>, multi: bool) -> Self {
+        Self {
+            items: items
+                .into_iter()
+                .map(|(name, color)| PickerItem {
+                    name,
+                    color,
+                    chosen: false,
+                })
+                .collect(),
+            cursor: 0,
+            multi,
+        }
+    }
+
+    pub fn items(&self) -> &[PickerItem] {
+        &self.items
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_multi(&self) -> bool {
+        self.multi
+    }
+
+    pub fn down(&mut self) {
+        if self.cursor + 1 < self.items.len() {
+            self.cursor += 1;
+        }
+    }
+
+    pub fn up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    // choose the item under the cursor, or drop it if it already is
+    pub fn toggle(&mut self) {
+        let Some(chosen) = self.items.get(self.cursor).map(|item| !item.chosen) else {
+            return;
+        };
+        if !self.multi {
+            for item in &mut self.items {
+                item.chosen = false;
+            }
+        }
+        self.items[self.cursor].chosen = chosen;
+    }
+
+    pub fn chosen(&self) -> Vec<String> {
+        self.items
+            .iter()
+            .filter(|item| item.chosen)
+            .map(|item| item.name.clone())
+            .collect()
+    }
+
+    // choose exactly `names`, ignoring case; names that aren't listed are skipped
+    pub fn choose(&mut self, names: &[String]) {
+        for item in &mut self.items {
+            item.chosen = names
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&item.name));
+        }
+    }
+}
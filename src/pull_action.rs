@@ -0,0 +1,228 @@
+use crate::app::AppMode;
+use crate::editor::TextEditor;
+use crate::models::{MergeMethod, MergeRequest, NewReview, NewReviewComment, PR};
+
+// Something done to a PR from its popup, after confirming it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PullActionKind {
+    Approve,
+    RequestChanges,
+    Comment,
+    Merge,
+    AutoMerge,
+    ReadyForReview,
+    ConvertToDraft,
+    DeleteBranch,
+}
+
+impl PullActionKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            PullActionKind::Approve => "Approve",
+            PullActionKind::RequestChanges => "Request changes",
+            PullActionKind::Comment => "Review",
+            PullActionKind::Merge => "Merge",
+            PullActionKind::AutoMerge => "Enable auto-merge",
+            PullActionKind::ReadyForReview => "Mark ready for review",
+            PullActionKind::ConvertToDraft => "Convert to draft",
+            PullActionKind::DeleteBranch => "Delete branch",
+        }
+    }
+
+    pub fn is_review(&self) -> bool {
+        matches!(
+            self,
+            PullActionKind::Approve | PullActionKind::RequestChanges | PullActionKind::Comment
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PullActionField {
+    Method,
+    Title,
+    // review body or merge commit message
+    Message,
+    DeleteBranch,
+}
+
+// Confirmation modal for a PR action, with whatever it can be given
+#[derive(Debug, Clone)]
+pub struct PullAction {
+    pub repo: String,
+    pub number: u32,
+    pub kind: PullActionKind,
+    pub method: MergeMethod,
+    // merge commit title; GitHub's default when left empty
+    pub title: String,
+    pub editor: TextEditor,
+    pub delete_branch: bool,
+    pub field: Option<PullActionField>,
+    // repo and name of the head branch, None when its fork is gone
+    pub head: Option<(String, String)>,
+    pub head_sha: Option<String>,
+    // queued line comments sent along with a review
+    pub comments: Vec<NewReviewComment>,
+    pub sending: bool,
+    pub error: Option<String>,
+    pub return_mode: AppMode,
+}
+
+impl PullAction {
+    pub fn new(repo: String, pr: &PR, kind: PullActionKind, return_mode: AppMode) -> Self {
+        let head = pr.head.as_ref().and_then(|head| {
+            // the PR endpoint names the repo of both branches, and none for a
+            // deleted fork; listings don't, so assume the PR's own repo there
+            let repo = match &head.repo {
+                Some(head_repo) => head_repo.full_name.clone(),
+                None if pr.base.as_ref().is_some_and(|base| base.repo.is_some()) => return None,
+                None => repo.clone(),
+            };
+            Some((repo, head.name.clone()))
+        });
+        let mut action = Self {
+            repo,
+            number: pr.number,
+            kind,
+            method: MergeMethod::default(),
+            title: String::new(),
+            editor: TextEditor::default(),
+            delete_branch: false,
+            field: None,
+            head,
+            head_sha: pr.head.as_ref().map(|head| head.sha.clone()),
+            comments: Vec::new(),
+            sending: false,
+            error: None,
+            return_mode,
+        };
+        if kind == PullActionKind::Merge {
+            action.title = format!("{} (#{})", pr.title, pr.number);
+        }
+        action.field = action.fields().first().copied();
+        action
+    }
+
+    pub fn fields(&self) -> Vec<PullActionField> {
+        match self.kind {
+            PullActionKind::Merge if self.method.has_message() => vec![
+                PullActionField::Method,
+                PullActionField::Title,
+                PullActionField::Message,
+                PullActionField::DeleteBranch,
+            ],
+            PullActionKind::Merge => vec![PullActionField::Method, PullActionField::DeleteBranch],
+            PullActionKind::AutoMerge => vec![PullActionField::Method],
+            kind if kind.is_review() => vec![PullActionField::Message],
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn step_field(&mut self, forward: bool) {
+        let fields = self.fields();
+        let Some(i) = fields.iter().position(|f| Some(*f) == self.field) else {
+            return;
+        };
+        let next = if forward {
+            (i + 1) % fields.len()
+        } else {
+            (i + fields.len() - 1) % fields.len()
+        };
+        self.field = Some(fields[next]);
+    }
+
+    pub fn cycle_method(&mut self, forward: bool) {
+        let all = MergeMethod::ALL;
+        let i = all.iter().position(|m| *m == self.method).unwrap_or(0);
+        let next = if forward {
+            (i + 1) % all.len()
+        } else {
+            (i + all.len() - 1) % all.len()
+        };
+        self.method = all[next];
+    }
+
+    // the question the modal asks
+    pub fn prompt(&self) -> String {
+        match self.kind {
+            PullActionKind::Approve => format!("Approve #{}?", self.number),
+            PullActionKind::RequestChanges => format!("Request changes on #{}?", self.number),
+            PullActionKind::Comment if !self.comments.is_empty() => format!(
+                "Submit a review of #{} with {} line comment(s)?",
+                self.number,
+                self.comments.len()
+            ),
+            PullActionKind::Comment => format!("Comment on the changes of #{}?", self.number),
+            PullActionKind::Merge => format!("Merge #{} into its base branch?", self.number),
+            PullActionKind::AutoMerge => format!(
+                "Merge #{} automatically once its requirements are met?",
+                self.number
+            ),
+            PullActionKind::ReadyForReview => format!("Mark #{} ready for review?", self.number),
+            PullActionKind::ConvertToDraft => format!("Convert #{} back to a draft?", self.number),
+            PullActionKind::DeleteBranch => match &self.head {
+                Some((_, branch)) => format!("Delete branch {}?", branch),
+                None => String::from("The head branch is gone"),
+            },
+        }
+    }
+
+    // why the action can't be sent as it is
+    pub fn problem(&self) -> Option<&'static str> {
+        match self.kind {
+            PullActionKind::RequestChanges if self.editor.is_blank() => {
+                Some("This review needs a comment")
+            }
+            PullActionKind::Comment if self.editor.is_blank() && self.comments.is_empty() => {
+                Some("This review needs a comment")
+            }
+            PullActionKind::DeleteBranch if self.head.is_none() => Some("No branch to delete"),
+            _ => None,
+        }
+    }
+
+    pub fn review(&self) -> NewReview {
+        let event = match self.kind {
+            PullActionKind::Approve => "APPROVE",
+            PullActionKind::RequestChanges => "REQUEST_CHANGES",
+            _ => "COMMENT",
+        };
+        NewReview {
+            event: event.to_string(),
+            body: self.editor.text().trim().to_string(),
+            comments: self.comments.clone(),
+        }
+    }
+
+    pub fn merge_request(&self) -> MergeRequest {
+        let text = |s: String| Some(s).filter(|s| !s.trim().is_empty());
+        let has_message = self.method.has_message();
+        MergeRequest {
+            merge_method: self.method,
+            commit_title: text(self.title.clone()).filter(|_| has_message),
+            commit_message: text(self.editor.text()).filter(|_| has_message),
+            sha: self.head_sha.clone(),
+        }
+    }
+
+    // e.g. `Merged #12 (squash)`
+    pub fn describe(&self) -> String {
+        match self.kind {
+            PullActionKind::Approve => format!("Approved #{}", self.number),
+            PullActionKind::RequestChanges => format!("Requested changes on #{}", self.number),
+            PullActionKind::Comment => format!("Reviewed #{}", self.number),
+            PullActionKind::Merge => format!("Merged #{} ({})", self.number, self.method.name()),
+            PullActionKind::AutoMerge => format!(
+                "#{} will be merged ({}) once it can be",
+                self.number,
+                self.method.name()
+            ),
+            PullActionKind::ReadyForReview => format!("#{} is ready for review", self.number),
+            PullActionKind::ConvertToDraft => format!("#{} is a draft again", self.number),
+            PullActionKind::DeleteBranch => match &self.head {
+                Some((_, branch)) => format!("Deleted branch {}", branch),
+                None => String::from("Deleted the head branch"),
+            },
+        }
+    }
+}
@@ -0,0 +1,424 @@
+use crate::app::{AppMode, IssueFormOptions, LoadingState};
+use crate::models::{Issue, IssueUpdate, Label, Milestone, User};
+use crate::picker::Picker;
+
+// What a triage picker sets on an issue
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriageAction {
+    Close,
+    Labels,
+    Assignees,
+    Milestone,
+    Lock,
+}
+
+impl TriageAction {
+    pub fn title(&self) -> &'static str {
+        match self {
+            TriageAction::Close => "Close as",
+            TriageAction::Labels => "Labels",
+            TriageAction::Assignees => "Assignees",
+            TriageAction::Milestone => "Milestone",
+            TriageAction::Lock => "Lock reason",
+        }
+    }
+
+    // whether the choices have to be fetched from the repo
+    pub fn needs_options(&self) -> bool {
+        matches!(
+            self,
+            TriageAction::Labels | TriageAction::Assignees | TriageAction::Milestone
+        )
+    }
+}
+
+// One change to an issue. The app applies it right away and puts back what it
+// touched if GitHub refuses it.
+#[derive(Debug, Clone)]
+pub enum IssueChange {
+    // completed or not_planned
+    Close(String),
+    Reopen,
+    Labels(Vec<Label>),
+    Assignees(Vec<String>),
+    Milestone(Option<Milestone>),
+    Lock(Option<String>),
+    Unlock,
+}
+
+impl IssueChange {
+    pub fn apply(&self, issue: &mut Issue) {
+        match self {
+            IssueChange::Close(reason) => {
+                issue.state = String::from("closed");
+                issue.state_reason = Some(reason.clone());
+            }
+            IssueChange::Reopen => {
+                issue.state = String::from("open");
+                issue.state_reason = Some(String::from("reopened"));
+            }
+            IssueChange::Labels(labels) => issue.labels.clone_from(labels),
+            IssueChange::Assignees(logins) => {
+                issue.assignees = logins
+                    .iter()
+                    .map(|login| User {
+                        login: login.clone(),
+                    })
+                    .collect();
+            }
+            IssueChange::Milestone(milestone) => issue.milestone.clone_from(milestone),
+            IssueChange::Lock(_) => issue.locked = true,
+            IssueChange::Unlock => issue.locked = false,
+        }
+    }
+
+    // Undo this change on `issue` with the fields it touched as `previous` had
+    // them; changes made to other fields since are kept
+    pub fn revert(&self, issue: &mut Issue, previous: &Issue) {
+        match self {
+            IssueChange::Close(_) | IssueChange::Reopen => {
+                issue.state.clone_from(&previous.state);
+                issue.state_reason.clone_from(&previous.state_reason);
+            }
+            IssueChange::Labels(_) => issue.labels.clone_from(&previous.labels),
+            IssueChange::Assignees(_) => issue.assignees.clone_from(&previous.assignees),
+            IssueChange::Milestone(_) => issue.milestone.clone_from(&previous.milestone),
+            IssueChange::Lock(_) | IssueChange::Unlock => issue.locked = previous.locked,
+        }
+    }
+
+    // the PATCH body, None for locking which has endpoints of its own
+    pub fn update(&self) -> Option<IssueUpdate> {
+        let update = match self {
+            IssueChange::Close(reason) => IssueUpdate {
+                state: Some(String::from("closed")),
+                state_reason: Some(reason.clone()),
+                ..IssueUpdate::default()
+            },
+            IssueChange::Reopen => IssueUpdate {
+                state: Some(String::from("open")),
+                ..IssueUpdate::default()
+            },
+            IssueChange::Labels(labels) => IssueUpdate {
+                labels: Some(labels.iter().map(|label| label.name.clone()).collect()),
+                ..IssueUpdate::default()
+            },
+            IssueChange::Assignees(logins) => IssueUpdate {
+                assignees: Some(logins.clone()),
+                ..IssueUpdate::default()
+            },
+            IssueChange::Milestone(milestone) => IssueUpdate {
+                milestone: Some(milestone.as_ref().map(|m| m.number)),
+                ..IssueUpdate::default()
+            },
+            IssueChange::Lock(_) | IssueChange::Unlock => return None,
+        };
+        Some(update)
+    }
+
+    // e.g. `Closed #12 as not planned`
+    pub fn describe(&self, number: u32) -> String {
+        match self {
+            IssueChange::Close(reason) => {
+                format!("Closed #{} as {}", number, reason.replace('_', " "))
+            }
+            IssueChange::Reopen => format!("Reopened #{}", number),
+            IssueChange::Labels(labels) if labels.is_empty() => {
+                format!("Removed all labels from #{}", number)
+            }
+            IssueChange::Labels(labels) => {
+                let names: Vec<&str> = labels.iter().map(|l| l.name.as_str()).collect();
+                format!("Labelled #{} {}", number, names.join(", "))
+            }
+            IssueChange::Assignees(logins) if logins.is_empty() => {
+                format!("Unassigned #{}", number)
+            }
+            IssueChange::Assignees(logins) => {
+                format!("Assigned #{} to {}", number, logins.join(", "))
+            }
+            IssueChange::Milestone(Some(milestone)) => {
+                format!("Moved #{} to {}", number, milestone.title)
+            }
+            IssueChange::Milestone(None) => format!("Removed #{} from its milestone", number),
+            IssueChange::Lock(_) => format!("Locked #{}", number),
+            IssueChange::Unlock => format!("Unlocked #{}", number),
+        }
+    }
+}
+
+// Picker for one triage action on an issue, opened over the table or popup
+#[derive(Debug)]
+pub struct Triage {
+    pub repo: String,
+    pub number: u32,
+    pub action: TriageAction,
+    pub picker: Picker,
+    pub state: LoadingState,
+    milestones: Vec<Milestone>,
+    pub return_mode: AppMode,
+}
+
+impl Triage {
+    pub fn new(repo: String, issue: &Issue, action: TriageAction, return_mode: AppMode) -> Self {
+        let fixed = |names: &[&str]| {
+            Picker::new(
+                names.iter().map(|name| (name.to_string(), None)).collect(),
+                false,
+            )
+        };
+        let (picker, state) = match action {
+            TriageAction::Close => {
+                let mut picker = fixed(&["completed", "not planned"]);
+                picker.choose(&[String::from("completed")]);
+                (picker, LoadingState::Success)
+            }
+            TriageAction::Lock => (
+                fixed(&["off-topic", "too heated", "resolved", "spam"]),
+                LoadingState::Success,
+            ),
+            _ => (Picker::default(), LoadingState::Loading),
+        };
+
+        Self {
+            repo,
+            number: issue.number,
+            action,
+            picker,
+            state,
+            milestones: Vec::new(),
+            return_mode,
+        }
+    }
+
+    // Fill the picker with the repo's choices, with the issue's current ones
+    // picked. Current values the repo doesn't list (a label or milestone
+    // since closed, an assignee who lost access) are added, so applying the
+    // picker doesn't drop them.
+    pub fn set_options(&mut self, options: IssueFormOptions, issue: &Issue) {
+        let mut milestones = options.milestones;
+        if let Some(milestone) = &issue.milestone
+            && !milestones.iter().any(|m| m.number == milestone.number)
+        {
+            milestones.push(milestone.clone());
+        }
+        let (mut items, current, multi): (Vec<(String, Option<String>)>, Vec<_>, bool) =
+            match self.action {
+                TriageAction::Labels => (
+                    options
+                        .labels
+                        .into_iter()
+                        .map(|label| (label.name, label.color))
+                        .collect(),
+                    issue
+                        .labels
+                        .iter()
+                        .map(|l| (l.name.clone(), l.color.clone()))
+                        .collect(),
+                    true,
+                ),
+                TriageAction::Assignees => (
+                    options
+                        .assignees
+                        .into_iter()
+                        .map(|user| (user.login, None))
+                        .collect(),
+                    issue
+                        .assignees
+                        .iter()
+                        .map(|u| (u.login.clone(), None))
+                        .collect(),
+                    true,
+                ),
+                TriageAction::Milestone => (
+                    milestones.iter().map(|m| (m.title.clone(), None)).collect(),
+                    issue
+                        .milestone
+                        .iter()
+                        .map(|m| (m.title.clone(), None))
+                        .collect(),
+                    false,
+                ),
+                TriageAction::Close | TriageAction::Lock => return,
+            };
+        for (name, color) in &current {
+            if !items
+                .iter()
+                .any(|(item, _)| item.eq_ignore_ascii_case(name))
+            {
+                items.push((name.clone(), color.clone()));
+            }
+        }
+        let names: Vec<String> = current.into_iter().map(|(name, _)| name).collect();
+        self.picker = Picker::new(items, multi);
+        self.picker.choose(&names);
+        self.milestones = milestones;
+        self.state = LoadingState::Success;
+    }
+
+    // what applying the picker's choice comes down to
+    pub fn change(&self) -> IssueChange {
+        let chosen = self.picker.chosen();
+        match self.action {
+            TriageAction::Close => IssueChange::Close(
+                chosen
+                    .first()
+                    .map_or(String::from("completed"), |reason| reason.replace(' ', "_")),
+            ),
+            TriageAction::Labels => IssueChange::Labels(
+                self.picker
+                    .items()
+                    .iter()
+                    .filter(|item| item.chosen)
+                    .map(|item| Label {
+                        url: String::new(),
+                        description: None,
+                        color: item.color.clone(),
+                        name: item.name.clone(),
+                    })
+                    .collect(),
+            ),
+            TriageAction::Assignees => IssueChange::Assignees(chosen),
+            TriageAction::Milestone => IssueChange::Milestone(
+                self.milestones
+                    .iter()
+                    .find(|m| chosen.contains(&m.title))
+                    .cloned(),
+            ),
+            TriageAction::Lock => IssueChange::Lock(chosen.first().cloned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+    use serde_json::{Value, json};
+
+    fn body(change: IssueChange) -> Value {
+        serde_json::to_value(change.update().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn update_sends_only_the_changed_field() {
+        assert_eq!(
+            body(IssueChange::Close(String::from("not_planned"))),
+            json!({"state": "closed", "state_reason": "not_planned"})
+        );
+        assert_eq!(body(IssueChange::Reopen), json!({"state": "open"}));
+        assert_eq!(
+            body(IssueChange::Assignees(vec![String::from("octocat")])),
+            json!({"assignees": ["octocat"]})
+        );
+    }
+
+    #[test]
+    fn update_sends_label_names() {
+        let label = Label {
+            url: String::new(),
+            description: None,
+            color: Some(String::from("d73a4a")),
+            name: String::from("bug"),
+        };
+        assert_eq!(
+            body(IssueChange::Labels(vec![label])),
+            json!({"labels": ["bug"]})
+        );
+        assert_eq!(body(IssueChange::Labels(Vec::new())), json!({"labels": []}));
+    }
+
+    #[test]
+    fn update_clears_a_milestone_with_null() {
+        let milestone = Milestone {
+            number: 4,
+            title: String::from("v1.0"),
+        };
+        assert_eq!(
+            body(IssueChange::Milestone(Some(milestone))),
+            json!({"milestone": 4})
+        );
+        assert_eq!(
+            body(IssueChange::Milestone(None)),
+            json!({"milestone": null})
+        );
+    }
+
+    #[test]
+    fn revert_only_restores_the_changed_field() {
+        let previous = fixture::issue(2, "Crash on startup");
+        let mut shown = previous.clone();
+        let labels = IssueChange::Labels(Vec::new());
+        labels.apply(&mut shown);
+        IssueChange::Assignees(vec![String::from("monalisa")]).apply(&mut shown);
+        IssueChange::Close(String::from("completed")).apply(&mut shown);
+
+        labels.revert(&mut shown, &previous);
+        assert_eq!(shown.labels.len(), 1);
+        assert_eq!(shown.assignees[0].login, "monalisa");
+        assert_eq!(shown.state, "closed");
+
+        IssueChange::Reopen.revert(&mut shown, &previous);
+        assert_eq!(shown.state, "open");
+        assert_eq!(shown.state_reason, None);
+    }
+
+    fn options() -> IssueFormOptions {
+        IssueFormOptions {
+            labels: vec![fixture::label("enhancement", "a2eeef")],
+            assignees: vec![fixture::user("octocat")],
+            milestones: Vec::new(),
+            templates: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn unlisted_assignees_stay_chosen() {
+        let mut issue = fixture::issue(2, "Crash on startup");
+        issue.assignees = vec![fixture::user("octocat"), fixture::user("former-member")];
+        let mut triage = Triage::new(
+            String::from("octocat/hello-world"),
+            &issue,
+            TriageAction::Assignees,
+            AppMode::RepoDetail,
+        );
+        triage.set_options(options(), &issue);
+
+        assert_eq!(triage.picker.items().len(), 2);
+        let IssueChange::Assignees(logins) = triage.change() else {
+            panic!("not an assignee change");
+        };
+        assert_eq!(logins, vec!["octocat", "former-member"]);
+    }
+
+    #[test]
+    fn unlisted_labels_and_milestones_stay_chosen() {
+        let mut issue = fixture::issue(2, "Crash on startup");
+        issue.milestone = Some(Milestone {
+            number: 7,
+            title: String::from("closed milestone"),
+        });
+        let new = |action| {
+            let mut triage = Triage::new(String::new(), &issue, action, AppMode::RepoDetail);
+            triage.set_options(options(), &issue);
+            triage
+        };
+
+        let IssueChange::Labels(labels) = new(TriageAction::Labels).change() else {
+            panic!("not a label change");
+        };
+        let names: Vec<&str> = labels.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, vec!["bug"]);
+        assert_eq!(labels[0].color.as_deref(), Some("d73a4a"));
+
+        let IssueChange::Milestone(milestone) = new(TriageAction::Milestone).change() else {
+            panic!("not a milestone change");
+        };
+        assert_eq!(milestone.map(|m| m.number), Some(7));
+    }
+
+    #[test]
+    fn locking_is_not_a_patch() {
+        assert!(IssueChange::Lock(None).update().is_none());
+        assert!(IssueChange::Unlock.update().is_none());
+    }
+}
@@ -1,6 +1,20 @@
+use crate::api_status::{StatusSnapshot, format_duration};
 use crate::app::App;
 use crate::app::AppMode;
+use crate::app::DiffLayout;
+use crate::app::IssueField;
 use crate::app::LoadingState;
+use crate::app::Pagination;
+use crate::app::RepoDetailState;
+use crate::cache::now_secs;
+use crate::code::{EntryKind, FileBody, RefKind};
+use crate::diff::{DiffFile, DiffLine, LineKind};
+use crate::editor::TextEditor;
+use crate::error::ApiError;
+use crate::markdown;
+use crate::models::{CheckState, Commit, NewReviewComment, ReviewThread};
+use crate::picker::Picker;
+use crate::pull_action::{PullActionField, PullActionKind};
 use ratatui::text::Span;
 use ratatui::text::Text;
 use ratatui::{
@@ -8,10 +22,12 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::Line,
-    widgets::{Block, Borders, Padding, Paragraph, Row, Table, Wrap},
+    widgets::{Block, Borders, Cell, Clear, Padding, Paragraph, Row, Table, Wrap},
 };
+use std::collections::HashMap;
+use std::time::Duration;
 
-use crate::utils::{hex_to_color, popup_area};
+use crate::utils::{format_timestamp, hex_to_color, popup_area};
 
 pub fn render_ui(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
@@ -30,13 +46,53 @@ pub fn render_ui(f: &mut Frame, app: &mut App) {
         AppMode::RepoDetail => render_repo_detail(f, chunks[1], app),
         AppMode::Search => render_search_input(f, chunks[1], app),
         AppMode::IssuePopUp => render_issue_popup(f, chunks[1], app),
+        AppMode::PRPopUp => render_pr_popup(f, chunks[1], app),
+        AppMode::FilterPopUp => {
+            render_repo_detail(f, chunks[1], app);
+            render_filter_popup(f, chunks[1], app);
+        }
+        AppMode::DiffView => render_diff(f, chunks[1], app),
+        AppMode::CommentEditor => {
+            match app.comment_return_mode() {
+                Some(AppMode::PRPopUp) => render_pr_popup(f, chunks[1], app),
+                Some(AppMode::DiffView) => render_diff(f, chunks[1], app),
+                _ => render_issue_popup(f, chunks[1], app),
+            }
+            render_comment_editor(f, chunks[1], app);
+        }
+        AppMode::IssueForm => {
+            render_repo_detail(f, chunks[1], app);
+            render_issue_form(f, chunks[1], app);
+        }
+        AppMode::TriagePopUp => {
+            match app.triage_return_mode() {
+                Some(AppMode::IssuePopUp) => render_issue_popup(f, chunks[1], app),
+                _ => render_repo_detail(f, chunks[1], app),
+            }
+            render_triage_popup(f, chunks[1], app);
+        }
+        AppMode::PullActionPopUp => {
+            match app.pull_action.as_ref().map(|action| &action.return_mode) {
+                Some(AppMode::DiffView) => render_diff(f, chunks[1], app),
+                _ => render_pr_popup(f, chunks[1], app),
+            }
+            render_pull_action(f, chunks[1], app);
+        }
+        AppMode::RefPicker => {
+            render_repo_detail(f, chunks[1], app);
+            render_ref_picker(f, chunks[1], app);
+        }
     }
 
     render_footer(f, chunks[2], app);
 }
 
 fn render_header(f: &mut Frame, area: Rect, app: &App) {
-    if app.mode == AppMode::IssuePopUp {
+    if matches!(
+        app.mode,
+        AppMode::IssuePopUp | AppMode::PRPopUp | AppMode::CommentEditor | AppMode::PullActionPopUp
+    ) || app.triage_return_mode() == Some(&AppMode::IssuePopUp)
+    {
         return;
     }
 
@@ -44,9 +100,20 @@ fn render_header(f: &mut Frame, area: Rect, app: &App) {
         AppMode::RepoList => {
             Line::from(format!("GitHub Repos - {}", app.user)).alignment(Alignment::Center)
         }
-        AppMode::RepoDetail => Line::from("Repository Details").alignment(Alignment::Center),
+        AppMode::RepoDetail
+        | AppMode::FilterPopUp
+        | AppMode::IssueForm
+        | AppMode::TriagePopUp
+        | AppMode::RefPicker => Line::from("Repository Details").alignment(Alignment::Center),
         AppMode::Search => Line::from("Search Repositories").alignment(Alignment::Center),
-        AppMode::IssuePopUp => Line::from("_").alignment(Alignment::Center),
+        AppMode::DiffView if app.diff.as_ref().is_some_and(|diff| diff.sha.is_some()) => {
+            Line::from("Commit").alignment(Alignment::Center)
+        }
+        AppMode::DiffView => Line::from("Pull Request Diff").alignment(Alignment::Center),
+        AppMode::IssuePopUp
+        | AppMode::PRPopUp
+        | AppMode::CommentEditor
+        | AppMode::PullActionPopUp => Line::from("_").alignment(Alignment::Center),
     };
 
     let header = Paragraph::new(title)
@@ -63,12 +130,60 @@ fn render_header(f: &mut Frame, area: Rect, app: &App) {
                     area.height / 4, // top
                     0,               // bottom
                 ))
-                .borders(Borders::ALL),
+                .borders(Borders::ALL)
+                .title_top(quota_line(&app.api_status()).right_aligned()),
         );
 
     f.render_widget(header, area);
 }
 
+// remaining API quota, e.g. `core 4990/5000 (resets in 42m10s)`
+fn quota_line(status: &StatusSnapshot) -> Line<'static> {
+    if status.offline {
+        return Line::from(" OFFLINE - showing cached data ")
+            .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD));
+    }
+
+    let mut resources: Vec<_> = status.rate_limits.iter().collect();
+    resources.sort_by(|a, b| a.0.cmp(b.0));
+
+    let spans: Vec<Span> = resources
+        .into_iter()
+        .flat_map(|(resource, limit)| {
+            let color = if limit.remaining == 0 {
+                Color::Red
+            } else if limit.remaining * 10 < limit.limit {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            [
+                Span::from(format!(" {} ", resource)).style(Style::default().fg(Color::Gray)),
+                Span::from(format!("{}/{}", limit.remaining, limit.limit))
+                    .style(Style::default().fg(color)),
+                Span::from(format!(
+                    " (resets in {}) ",
+                    format_duration(limit.resets_in())
+                ))
+                .style(Style::default().fg(Color::Gray)),
+            ]
+        })
+        .collect();
+
+    Line::from(spans)
+}
+
+// `, cached 3h02m ago` for data served from the offline cache
+fn staleness(stale_since: Option<u64>) -> String {
+    match stale_since {
+        Some(at) => format!(
+            ", cached {} ago",
+            format_duration(Duration::from_secs(now_secs().saturating_sub(at)))
+        ),
+        None => String::new(),
+    }
+}
+
 fn render_repo_list(f: &mut Frame, area: Rect, app: &mut App) {
     match &app.loading_state {
         LoadingState::Loading => {
@@ -78,10 +193,7 @@ fn render_repo_list(f: &mut Frame, area: Rect, app: &mut App) {
             f.render_widget(loading, area);
         }
         LoadingState::Error(err) => {
-            let error = Paragraph::new(format!("Error: {}", err))
-                .style(Style::default().fg(Color::Red))
-                .block(Block::default().borders(Borders::ALL).title("Error"));
-            f.render_widget(error, area);
+            f.render_widget(error_paragraph(err, "Error"), area);
         }
         _ => {
             if app.repos.is_empty() {
@@ -119,7 +231,11 @@ fn render_repo_list(f: &mut Frame, area: Rect, app: &mut App) {
                 ],
             )
             .header(header)
-            .block(Block::default().borders(Borders::ALL).title("Repositories"))
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                "Repositories ({}{})",
+                app.repos.len(),
+                staleness(app.repos_stale_since)
+            )))
             .row_highlight_style(
                 Style::default()
                     .bg(Color::DarkGray)
@@ -133,139 +249,580 @@ fn render_repo_list(f: &mut Frame, area: Rect, app: &mut App) {
 }
 
 fn render_repo_detail(f: &mut Frame, area: Rect, app: &mut App) {
-    match &app.loading_state {
-        LoadingState::Loading => {
-            let loading = Paragraph::new("Loading repositories...")
-                .style(Style::default().fg(Color::Yellow))
-                .block(Block::default().borders(Borders::ALL).title("Repositories"));
-            f.render_widget(loading, area);
-        }
-        LoadingState::Error(err) => {
-            let error = Paragraph::new(format!("Error: {}", err))
-                .style(Style::default().fg(Color::Red))
-                .block(Block::default().borders(Borders::ALL).title("Error"));
-            f.render_widget(error, area);
-        }
-        _ => {
-            if let Some(repo) = &app.selected_repo {
-                let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .margin(1)
-                    .constraints([
-                        Constraint::Percentage(20), // repo info
-                        Constraint::Percentage(40), // issues
-                        Constraint::Percentage(40), // PRs
-                    ])
-                    .split(area);
-
-                let details = format!(
-                    "Name: {}\n\
-                        Stars: ⭐ {}\n\
-                        Forks: 🍴 {}\n\
-                        Language: {}\n\
-                        Created: {}\n\
-                        Updated: {}\n\n\
-                        Description:\n{}\n\n\
-                        URL: {}",
-                    repo.full_name,
-                    repo.stargazers_count,
-                    repo.forks_count,
-                    repo.language.as_deref().unwrap_or("N/A"),
-                    repo.created_at,
-                    repo.updated_at,
-                    repo.description.as_deref().unwrap_or("No description"),
-                    repo.html_url
+    let Some(repo) = &app.selected_repo else {
+        return;
+    };
+    match app.detail_mode {
+        RepoDetailState::Code => return render_code(f, area, app),
+        RepoDetailState::Commits => return render_commits(f, area, app),
+        _ => {}
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Percentage(20), // repo info
+            Constraint::Percentage(40), // issues
+            Constraint::Percentage(40), // PRs
+        ])
+        .split(area);
+
+    let counts = match &app.repo_counts {
+        Some(counts) => format!(
+            "Open issues: {} | Open PRs: {}\n",
+            counts.open_issues, counts.open_prs
+        ),
+        None => String::new(),
+    };
+    let details = format!(
+        "Name: {}\n\
+            Stars: ⭐ {}\n\
+            Forks: 🍴 {}\n\
+            Language: {}\n\
+            {}\
+            Created: {}\n\
+            Updated: {}\n\n\
+            Description:\n{}\n\n\
+            URL: {}",
+        repo.full_name,
+        repo.stargazers_count,
+        repo.forks_count,
+        repo.language.as_deref().unwrap_or("N/A"),
+        counts,
+        repo.created_at,
+        repo.updated_at,
+        repo.description.as_deref().unwrap_or("No description"),
+        repo.html_url
+    );
+
+    // the README follows the stats, scrolled along with them
+    let mut text = Text::from(details);
+    text.push_line(Line::default());
+    match &app.readme_state {
+        LoadingState::Loading => text
+            .push_line(Line::from("Loading README...").style(Style::default().fg(Color::Yellow))),
+        LoadingState::Error(err) => text.push_line(
+            Line::from(format!("Couldn't load the README: {}", err))
+                .style(Style::default().fg(Color::Red)),
+        ),
+        _ => match &app.readme {
+            Some(readme) => {
+                text.push_line(
+                    Line::from("README").style(
+                        Style::default()
+                            .fg(Color::Gray)
+                            .add_modifier(Modifier::BOLD),
+                    ),
                 );
+                text.extend(readme.lines.iter().cloned());
+            }
+            None => text.push_line(Line::from("No README").style(Style::default().fg(Color::Gray))),
+        },
+    }
 
-                let paragraph = Paragraph::new(details)
-                    .block(
-                        Block::default()
-                            .borders(Borders::ALL)
-                            .title("Repository Details"),
-                    )
-                    .wrap(Wrap { trim: true })
-                    .scroll((app.scroll_offset, 0));
-
-                let issue_header = Row::new(vec!["Title", "Body", "State", "URL", "Labels"])
-                    .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
-                    .bottom_margin(1)
-                    .top_margin(2);
-
-                let issues = app.issues.clone();
-
-                let issue_rows = issues.iter().map(|issue| {
-                    Row::new(vec![
-                        issue.title.clone(),
-                        issue.body.clone().unwrap_or(String::from("N/A")),
-                        issue.state.clone(),
-                        issue.html_url.clone(),
-                        issue
-                            .labels
-                            .iter()
-                            .map(|label| label.name.clone())
-                            .collect::<Vec<String>>()
-                            .join(", "),
-                    ])
-                });
-
-                let issue_table = Table::new(
-                    issue_rows,
-                    [
-                        Constraint::Percentage(10),
-                        Constraint::Length(40),
-                        Constraint::Length(6),
-                        Constraint::Percentage(32),
-                        Constraint::Percentage(12),
-                    ],
-                )
-                .header(issue_header)
-                .block(Block::default().borders(Borders::ALL).title("Issues"))
-                .row_highlight_style(
-                    Style::default()
-                        .bg(Color::DarkGray)
-                        .add_modifier(Modifier::BOLD),
-                )
-                .highlight_symbol(">! ");
-
-                let pr_header = Row::new(vec!["Title", "Body", "State", "URL"])
-                    .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
-                    .bottom_margin(1)
-                    .top_margin(2);
-
-                let prs = app.prs.clone();
-
-                let pr_rows = prs.iter().map(|pr| {
-                    Row::new(vec![
-                        pr.title.clone(),
-                        pr.body.clone().unwrap_or(String::from("N/A")),
-                        pr.state.clone(),
-                        pr.html_url.clone(),
-                    ])
-                });
-
-                let pr_table = Table::new(
-                    pr_rows,
-                    [
-                        Constraint::Percentage(10),
-                        Constraint::Length(50),
-                        Constraint::Length(8),
-                        Constraint::Percentage(32),
-                    ],
-                )
-                .header(pr_header)
-                .block(Block::default().borders(Borders::ALL).title("PRs"))
-                .row_highlight_style(
+    let paragraph = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Repository Details"),
+        )
+        // untrimmed, so README code blocks keep their indentation
+        .wrap(Wrap { trim: false })
+        .scroll((app.scroll_offset, 0));
+
+    f.render_widget(paragraph, chunks[0]);
+    render_issue_table(f, chunks[1], app);
+    render_pr_table(f, chunks[2], app);
+}
+
+// code tab: directory tree on the left, the open file on the right
+fn render_code(f: &mut Frame, area: Rect, app: &mut App) {
+    let Some(code) = &mut app.code else {
+        return;
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .margin(1)
+        .constraints([Constraint::Percentage(30), Constraint::Min(0)])
+        .split(area);
+
+    let title = match &code.commit {
+        Some(commit) => format!(
+            "Code @ {} ({})",
+            code.git_ref,
+            &commit[..commit.len().min(7)]
+        ),
+        None => format!("Code @ {}", code.git_ref),
+    };
+    if let Some(placeholder) = loading_placeholder(&code.state, "files", &title) {
+        f.render_widget(placeholder, area);
+        return;
+    }
+
+    let focus = |focused: bool| {
+        if focused {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        }
+    };
+    let rows: Vec<Line> = code
+        .tree
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let (icon, style) = match row.kind {
+                EntryKind::Dir if row.expanded => ("▾ ", Style::default().fg(Color::Blue)),
+                EntryKind::Dir => ("▸ ", Style::default().fg(Color::Blue)),
+                EntryKind::Submodule => ("@ ", Style::default().fg(Color::Magenta)),
+                EntryKind::File => ("  ", Style::default()),
+            };
+            let line = Line::from(vec![
+                Span::from("  ".repeat(row.depth)),
+                Span::from(icon).style(style),
+                Span::from(row.name.clone()).style(style),
+            ]);
+            if i == code.selected {
+                line.style(
                     Style::default()
                         .bg(Color::DarkGray)
                         .add_modifier(Modifier::BOLD),
                 )
-                .highlight_symbol(">! ");
+            } else {
+                line
+            }
+        })
+        .collect();
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(focus(!code.viewing));
+    if code.truncated {
+        block = block.title_bottom(
+            Line::from(" Too many files, GitHub cut the list short ")
+                .style(Style::default().fg(Color::Yellow)),
+        );
+    }
+    let tree = Paragraph::new(rows)
+        .block(block)
+        // keep the selected row in view
+        .scroll((
+            code.selected
+                .saturating_sub(chunks[0].height.saturating_sub(3) as usize) as u16,
+            0,
+        ));
+    f.render_widget(tree, chunks[0]);
+
+    let title = code
+        .path
+        .clone()
+        .unwrap_or_else(|| String::from("No file open"));
+    if let Some(placeholder) = loading_placeholder(&code.file_state, "file", &title) {
+        f.render_widget(placeholder, chunks[1]);
+        return;
+    }
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(focus(code.viewing));
+    let Some(file) = &code.file else {
+        let hint = Paragraph::new("Enter opens the selected file")
+            .style(Style::default().fg(Color::Gray))
+            .block(block.title(title));
+        f.render_widget(hint, chunks[1]);
+        return;
+    };
+    let block = block.title(format!("{} ({} bytes)", file.path, file.size));
+    let lines = match &file.body {
+        FileBody::Text(lines) => lines,
+        FileBody::Binary => {
+            let binary = Paragraph::new("Binary file, not shown")
+                .style(Style::default().fg(Color::Gray))
+                .block(block);
+            f.render_widget(binary, chunks[1]);
+            return;
+        }
+        FileBody::TooLarge => {
+            let large = Paragraph::new("File too large to show (over 1 MB)")
+                .style(Style::default().fg(Color::Gray))
+                .block(block);
+            f.render_widget(large, chunks[1]);
+            return;
+        }
+    };
+
+    // scroll just enough to keep the cursor line in view
+    let height = chunks[1].height.saturating_sub(2) as usize;
+    if code.cursor < code.scroll {
+        code.scroll = code.cursor;
+    } else if code.cursor >= code.scroll + height {
+        code.scroll = code.cursor + 1 - height;
+    }
+    let width = lines.len().to_string().len();
+    let shown: Vec<Line> = lines
+        .iter()
+        .enumerate()
+        .skip(code.scroll)
+        .take(height)
+        .map(|(i, spans)| {
+            let number_style = if i == code.cursor && code.viewing {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            let mut line = vec![Span::from(format!("{:>width$} ", i + 1)).style(number_style)];
+            line.extend(spans.iter().cloned());
+            let line = Line::from(line);
+            if i == code.cursor && code.viewing {
+                line.style(Style::default().bg(Color::Rgb(40, 40, 40)))
+            } else {
+                line
+            }
+        })
+        .collect();
+    f.render_widget(Paragraph::new(shown).block(block), chunks[1]);
+}
+
+// branch, tag or commit for the code tab
+fn render_ref_picker(f: &mut Frame, area: Rect, app: &App) {
+    let Some(picker) = &app.ref_picker else {
+        return;
+    };
+    let area = popup_area(area, 50, 60);
+    f.render_widget(Clear, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+
+    let input = Paragraph::new(format!("{}_", picker.input)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Branch, tag or commit"),
+    );
+    f.render_widget(input, chunks[0]);
+
+    if let Some(placeholder) = loading_placeholder(&app.refs_state, "branches and tags", "Refs") {
+        f.render_widget(placeholder, chunks[1]);
+        return;
+    }
+    let matches = picker.matches();
+    let lines: Vec<Line> = if matches.is_empty() {
+        vec![
+            Line::from("No branch or tag matches; Enter looks it up as a commit")
+                .style(Style::default().fg(Color::Gray)),
+        ]
+    } else {
+        matches
+            .iter()
+            .enumerate()
+            .map(|(i, (name, kind))| {
+                let kind = match kind {
+                    RefKind::Branch => {
+                        Span::from("branch ").style(Style::default().fg(Color::Blue))
+                    }
+                    RefKind::Tag => {
+                        Span::from("tag    ").style(Style::default().fg(Color::Magenta))
+                    }
+                };
+                let line = Line::from(vec![kind, Span::from(name.clone())]);
+                if i == picker.cursor {
+                    line.style(
+                        Style::default()
+                            .bg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    line
+                }
+            })
+            .collect()
+    };
+    let list = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title("Refs"))
+        .scroll((
+            picker
+                .cursor
+                .saturating_sub(chunks[1].height.saturating_sub(3) as usize) as u16,
+            0,
+        ));
+    f.render_widget(list, chunks[1]);
+}
 
-                f.render_widget(paragraph, chunks[0]);
-                f.render_stateful_widget(issue_table, chunks[1], &mut app.issue_table_state);
-                f.render_stateful_widget(pr_table, chunks[2], &mut app.pr_table_state);
+// placeholder shown in place of a table while its data is loading or failed
+fn loading_placeholder(
+    state: &LoadingState,
+    what: &str,
+    title: &str,
+) -> Option<Paragraph<'static>> {
+    match state {
+        LoadingState::Loading => Some(
+            Paragraph::new(format!("Loading {}...", what))
+                .style(Style::default().fg(Color::Yellow))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(title.to_string()),
+                ),
+        ),
+        LoadingState::Error(err) => Some(error_paragraph(err, title)),
+        _ => None,
+    }
+}
+
+// error panel: what failed, GitHub's explanation and what to do about it
+fn error_paragraph(err: &ApiError, title: &str) -> Paragraph<'static> {
+    let mut lines = vec![
+        Line::from(err.title()).style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+    ];
+
+    match err.response() {
+        Some(response) => {
+            lines.push(Line::from(format!(
+                "HTTP {}: {}",
+                response.status, response.message
+            )));
+            for detail in &response.details {
+                lines.push(Line::from(format!("  - {}", detail)));
             }
         }
+        None => lines.push(Line::from(err.to_string())),
+    }
+
+    if let Some(hint) = err.hint() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!("Hint: {}", hint)).style(Style::default().fg(Color::Yellow)));
+    }
+    if let Some(url) = err.response().and_then(|r| r.documentation_url.clone()) {
+        lines.push(Line::from(format!("Docs: {}", url)).style(Style::default().fg(Color::Gray)));
+    }
+
+    Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title.to_string())
+            .border_style(Style::default().fg(Color::Red)),
+    )
+}
+
+// table title with how much of the listing has been loaded so far
+fn paged_title(name: &str, count: usize, pages: &Pagination) -> String {
+    let stale = staleness(pages.stale_since);
+    if pages.loading_more {
+        format!("{} ({}, loading more...{})", name, count, stale)
+    } else if let Some(err) = &pages.error {
+        format!(
+            "{} ({}, failed to load more: {}{})",
+            name, count, err, stale
+        )
+    } else if pages.next.is_some() {
+        format!("{} ({}, more below{})", name, count, stale)
+    } else {
+        format!("{} ({}{})", name, count, stale)
+    }
+}
+
+fn render_issue_table(f: &mut Frame, area: Rect, app: &mut App) {
+    if let Some(placeholder) = loading_placeholder(&app.issues_state, "issues", "Issues") {
+        f.render_widget(placeholder, area);
+        return;
+    }
+
+    let issue_header = Row::new(vec!["Title", "Body", "State", "URL", "Labels"])
+        .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+        .bottom_margin(1)
+        .top_margin(2);
+
+    let issue_rows = app.visible_issues().map(|issue| {
+        let title = if issue.is_pull_request() {
+            format!("[PR] {}", issue.title)
+        } else {
+            issue.title.clone()
+        };
+        Row::new(vec![
+            Cell::from(title),
+            Cell::from(body_summary(issue.body.as_deref())),
+            Cell::from(issue.state.clone()),
+            Cell::from(issue.html_url.clone()),
+            Cell::from(
+                issue
+                    .labels
+                    .iter()
+                    .map(|label| label.name.clone())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ),
+        ])
+    });
+
+    let issue_table = Table::new(
+        issue_rows,
+        [
+            Constraint::Percentage(10),
+            Constraint::Length(40),
+            Constraint::Length(6),
+            Constraint::Percentage(32),
+            Constraint::Percentage(12),
+        ],
+    )
+    .header(issue_header)
+    .block(Block::default().borders(Borders::ALL).title(paged_title(
+        &format!(
+            "{} [{}]",
+            if app.issues_include_prs {
+                "Issues + PRs"
+            } else {
+                "Issues"
+            },
+            app.issue_filter.summary()
+        ),
+        app.visible_issue_count(),
+        &app.issues_pages,
+    )))
+    .row_highlight_style(
+        Style::default()
+            .bg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD),
+    )
+    .highlight_symbol(">! ");
+
+    f.render_stateful_widget(issue_table, area, &mut app.issue_table_state);
+}
+
+fn render_pr_table(f: &mut Frame, area: Rect, app: &mut App) {
+    if let Some(placeholder) = loading_placeholder(&app.prs_state, "pull requests", "PRs") {
+        f.render_widget(placeholder, area);
+        return;
+    }
+
+    let pr_header = Row::new(vec!["Title", "Body", "State", "URL"])
+        .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+        .bottom_margin(1)
+        .top_margin(2);
+
+    let pr_rows = app.prs.iter().map(|pr| {
+        Row::new(vec![
+            Cell::from(pr.title.clone()),
+            Cell::from(body_summary(pr.body.as_deref())),
+            Cell::from(pr.state.clone()),
+            Cell::from(pr.html_url.clone()),
+        ])
+    });
+
+    let pr_table = Table::new(
+        pr_rows,
+        [
+            Constraint::Percentage(10),
+            Constraint::Length(50),
+            Constraint::Length(8),
+            Constraint::Percentage(32),
+        ],
+    )
+    .header(pr_header)
+    .block(Block::default().borders(Borders::ALL).title(paged_title(
+        &format!("PRs [{}]", app.pr_filter.summary()),
+        app.prs.len(),
+        &app.prs_pages,
+    )))
+    .row_highlight_style(
+        Style::default()
+            .bg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD),
+    )
+    .highlight_symbol(">! ");
+
+    f.render_stateful_widget(pr_table, area, &mut app.pr_table_state);
+}
+
+fn render_commits(f: &mut Frame, area: Rect, app: &mut App) {
+    let Some(log) = &mut app.commits else {
+        return;
+    };
+    let area = Layout::default()
+        .margin(1)
+        .constraints([Constraint::Min(0)])
+        .split(area)[0];
+    if let Some(placeholder) = loading_placeholder(&log.state, "commits", "Commits") {
+        f.render_widget(placeholder, area);
+        return;
+    }
+
+    let mut name = match log.pr {
+        Some(number) => format!("Commits of #{}", number),
+        None => {
+            let default_branch = app
+                .selected_repo
+                .as_ref()
+                .and_then(|repo| repo.default_branch.as_deref());
+            format!(
+                "Commits of {}",
+                log.filter
+                    .git_ref
+                    .as_deref()
+                    .or(default_branch)
+                    .unwrap_or("HEAD")
+            )
+        }
+    };
+    let summary = log.filter.summary();
+    if !summary.is_empty() {
+        name = format!("{} [{}]", name, summary);
+    }
+
+    let header = Row::new(vec!["SHA", "Author", "Date", "Subject"])
+        .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+        .bottom_margin(1);
+
+    let rows = log.commits.iter().map(|commit| {
+        Row::new(vec![
+            Cell::from(commit.short_sha().to_string()).style(Style::default().fg(Color::Yellow)),
+            Cell::from(commit.author_name().to_string()),
+            Cell::from(format_timestamp(commit.date())).style(Style::default().fg(Color::Gray)),
+            Cell::from(commit.subject().to_string()),
+        ])
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(20),
+            Constraint::Length(17),
+            Constraint::Min(0),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(paged_title(
+        &name,
+        log.commits.len(),
+        &log.pages,
+    )))
+    .row_highlight_style(
+        Style::default()
+            .bg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD),
+    )
+    .highlight_symbol(">! ");
+
+    f.render_stateful_widget(table, area, &mut log.table_state);
+}
+
+// the Markdown of a body flattened onto one table cell
+fn body_summary(body: Option<&str>) -> Line<'static> {
+    match body.filter(|body| !body.trim().is_empty()) {
+        Some(body) => markdown::summary(body),
+        None => Line::from("N/A").style(Style::default().fg(Color::Gray)),
+    }
+}
+
+// a body rendered as Markdown, or `empty` in gray
+fn body_text(body: Option<&str>, empty: &str) -> Text<'static> {
+    match body.filter(|body| !body.trim().is_empty()) {
+        Some(body) => markdown::render_cached(body),
+        None => Text::from(empty.to_string()).style(Style::default().fg(Color::Gray)),
     }
 }
 
@@ -278,31 +835,57 @@ fn render_search_input(f: &mut Frame, area: Rect, app: &App) {
 }
 
 fn render_issue_popup(f: &mut Frame, area: Rect, app: &App) {
-    let area = popup_area(area, 80, 60);
+    let area = popup_area(area, 80, 80);
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
+        .constraints([Constraint::Min(0), Constraint::Length(3)])
         .split(area);
 
-    let issue = app.selected_issue.clone().unwrap();
+    let Some(issue) = app.selected_issue.clone() else {
+        return;
+    };
+    let state = match issue.state_reason.as_deref() {
+        Some(reason) if issue.state == "closed" => {
+            format!("{} as {}", issue.state, reason.replace('_', " "))
+        }
+        _ => issue.state.clone(),
+    };
+    let assignees: Vec<&str> = issue.assignees.iter().map(|u| u.login.as_str()).collect();
     let details = format!(
         "Title: {}\n\
-        State: {}\n\
-        Body: {}\n\
-        URL: {}",
+        State: {}{}\n\
+        Author: {}\n\
+        Assignees: {}\n\
+        Milestone: {}\n\
+        URL: {}\n",
         issue.title,
-        issue.state,
-        issue.body.unwrap_or(String::from("N/A")),
+        state,
+        if issue.locked { " (locked)" } else { "" },
+        issue.user.as_ref().map_or("ghost", |u| u.login.as_str()),
+        if assignees.is_empty() {
+            String::from("none")
+        } else {
+            assignees.join(", ")
+        },
+        issue
+            .milestone
+            .as_ref()
+            .map_or("none", |m| m.title.as_str()),
         issue.html_url,
     );
 
-    let paragraph = Paragraph::new(details)
+    let mut text = Text::from(details);
+    text.extend(body_text(issue.body.as_deref(), "No description"));
+    text.extend(comment_timeline(app));
+
+    let paragraph = Paragraph::new(text)
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .title(format!("Issue #{} details", issue.number)),
         )
-        .wrap(Wrap { trim: true });
+        .wrap(Wrap { trim: false })
+        .scroll((app.popup_scroll, 0));
 
     let mut spans: Vec<Span> = Vec::new();
 
@@ -325,19 +908,1084 @@ fn render_issue_popup(f: &mut Frame, area: Rect, app: &App) {
     f.render_widget(labels_paragraph, chunks[1]);
 }
 
+fn check_style(state: CheckState) -> (&'static str, Style) {
+    match state {
+        CheckState::Success => ("✓", Style::default().fg(Color::Green)),
+        CheckState::Failure => ("✗", Style::default().fg(Color::Red)),
+        CheckState::Pending => ("●", Style::default().fg(Color::Yellow)),
+        CheckState::Neutral => ("-", Style::default().fg(Color::Gray)),
+    }
+}
+
+fn render_pr_popup(f: &mut Frame, area: Rect, app: &App) {
+    let area = popup_area(area, 80, 80);
+    let Some(pr) = &app.selected_pr else {
+        return;
+    };
+    let title = format!("PR #{} details", pr.number);
+
+    if let Some(placeholder) = loading_placeholder(&app.pull_detail_state, "pull request", &title) {
+        f.render_widget(Clear, area);
+        f.render_widget(placeholder, area);
+        return;
+    }
+    let Some(detail) = &app.pull_detail else {
+        return;
+    };
+    let pr = &detail.pr;
+    let label =
+        |name: &str| Span::from(format!("{:<11}", name)).style(Style::default().fg(Color::Gray));
+    let mut lines = Vec::new();
+
+    lines.push(Line::from(pr.title.clone()).style(Style::default().add_modifier(Modifier::BOLD)));
+    let state = if pr.merged_at.is_some() {
+        String::from("merged")
+    } else if pr.draft {
+        format!("{} (draft)", pr.state)
+    } else {
+        pr.state.clone()
+    };
+    lines.push(Line::from(vec![label("State"), Span::from(state)]));
+    lines.push(Line::from(vec![
+        label("Author"),
+        Span::from(
+            pr.user
+                .as_ref()
+                .map_or("ghost", |u| u.login.as_str())
+                .to_string(),
+        ),
+    ]));
+    if let (Some(head), Some(base)) = (&pr.head, &pr.base) {
+        lines.push(Line::from(vec![
+            label("Branches"),
+            Span::from(format!("{} → {}", head.name, base.name)),
+        ]));
+    }
+    let mergeable = match (pr.mergeable, pr.mergeable_state.as_deref()) {
+        (None, _) => String::from("checking..."),
+        (Some(true), Some(state)) => format!("yes ({})", state),
+        (Some(false), Some(state)) => format!("no ({})", state),
+        (Some(mergeable), None) => String::from(if mergeable { "yes" } else { "no" }),
+    };
+    lines.push(Line::from(vec![label("Mergeable"), Span::from(mergeable)]));
+    lines.push(Line::from(vec![
+        label("Changes"),
+        Span::from(format!("+{}", pr.additions.unwrap_or_default()))
+            .style(Style::default().fg(Color::Green)),
+        Span::from(" "),
+        Span::from(format!("-{}", pr.deletions.unwrap_or_default()))
+            .style(Style::default().fg(Color::Red)),
+        Span::from(format!(
+            " in {} files, {} commits",
+            pr.changed_files.unwrap_or_default(),
+            pr.commits.unwrap_or_default()
+        )),
+    ]));
+
+    let requested: Vec<String> = pr
+        .requested_reviewers
+        .iter()
+        .map(|u| format!("@{}", u.login))
+        .collect();
+    if !requested.is_empty() {
+        lines.push(Line::from(vec![
+            label("Requested"),
+            Span::from(requested.join(", ")),
+        ]));
+    }
+    for (login, state) in detail.review_decisions() {
+        let color = match state.as_str() {
+            "APPROVED" => Color::Green,
+            "CHANGES_REQUESTED" => Color::Red,
+            _ => Color::Gray,
+        };
+        lines.push(Line::from(vec![
+            label("Review"),
+            Span::from(format!("@{} ", login)),
+            Span::from(state.to_lowercase().replace('_', " ")).style(Style::default().fg(color)),
+        ]));
+    }
+
+    match detail.ci_state() {
+        Some(state) => {
+            let (symbol, style) = check_style(state);
+            lines.push(Line::from(vec![
+                label("CI"),
+                Span::from(symbol).style(style),
+                Span::from(format!(" {} checks", detail.checks.len())),
+            ]));
+            for check in &detail.checks {
+                let (symbol, style) = check_style(check.state);
+                lines.push(Line::from(vec![
+                    Span::from("             "),
+                    Span::from(symbol).style(style),
+                    Span::from(format!(" {}", check.name)),
+                ]));
+            }
+        }
+        None => lines.push(Line::from(vec![label("CI"), Span::from("no checks")])),
+    }
+
+    lines.push(Line::from(""));
+    lines.extend(body_text(pr.body.as_deref(), "No description"));
+    lines.push(Line::from(""));
+    lines.push(Line::from(pr.html_url.clone()).style(Style::default().fg(Color::Gray)));
+
+    lines.extend(comment_timeline(app));
+
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .wrap(Wrap { trim: false })
+        .scroll((app.popup_scroll, 0));
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+// the issue's comments, one block per comment, below its body
+fn comment_timeline(app: &App) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from("")];
+    match &app.comments_state {
+        LoadingState::Loading => {
+            lines.push(Line::from("Loading comments...").style(Style::default().fg(Color::Yellow)));
+            return lines;
+        }
+        LoadingState::Error(err) => {
+            lines.push(
+                Line::from(format!("Failed to load comments: {}", err))
+                    .style(Style::default().fg(Color::Red)),
+            );
+            return lines;
+        }
+        _ => {}
+    }
+
+    lines.push(
+        Line::from(paged_title(
+            "Comments",
+            app.comments.len(),
+            &app.comments_pages,
+        ))
+        .style(Style::default().add_modifier(Modifier::BOLD)),
+    );
+    for comment in &app.comments {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::from(format!(
+                "@{}",
+                comment.user.as_ref().map_or("ghost", |u| u.login.as_str())
+            ))
+            .style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::from(format!(
+                " commented {}",
+                format_timestamp(&comment.created_at)
+            ))
+            .style(Style::default().fg(Color::Gray)),
+        ]));
+        if let Some(body) = &comment.body {
+            lines.extend(markdown::render_cached(body));
+        }
+        let reactions = comment
+            .reactions
+            .as_ref()
+            .map(|r| r.counts())
+            .unwrap_or_default();
+        if !reactions.is_empty() {
+            let summary: Vec<String> = reactions
+                .iter()
+                .map(|(emoji, count)| format!("{} {}", emoji, count))
+                .collect();
+            lines.push(Line::from(summary.join("  ")).style(Style::default().fg(Color::Gray)));
+        }
+    }
+
+    lines
+}
+
+fn render_comment_editor(f: &mut Frame, area: Rect, app: &App) {
+    let Some(draft) = &app.draft else {
+        return;
+    };
+    let area = popup_area(area, 70, 60);
+    let target = match &draft.line {
+        Some(line) => match line.start_line {
+            Some(start) => format!("{}:{}-{}", line.path, start, line.line),
+            None => format!("{}:{}", line.path, line.line),
+        },
+        None => format!("#{}", draft.number),
+    };
+    let title = if draft.preview {
+        format!("Comment on {} (preview)", target)
+    } else {
+        format!("Comment on {}", target)
+    };
+    let mut block = Block::default().borders(Borders::ALL).title(title);
+    if draft.posting {
+        block = block
+            .title_bottom(Line::from(" Sending... ").style(Style::default().fg(Color::Yellow)));
+    } else if let Some(error) = &draft.error {
+        block = block.title_bottom(
+            Line::from(format!(" {} ", error)).style(Style::default().fg(Color::Red)),
+        );
+    }
+    f.render_widget(Clear, area);
+
+    if draft.preview {
+        let text = if draft.editor.is_blank() {
+            Text::from("Nothing to preview").style(Style::default().fg(Color::Gray))
+        } else {
+            markdown::render_cached(&draft.editor.text())
+        };
+        f.render_widget(
+            Paragraph::new(text).block(block).wrap(Wrap { trim: false }),
+            area,
+        );
+        return;
+    }
+
+    render_text_editor(f, area, &draft.editor, block, !draft.posting);
+}
+
+// A multi-line text field, with the terminal cursor in it when `focused`. Lines
+// aren't wrapped, so the cursor maps straight onto the screen.
+fn render_text_editor(f: &mut Frame, area: Rect, editor: &TextEditor, block: Block, focused: bool) {
+    let inner = block.inner(area);
+    let (row, col) = editor.cursor();
+    let scroll = row.saturating_sub(inner.height.saturating_sub(1) as usize);
+    let lines: Vec<Line> = editor
+        .lines()
+        .iter()
+        .map(|line| Line::from(line.clone()))
+        .collect();
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .scroll((scroll as u16, 0)),
+        area,
+    );
+    if focused {
+        let before: String = editor.lines()[row].chars().take(col).collect();
+        f.set_cursor_position((
+            inner.x + Span::from(before).width() as u16,
+            inner.y + (row - scroll) as u16,
+        ));
+    }
+}
+
+fn focus_style(focused: bool) -> Style {
+    if focused {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    }
+}
+
+// checkbox list of a picker; labels in their own color
+fn render_picker(f: &mut Frame, area: Rect, title: &str, picker: &Picker, focused: bool) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title.to_string())
+        .border_style(focus_style(focused));
+    if picker.items().is_empty() {
+        f.render_widget(
+            Paragraph::new("none")
+                .style(Style::default().fg(Color::Gray))
+                .block(block),
+            area,
+        );
+        return;
+    }
+
+    let lines: Vec<Line> = picker
+        .items()
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let mark = match (picker.is_multi(), item.chosen) {
+                (true, true) => "[x] ",
+                (true, false) => "[ ] ",
+                (false, true) => "(•) ",
+                (false, false) => "( ) ",
+            };
+            let name = match &item.color {
+                Some(color) => {
+                    Span::from(item.name.clone()).style(Style::default().fg(hex_to_color(color)))
+                }
+                None => Span::from(item.name.clone()),
+            };
+            let line = Line::from(vec![Span::from(mark), name]);
+            if focused && i == picker.cursor() {
+                line.style(Style::default().bg(Color::DarkGray))
+            } else {
+                line
+            }
+        })
+        .collect();
+    // keep the cursor in view
+    let height = area.height.saturating_sub(2) as usize;
+    let scroll = (picker.cursor() + 1).saturating_sub(height);
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .scroll((scroll as u16, 0)),
+        area,
+    );
+}
+
+fn render_triage_popup(f: &mut Frame, area: Rect, app: &App) {
+    let Some(triage) = &app.triage else {
+        return;
+    };
+    let area = popup_area(area, 50, 50);
+    let title = format!("{} #{}", triage.action.title(), triage.number);
+    f.render_widget(Clear, area);
+    match loading_placeholder(&triage.state, "choices", &title) {
+        Some(placeholder) => f.render_widget(placeholder, area),
+        None => render_picker(f, area, &title, &triage.picker, true),
+    }
+}
+
+// confirmation of a PR action, with the fields the action takes
+fn render_pull_action(f: &mut Frame, area: Rect, app: &App) {
+    let Some(action) = &app.pull_action else {
+        return;
+    };
+    let fields = action.fields();
+    let area = popup_area(area, 60, if fields.is_empty() { 30 } else { 60 });
+    let mut block = Block::default().borders(Borders::ALL).title(format!(
+        "{} #{}",
+        action.kind.title(),
+        action.number
+    ));
+    if action.sending {
+        block = block
+            .title_bottom(Line::from(" Sending... ").style(Style::default().fg(Color::Yellow)));
+    } else if let Some(error) = &action.error {
+        block = block.title_bottom(
+            Line::from(format!(" {} ", error)).style(Style::default().fg(Color::Red)),
+        );
+    }
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let constraints: Vec<Constraint> = std::iter::once(Constraint::Length(2))
+        .chain(fields.iter().map(|field| match field {
+            PullActionField::Method | PullActionField::DeleteBranch => Constraint::Length(1),
+            PullActionField::Title => Constraint::Length(3),
+            PullActionField::Message => Constraint::Min(3),
+        }))
+        .collect();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(inner);
+
+    f.render_widget(
+        Paragraph::new(action.prompt()).style(Style::default().add_modifier(Modifier::BOLD)),
+        chunks[0],
+    );
+    for (field, &chunk) in fields.iter().zip(chunks.iter().skip(1)) {
+        let focused = action.field == Some(*field) && !action.sending;
+        match field {
+            PullActionField::Method => f.render_widget(
+                Paragraph::new(format!("Method: < {} >", action.method.name()))
+                    .style(focus_style(focused)),
+                chunk,
+            ),
+            PullActionField::Title => {
+                f.render_widget(
+                    Paragraph::new(action.title.clone()).block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title("Commit title")
+                            .border_style(focus_style(focused)),
+                    ),
+                    chunk,
+                );
+                if focused {
+                    f.set_cursor_position((
+                        chunk.x + 1 + Span::from(action.title.clone()).width() as u16,
+                        chunk.y + 1,
+                    ));
+                }
+            }
+            PullActionField::Message => {
+                let title = match action.kind {
+                    PullActionKind::Merge => "Commit message (optional)",
+                    PullActionKind::Approve => "Comment (optional)",
+                    _ => "Comment",
+                };
+                render_text_editor(
+                    f,
+                    chunk,
+                    &action.editor,
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(title)
+                        .border_style(focus_style(focused)),
+                    focused,
+                );
+            }
+            PullActionField::DeleteBranch => {
+                let branch = action
+                    .head
+                    .as_ref()
+                    .map_or("the head branch", |(_, branch)| branch.as_str());
+                let mark = if action.delete_branch { "[x]" } else { "[ ]" };
+                f.render_widget(
+                    Paragraph::new(format!("{} Delete {} after merging", mark, branch))
+                        .style(focus_style(focused)),
+                    chunk,
+                );
+            }
+        }
+    }
+}
+
+fn render_issue_form(f: &mut Frame, area: Rect, app: &App) {
+    let Some(form) = &app.issue_form else {
+        return;
+    };
+    let area = popup_area(area, 80, 80);
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("New issue in {}", form.repo));
+    if form.submitting {
+        block = block
+            .title_bottom(Line::from(" Creating... ").style(Style::default().fg(Color::Yellow)));
+    } else if let Some(error) = &form.error {
+        block = block.title_bottom(
+            Line::from(format!(" {} ", error)).style(Style::default().fg(Color::Red)),
+        );
+    } else if let LoadingState::Error(e) = &form.options_state {
+        block = block.title_bottom(
+            Line::from(format!(" Couldn't load labels and assignees: {} ", e))
+                .style(Style::default().fg(Color::Red)),
+        );
+    }
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(if form.templates.is_empty() { 0 } else { 1 }),
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(8),
+        ])
+        .split(inner);
+
+    if let Some(template) = form.template.and_then(|i| form.templates.get(i)) {
+        f.render_widget(
+            Paragraph::new(format!(
+                "Template: < {} > {}",
+                template.name, template.about
+            ))
+            .style(focus_style(form.field == IssueField::Template)),
+            chunks[0],
+        );
+    } else if !form.templates.is_empty() {
+        f.render_widget(
+            Paragraph::new("Template: < Blank issue >")
+                .style(focus_style(form.field == IssueField::Template)),
+            chunks[0],
+        );
+    }
+
+    let title_focused = form.field == IssueField::Title;
+    f.render_widget(
+        Paragraph::new(form.title.clone()).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Title")
+                .border_style(focus_style(title_focused)),
+        ),
+        chunks[1],
+    );
+    if title_focused && !form.submitting {
+        f.set_cursor_position((
+            chunks[1].x + 1 + Span::from(form.title.clone()).width() as u16,
+            chunks[1].y + 1,
+        ));
+    }
+
+    let body_focused = form.field == IssueField::Body;
+    render_text_editor(
+        f,
+        chunks[2],
+        &form.body,
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Body")
+            .border_style(focus_style(body_focused)),
+        body_focused && !form.submitting,
+    );
+
+    let pickers = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Ratio(1, 3),
+            Constraint::Ratio(1, 3),
+            Constraint::Ratio(1, 3),
+        ])
+        .split(chunks[3]);
+    if form.options_state == LoadingState::Loading {
+        f.render_widget(
+            Paragraph::new("Loading labels, assignees and milestones...")
+                .style(Style::default().fg(Color::Yellow)),
+            chunks[3],
+        );
+        return;
+    }
+    render_picker(
+        f,
+        pickers[0],
+        "Labels",
+        &form.labels,
+        form.field == IssueField::Labels,
+    );
+    render_picker(
+        f,
+        pickers[1],
+        "Assignees",
+        &form.assignees,
+        form.field == IssueField::Assignees,
+    );
+    render_picker(
+        f,
+        pickers[2],
+        "Milestone",
+        &form.milestone,
+        form.field == IssueField::Milestone,
+    );
+}
+
+fn render_filter_popup(f: &mut Frame, area: Rect, app: &App) {
+    let Some(form) = &app.filter_form else {
+        return;
+    };
+    let area = popup_area(area, 50, 50);
+
+    let mut lines: Vec<Line> = form
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let style = if i == form.selected {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::from(format!("{:<10} ", field.label()))
+                    .style(Style::default().fg(Color::Gray)),
+                Span::from(form.value(*field)).style(style),
+                Span::from(if i == form.selected { "_" } else { "" }).style(style),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+    let hint = if form.commits {
+        "Path of a file or directory; author by login or email"
+    } else {
+        "Labels comma separated; milestone by number, * or none"
+    };
+    lines.push(Line::from(hint).style(Style::default().fg(Color::Gray)));
+    if form.prs && form.to_filter().narrows() {
+        // the search index lags behind the PR listing by a little
+        lines.push(
+            Line::from("Narrowed PRs come from search, which may miss the newest")
+                .style(Style::default().fg(Color::Gray)),
+        );
+    }
+
+    let title = if form.commits {
+        "Filter commits"
+    } else if form.prs {
+        "Filter PRs"
+    } else {
+        "Filter issues"
+    };
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+// terminals narrower than this get the unified diff in auto layout
+const SIDE_BY_SIDE_WIDTH: u16 = 160;
+const ADDED_BG: Color = Color::Rgb(24, 56, 32);
+const REMOVED_BG: Color = Color::Rgb(72, 28, 30);
+
+fn render_diff(f: &mut Frame, area: Rect, app: &mut App) {
+    let pending = app.pending_comments().to_vec();
+    let Some(diff) = &mut app.diff else {
+        return;
+    };
+    let title = match &diff.sha {
+        Some(sha) => format!("Commit {}", sha.get(..7).unwrap_or(sha)),
+        None => format!("PR #{} diff", diff.number),
+    };
+    if let Some(placeholder) = loading_placeholder(&diff.state, "diff", &title) {
+        f.render_widget(placeholder, area);
+        return;
+    }
+
+    // a commit's message, author and stats go above its files
+    let area = match &diff.commit {
+        Some(commit) => {
+            let lines = commit_lines(commit, diff.files.len());
+            let height = (lines.len() as u16 + 2).min(area.height / 3);
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(height), Constraint::Min(0)])
+                .split(area);
+            let paragraph = Paragraph::new(lines)
+                .block(Block::default().borders(Borders::ALL).title(title.clone()))
+                .wrap(Wrap { trim: false });
+            f.render_widget(paragraph, chunks[0]);
+            chunks[1]
+        }
+        None => area,
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(25), Constraint::Min(0)])
+        .split(area);
+
+    // file list: status letter, name and line counts
+    let files: Vec<Line> = diff
+        .files
+        .iter()
+        .enumerate()
+        .map(|(i, diff_file)| {
+            let file = &diff_file.file;
+            let (status, color) = match file.status.as_str() {
+                "added" => ("A", Color::Green),
+                "removed" => ("D", Color::Red),
+                "renamed" => ("R", Color::Magenta),
+                "copied" => ("C", Color::Magenta),
+                _ => ("M", Color::Yellow),
+            };
+            let line = Line::from(vec![
+                Span::from(format!("{} ", status)).style(Style::default().fg(color)),
+                Span::from(format!("{} ", file.filename)),
+                Span::from(format!("+{}", file.additions)).style(Style::default().fg(Color::Green)),
+                Span::from(" "),
+                Span::from(format!("-{}", file.deletions)).style(Style::default().fg(Color::Red)),
+            ]);
+            if i == diff.selected {
+                line.style(
+                    Style::default()
+                        .bg(Color::DarkGray)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                line
+            }
+        })
+        .collect();
+    let file_list = Paragraph::new(files)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Files ({})", diff.files.len())),
+        )
+        // keep the selected file in view
+        .scroll((
+            diff.selected
+                .saturating_sub(chunks[0].height.saturating_sub(3) as usize) as u16,
+            0,
+        ));
+    f.render_widget(file_list, chunks[0]);
+
+    diff.side_by_side = match diff.layout {
+        DiffLayout::Auto => area.width >= SIDE_BY_SIDE_WIDTH,
+        DiffLayout::Unified => false,
+        DiffLayout::SideBySide => true,
+    };
+    // borrows only the files, leaving the scroll free to adjust below
+    let Some(file) = diff.files.get(diff.selected) else {
+        let empty = Paragraph::new("No files changed")
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(empty, chunks[1]);
+        return;
+    };
+
+    let name = match &file.file.previous_filename {
+        Some(previous) => format!("{} → {}", previous, file.file.filename),
+        None => file.file.filename.clone(),
+    };
+    let block = Block::default().borders(Borders::ALL).title(name);
+    if file.file.patch.is_none() {
+        let paragraph = Paragraph::new("Binary file or diff too large to show")
+            .style(Style::default().fg(Color::Gray))
+            .block(block);
+        f.render_widget(paragraph, chunks[1]);
+        return;
+    }
+
+    let mut block = block;
+    if !pending.is_empty() {
+        block = block.title_bottom(
+            Line::from(format!(
+                " {} pending comment(s) · S: submit review ",
+                pending.len()
+            ))
+            .style(Style::default().fg(Color::Yellow)),
+        );
+    }
+    let inner = block.inner(chunks[1]);
+    f.render_widget(block, chunks[1]);
+
+    let (notes, outdated) = diff_notes(file, &diff.threads, &pending, diff.side_by_side);
+    let height = inner.height as usize;
+    // scroll down until the cursor fits under the threads shown above it
+    while diff.scroll < diff.cursor
+        && (diff.scroll..=diff.cursor)
+            .map(|row| 1 + notes.get(&row).map_or(0, Vec::len))
+            .sum::<usize>()
+            > height
+    {
+        diff.scroll += 1;
+    }
+
+    let selected = match diff.range_start {
+        Some(start) => start.min(diff.cursor)..=start.max(diff.cursor),
+        None => diff.cursor..=diff.cursor,
+    };
+    // only the rows on screen are built
+    let half = (inner.width as usize).saturating_sub(1) / 2;
+    let mut lines: Vec<Line> = Vec::new();
+    for row in diff.scroll..file.row_count(diff.side_by_side) {
+        if lines.len() >= height {
+            break;
+        }
+        let gutter = if row == diff.cursor {
+            Span::from("▶").style(Style::default().fg(Color::Yellow))
+        } else if selected.contains(&row) {
+            Span::from("┃").style(Style::default().fg(Color::Yellow))
+        } else {
+            Span::from(" ")
+        };
+        let line = if diff.side_by_side {
+            let (old, new) = file.pairs[row];
+            let blank = Span::from(" ");
+            let (old_gutter, new_gutter) = if diff.old_side {
+                (gutter, blank)
+            } else {
+                (blank, gutter)
+            };
+            let mut spans = vec![old_gutter];
+            spans.extend(fit_line(side_line(file, old, true), half.saturating_sub(1)));
+            spans.push(Span::from("│").style(Style::default().fg(Color::DarkGray)));
+            spans.push(new_gutter);
+            spans.extend(fit_line(
+                side_line(file, new, false),
+                half.saturating_sub(1),
+            ));
+            Line::from(spans)
+        } else {
+            let mut spans = vec![gutter];
+            spans.extend(fit_line(
+                unified_line(&file.lines[row]),
+                (inner.width as usize).saturating_sub(1),
+            ));
+            Line::from(spans)
+        };
+        lines.push(line);
+        if let Some(row_notes) = notes.get(&row) {
+            lines.extend(row_notes.iter().cloned());
+        }
+    }
+    if !outdated.is_empty() && lines.len() < height {
+        lines.push(
+            Line::from("Comments on lines no longer in the diff")
+                .style(Style::default().fg(Color::Gray)),
+        );
+        lines.extend(outdated);
+    }
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+// Review threads and pending comments of a file, grouped by the row they go
+// under; those whose lines are no longer in the diff come separately
+fn diff_notes(
+    file: &DiffFile,
+    threads: &[ReviewThread],
+    pending: &[NewReviewComment],
+    side_by_side: bool,
+) -> (HashMap<usize, Vec<Line<'static>>>, Vec<Line<'static>>) {
+    let mut notes: HashMap<usize, Vec<Line<'static>>> = HashMap::new();
+    let mut outdated = Vec::new();
+    let path = &file.file.filename;
+
+    for thread in threads.iter().filter(|thread| &thread.path == path) {
+        let lines = thread_lines(thread);
+        match thread
+            .line
+            .and_then(|line| file.row_of(&thread.side, line, side_by_side))
+        {
+            Some(row) => notes.entry(row).or_default().extend(lines),
+            None => outdated.extend(lines),
+        }
+    }
+    for comment in pending.iter().filter(|comment| &comment.path == path) {
+        let lines = note_lines(&comment.body, "pending", Style::default().fg(Color::Yellow));
+        match file.row_of(&comment.side, comment.line, side_by_side) {
+            Some(row) => notes.entry(row).or_default().extend(lines),
+            None => outdated.extend(lines),
+        }
+    }
+    (notes, outdated)
+}
+
+// a thread's comments; resolved ones fold into their first line
+fn thread_lines(thread: &ReviewThread) -> Vec<Line<'static>> {
+    let author = |i: usize| {
+        thread.comments[i]
+            .user
+            .as_ref()
+            .map_or("ghost", |u| u.login.as_str())
+            .to_string()
+    };
+    if thread.comments.is_empty() {
+        return Vec::new();
+    }
+    if thread.resolved {
+        let mut line = Line::from(vec![
+            Span::from("       ┃ ✓ resolved · ").style(Style::default().fg(Color::Green)),
+            Span::from(format!("{}: ", author(0))),
+        ]);
+        line.spans
+            .extend(markdown::summary(&thread.comments[0].body).spans);
+        return vec![line.style(Style::default().fg(Color::Gray))];
+    }
+    (0..thread.comments.len())
+        .flat_map(|i| {
+            note_lines(
+                &thread.comments[i].body,
+                &author(i),
+                Style::default().fg(Color::Cyan),
+            )
+        })
+        .collect()
+}
+
+// `       ┃ who:` over the comment's Markdown, indented behind the bar
+fn note_lines(body: &str, who: &str, style: Style) -> Vec<Line<'static>> {
+    let bar = || Span::from("       ┃ ").style(style);
+    let mut lines = vec![Line::from(vec![
+        bar(),
+        Span::from(format!("{}:", who)).style(style.add_modifier(Modifier::BOLD)),
+    ])];
+    for line in markdown::render_cached(body).lines {
+        let mut spans = vec![bar(), Span::from("  ")];
+        spans.extend(line.spans);
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+// the spans of `line` cut or padded to `width` columns, with the line's own
+// style (the diff background) carried over onto them
+fn fit_line(line: Line<'static>, width: usize) -> Vec<Span<'static>> {
+    let mut left = width;
+    let mut spans = Vec::new();
+    for span in line.spans {
+        if left == 0 {
+            break;
+        }
+        let text: String = span.content.chars().take(left).collect();
+        left -= text.chars().count();
+        spans.push(Span::styled(text, line.style.patch(span.style)));
+    }
+    spans.push(Span::styled(" ".repeat(left), line.style));
+    spans
+}
+
+fn line_number(number: Option<u32>) -> Span<'static> {
+    let text = number.map_or(String::new(), |n| n.to_string());
+    Span::from(format!("{:>5} ", text)).style(Style::default().fg(Color::DarkGray))
+}
+
+// marker and background of a changed line
+fn change_style(kind: LineKind) -> (&'static str, Style) {
+    match kind {
+        LineKind::Added => ("+", Style::default().bg(ADDED_BG)),
+        LineKind::Removed => ("-", Style::default().bg(REMOVED_BG)),
+        LineKind::Context | LineKind::Hunk => (" ", Style::default()),
+    }
+}
+
+fn hunk_line(line: &DiffLine) -> Line<'static> {
+    Line::from(line.text.clone()).style(Style::default().fg(Color::Cyan))
+}
+
+// `  12    13 + code`
+fn unified_line(line: &DiffLine) -> Line<'static> {
+    if line.kind == LineKind::Hunk {
+        return hunk_line(line);
+    }
+    let (marker, style) = change_style(line.kind);
+    let mut spans = vec![
+        line_number(line.old_line),
+        line_number(line.new_line),
+        Span::from(format!("{} ", marker)),
+    ];
+    spans.extend(line.spans.iter().cloned());
+    Line::from(spans).style(style)
+}
+
+// full message of a commit under who wrote it, when and how much it changed
+fn commit_lines(commit: &Commit, files: usize) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(vec![
+        Span::from(commit.sha.clone()).style(Style::default().fg(Color::Yellow)),
+        Span::from(format!(
+            "  {} on {}",
+            commit.author_name(),
+            format_timestamp(commit.date())
+        )),
+    ])];
+    if let Some(stats) = &commit.stats {
+        lines.push(Line::from(vec![
+            Span::from(format!("+{}", stats.additions)).style(Style::default().fg(Color::Green)),
+            Span::from(" "),
+            Span::from(format!("-{}", stats.deletions)).style(Style::default().fg(Color::Red)),
+            Span::from(format!(" in {} file(s)", files)),
+        ]));
+    }
+    lines.push(Line::from(""));
+    let mut message = commit.commit.message.lines();
+    if let Some(subject) = message.next() {
+        lines.push(
+            Line::from(subject.to_string()).style(Style::default().add_modifier(Modifier::BOLD)),
+        );
+    }
+    lines.extend(message.map(|line| Line::from(line.to_string())));
+    lines
+}
+
+// one side of a side-by-side row; a blank cell where the other side has no counterpart
+fn side_line(file: &DiffFile, index: Option<usize>, old_side: bool) -> Line<'static> {
+    let Some(line) = index.map(|i| &file.lines[i]) else {
+        return Line::from("");
+    };
+    if line.kind == LineKind::Hunk {
+        return hunk_line(line);
+    }
+    let (marker, style) = change_style(line.kind);
+    let number = if old_side {
+        line.old_line
+    } else {
+        line.new_line
+    };
+    let mut spans = vec![line_number(number), Span::from(format!("{} ", marker))];
+    spans.extend(line.spans.iter().cloned());
+    Line::from(spans).style(style)
+}
+
+// how long a success notice stays in the footer; errors stay until the next one
+const NOTICE_TIME: Duration = Duration::from_secs(5);
+
 fn render_footer(f: &mut Frame, area: Rect, app: &App) {
     let help_text = match app.mode {
         AppMode::RepoList => "↑/↓: Navigate | Enter: View Details | /: Search | q: Quit",
+        AppMode::RepoDetail
+            if app.detail_mode == RepoDetailState::Code
+                && app.code.as_ref().is_some_and(|code| code.viewing) =>
+        {
+            "↑/↓ or j/k: Line | g/G: Top/bottom | y: Copy permalink | b: Branch/tag/commit | Esc/h: Back to the tree | Tab: Details"
+        }
+        AppMode::RepoDetail if app.detail_mode == RepoDetailState::Code => {
+            "↑/↓ or j/k: Move | Enter: Open/close | l/h: Expand/collapse | b: Branch/tag/commit | y: Copy permalink | Tab: Details | Esc: Back"
+        }
+        AppMode::RepoDetail
+            if app.detail_mode == RepoDetailState::Commits
+                && app.commits.as_ref().is_some_and(|log| log.pr.is_some()) =>
+        {
+            "↑/↓ or j/k: Move | Enter: Message and diff | Tab: Details | Esc: Back to the PR"
+        }
+        AppMode::RepoDetail if app.detail_mode == RepoDetailState::Commits => {
+            "↑/↓ or j/k: Move | Enter: Message and diff | f: Filter by path/author | b: Branch/tag/commit | Tab: Details | Esc: Back"
+        }
+        AppMode::RefPicker => "Type to filter | ↑/↓: Move | Enter: Show | Esc: Cancel",
         AppMode::RepoDetail => {
-            "↑/↓: Scroll | Esc: Back | q: Quit | Tab: toggle between repo issues, PRs and details"
+            "↑/↓: Scroll | Esc: Back | q: Quit | Tab: toggle between repo issues, PRs, details, code and commits | Enter: open | p: show/hide PRs in issues | f: filter | d: PR diff | C: PR commits | n: new issue | x/l/a/m/L: close, label, assign, milestone, lock issue"
         }
         AppMode::Search => "Type to search | Enter: Execute | Esc: Cancel",
-        AppMode::IssuePopUp => "↑/↓: Scroll | Esc: Back | q: Quit",
+        AppMode::IssuePopUp => {
+            "↑/↓ or j/k: Scroll | c: Comment | x: Close/reopen | l: Labels | a: Assignees | m: Milestone | L: Lock/unlock | Esc: Back"
+        }
+        AppMode::TriagePopUp => "↑/↓ or j/k: Move | Space: Pick | Enter: Apply | Esc: Cancel",
+        AppMode::PRPopUp => {
+            "↑/↓ or j/k: Scroll | c: Comment | d: Diff | C: Commits | a/r/v: Approve, request changes, review | M: Merge | A: Auto-merge | w: Draft/ready | D: Delete branch | Esc: Back"
+        }
+        AppMode::PullActionPopUp => {
+            "Enter: Confirm | Tab/Shift+Tab: Field | ←/→: Method | Space: Toggle | Ctrl+S: Confirm from a text field | Ctrl+E: $EDITOR | Esc: Back"
+        }
+        AppMode::IssueForm => {
+            "Tab/Shift+Tab: Field | ←/→: Template | ↑/↓ Space: Pick | Ctrl+E: Body in $EDITOR | Ctrl+S: Create | Esc: Back (keeps the draft)"
+        }
+        AppMode::CommentEditor if app.draft.as_ref().is_some_and(|d| d.line.is_some()) => {
+            "Ctrl+S: Add to the pending review | Ctrl+P: Preview/edit | Ctrl+E: Open in $EDITOR | Esc: Back (keeps the draft)"
+        }
+        AppMode::CommentEditor => {
+            "Ctrl+S: Send | Ctrl+P: Preview/edit | Ctrl+E: Open in $EDITOR | Esc: Back (keeps the draft)"
+        }
+        AppMode::DiffView if app.diff.as_ref().is_some_and(|diff| diff.sha.is_some()) => {
+            "j/k: Line | Tab/Shift+Tab: File | ]/[: Hunk | s: Layout | h/l: Old/new side | Esc: Back"
+        }
+        AppMode::DiffView => {
+            "j/k: Line | Tab/Shift+Tab: File | ]/[: Hunk | s: Layout | h/l: Old/new side | v: Select range | c: Comment | x: Drop pending | r: Resolve thread | S: Submit review | Esc: Back"
+        }
+        AppMode::FilterPopUp => {
+            "↑/↓: Field | ←/→/Space: Change | Type to edit | Enter: Apply | Esc: Cancel"
+        }
     };
 
+    let mut block = Block::default().borders(Borders::ALL);
+    if let Some(notice) = app
+        .notice
+        .as_ref()
+        .filter(|n| n.error || n.since.elapsed() < NOTICE_TIME)
+    {
+        let color = if notice.error {
+            Color::Red
+        } else {
+            Color::Green
+        };
+        block = block.title_bottom(
+            Line::from(format!(" {} ", notice.text)).style(Style::default().fg(color)),
+        );
+    }
+    if let Some(backoff) = app.api_status().backoff {
+        block = block.title_bottom(
+            Line::from(format!(
+                " {}, retrying in {} ",
+                backoff.reason,
+                format_duration(backoff.remaining())
+            ))
+            .style(Style::default().fg(Color::Yellow))
+            .right_aligned(),
+        );
+    }
+
     let footer = Paragraph::new(help_text)
         .style(Style::default().fg(Color::Gray))
-        .block(Block::default().borders(Borders::ALL));
+        .block(block);
 
     f.render_widget(footer, area);
 }
@@ -5,14 +5,14 @@ use ratatui::style::Color;
 pub fn hex_to_color(hex: &str) -> Color {
     // parse
     let hex = hex.trim_start_matches('#');
-    if hex.len() == 6 {
-        if let (Ok(r), Ok(g), Ok(b)) = (
+    if hex.len() == 6
+        && let (Ok(r), Ok(g), Ok(b)) = (
             u8::from_str_radix(&hex[0..2], 16),
             u8::from_str_radix(&hex[2..4], 16),
             u8::from_str_radix(&hex[4..6], 16),
-        ) {
-            return Color::Rgb(r, g, b);
-        }
+        )
+    {
+        return Color::Rgb(r, g, b);
     }
     // fallback
     Color::White
@@ -26,3 +26,11 @@ pub fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     let [area] = horizontal.areas(area);
     area
 }
+
+// `2024-01-02T03:04:05Z` -> `2024-01-02 03:04`
+pub fn format_timestamp(timestamp: &str) -> String {
+    match timestamp.get(..16) {
+        Some(prefix) => prefix.replacen('T', " ", 1),
+        None => timestamp.to_string(),
+    }
+}